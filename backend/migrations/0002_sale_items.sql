@@ -0,0 +1,26 @@
+-- Sale line items: a sale can contain many products
+CREATE TABLE sale_items (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    sale_id UUID NOT NULL REFERENCES sales(id) ON DELETE CASCADE,
+    product_id UUID REFERENCES products(id) ON DELETE SET NULL,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price DECIMAL(10, 2) NOT NULL,
+    discount DECIMAL(10, 2) NOT NULL DEFAULT 0.00,
+    line_total DECIMAL(10, 2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Stock reserved by pending sales
+ALTER TABLE products ADD COLUMN reserved_quantity INTEGER NOT NULL DEFAULT 0;
+
+-- Move existing single-product sales into line items
+INSERT INTO sale_items (sale_id, product_id, quantity, unit_price, line_total)
+SELECT id, product_id, quantity, amount / quantity, amount
+FROM sales
+WHERE product_id IS NOT NULL;
+
+ALTER TABLE sales DROP COLUMN product_id;
+ALTER TABLE sales DROP COLUMN quantity;
+
+CREATE INDEX idx_sale_items_sale_id ON sale_items(sale_id);
+CREATE INDEX idx_sale_items_product_id ON sale_items(product_id);
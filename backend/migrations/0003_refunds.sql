@@ -0,0 +1,31 @@
+-- Partial refunds
+ALTER TYPE sale_status ADD VALUE IF NOT EXISTS 'partially_refunded';
+CREATE TYPE refund_reason AS ENUM ('customer_request', 'duplicate', 'defective', 'fraudulent', 'other');
+
+ALTER TABLE sales ADD COLUMN refunded_amount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
+ALTER TABLE sales ADD CONSTRAINT chk_sales_refunded_amount CHECK (refunded_amount <= amount);
+
+ALTER TABLE sale_items ADD COLUMN refunded_quantity INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE sale_items ADD COLUMN refunded_amount DECIMAL(10, 2) NOT NULL DEFAULT 0.00;
+
+-- Refunds table
+CREATE TABLE refunds (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    sale_id UUID NOT NULL REFERENCES sales(id) ON DELETE CASCADE,
+    amount DECIMAL(10, 2) NOT NULL CHECK (amount > 0),
+    reason refund_reason NOT NULL,
+    note TEXT,
+    restocked BOOLEAN DEFAULT false,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE TABLE refund_lines (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    refund_id UUID NOT NULL REFERENCES refunds(id) ON DELETE CASCADE,
+    product_id UUID REFERENCES products(id) ON DELETE SET NULL,
+    quantity INTEGER NOT NULL DEFAULT 0,
+    amount DECIMAL(10, 2) NOT NULL
+);
+
+CREATE INDEX idx_refunds_sale_id ON refunds(sale_id);
+CREATE INDEX idx_refund_lines_refund_id ON refund_lines(refund_id);
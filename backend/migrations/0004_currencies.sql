@@ -0,0 +1,16 @@
+-- Per-sale currency with the exchange rate used at sale time
+ALTER TABLE sales ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'BRL';
+ALTER TABLE sales ADD COLUMN exchange_rate DECIMAL(18, 8) NOT NULL DEFAULT 1;
+
+ALTER TABLE refunds ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'BRL';
+
+ALTER TABLE products ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'BRL';
+ALTER TABLE customers ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'BRL';
+
+-- Exchange rates to the base currency (1 unit of `currency` = `rate` units of base)
+CREATE TABLE exchange_rates (
+    currency CHAR(3) NOT NULL,
+    rate DECIMAL(18, 8) NOT NULL CHECK (rate > 0),
+    effective_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    PRIMARY KEY (currency, effective_date)
+);
@@ -0,0 +1,29 @@
+-- Background spreadsheet imports
+CREATE TYPE import_status AS ENUM ('queued', 'running', 'completed', 'failed');
+
+CREATE TABLE import_jobs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    file_name VARCHAR(255) NOT NULL,
+    dry_run BOOLEAN NOT NULL DEFAULT false,
+    status import_status NOT NULL DEFAULT 'queued',
+    mapping JSONB NOT NULL,
+    total_rows INTEGER,
+    processed_rows INTEGER NOT NULL DEFAULT 0,
+    valid_rows INTEGER NOT NULL DEFAULT 0,
+    error_rows INTEGER NOT NULL DEFAULT 0,
+    valid_sales INTEGER NOT NULL DEFAULT 0,
+    sales_created INTEGER NOT NULL DEFAULT 0,
+    message TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE TABLE import_row_errors (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    job_id UUID NOT NULL REFERENCES import_jobs(id) ON DELETE CASCADE,
+    row_number INTEGER NOT NULL,
+    column_name VARCHAR(255),
+    message TEXT NOT NULL
+);
+
+CREATE INDEX idx_import_row_errors_job_id ON import_row_errors(job_id, row_number);
@@ -0,0 +1,18 @@
+-- Stored responses for POST requests sent with an Idempotency-Key header.
+-- Keys are scoped per client ("user:<sub>" or "ip:<addr>"), method and path, so two
+-- clients (or two routes) reusing the same key never see each other's responses
+CREATE TABLE idempotency_keys (
+    client VARCHAR(255) NOT NULL,
+    method VARCHAR(10) NOT NULL,
+    path VARCHAR(1024) NOT NULL,
+    key VARCHAR(255) NOT NULL,
+    fingerprint CHAR(64) NOT NULL,
+    response_status SMALLINT,
+    response_content_type VARCHAR(255),
+    response_body BYTEA,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    PRIMARY KEY (client, method, path, key)
+);
+
+CREATE INDEX idx_idempotency_keys_expires ON idempotency_keys(expires_at);
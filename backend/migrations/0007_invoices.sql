@@ -0,0 +1,34 @@
+-- Gapless invoice numbering per tenant
+CREATE TABLE invoice_sequences (
+    tenant_id VARCHAR(100) PRIMARY KEY,
+    next_value BIGINT NOT NULL DEFAULT 1
+);
+
+-- Invoices keep a snapshot of customer and product data at issue time
+CREATE TABLE invoices (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    tenant_id VARCHAR(100) NOT NULL,
+    number VARCHAR(50) NOT NULL,
+    sale_id UUID NOT NULL UNIQUE REFERENCES sales(id),
+    customer_snapshot JSONB NOT NULL,
+    subtotal DECIMAL(12,2) NOT NULL,
+    discount_total DECIMAL(12,2) NOT NULL,
+    total DECIMAL(12,2) NOT NULL,
+    currency CHAR(3) NOT NULL,
+    payment_method VARCHAR(50),
+    sale_date TIMESTAMP WITH TIME ZONE NOT NULL,
+    issued_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (tenant_id, number)
+);
+
+CREATE TABLE invoice_lines (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    invoice_id UUID NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL,
+    sku VARCHAR(100) NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_price DECIMAL(12,2) NOT NULL,
+    discount DECIMAL(12,2) NOT NULL DEFAULT 0,
+    line_total DECIMAL(12,2) NOT NULL
+);
@@ -0,0 +1,16 @@
+-- CPF (11 digits) or CNPJ (14 characters, alphanumeric since 2026), stored without punctuation
+ALTER TABLE customers ADD COLUMN tax_id VARCHAR(14);
+ALTER TABLE products ADD COLUMN ncm CHAR(8);
+
+-- NF-e documents generated for sales (unsigned, not transmitted)
+CREATE TABLE nfe_documents (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    sale_id UUID NOT NULL UNIQUE REFERENCES sales(id),
+    series INTEGER NOT NULL,
+    number BIGINT NOT NULL,
+    access_key CHAR(44) NOT NULL UNIQUE,
+    total DECIMAL(15,2) NOT NULL,
+    xml TEXT NOT NULL,
+    issued_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (series, number)
+);
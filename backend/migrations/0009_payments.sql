@@ -0,0 +1,35 @@
+-- Typed payment methods; unknown historical values become 'other'
+CREATE TYPE payment_method AS ENUM (
+    'credit_card', 'debit_card', 'paypal', 'bank_transfer', 'pix', 'boleto', 'cash', 'other'
+);
+UPDATE sales SET payment_method = 'other'
+WHERE payment_method IS NULL
+   OR payment_method NOT IN ('credit_card', 'debit_card', 'paypal', 'bank_transfer', 'pix', 'boleto', 'cash');
+ALTER TABLE sales ALTER COLUMN payment_method TYPE payment_method USING payment_method::payment_method;
+
+CREATE TYPE payment_intent_status AS ENUM ('pending', 'succeeded', 'failed');
+
+CREATE TABLE payment_intents (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    sale_id UUID NOT NULL REFERENCES sales(id) ON DELETE CASCADE,
+    gateway VARCHAR(50) NOT NULL,
+    method payment_method NOT NULL,
+    amount DECIMAL(15,2) NOT NULL,
+    currency CHAR(3) NOT NULL,
+    status payment_intent_status NOT NULL DEFAULT 'pending',
+    instructions JSONB,
+    failure_reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- At most one open payment per sale
+CREATE UNIQUE INDEX idx_payment_intents_open ON payment_intents(sale_id) WHERE status = 'pending';
+
+-- Webhook events already applied, so redeliveries are ignored
+CREATE TABLE payment_events (
+    id UUID PRIMARY KEY,
+    intent_id UUID NOT NULL REFERENCES payment_intents(id) ON DELETE CASCADE,
+    status payment_intent_status NOT NULL,
+    received_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
@@ -0,0 +1,30 @@
+-- Tax rate table by product category and region; NULL matches any
+CREATE TABLE tax_rules (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    code VARCHAR(20) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    rate DECIMAL(7,4) NOT NULL CHECK (rate >= 0 AND rate < 100),
+    category VARCHAR(100),
+    region VARCHAR(10),
+    compound BOOLEAN NOT NULL DEFAULT FALSE,
+    priority INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (code, category, region)
+);
+
+ALTER TABLE sales ADD COLUMN tax_region VARCHAR(10);
+ALTER TABLE sales ADD COLUMN prices_include_tax BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- line_total is the amount charged, taxes included
+ALTER TABLE sale_items ADD COLUMN tax_amount DECIMAL(15,2) NOT NULL DEFAULT 0;
+
+-- Taxes computed for each sale line at sale creation
+CREATE TABLE sale_item_taxes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    sale_item_id UUID NOT NULL REFERENCES sale_items(id) ON DELETE CASCADE,
+    code VARCHAR(20) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    rate DECIMAL(7,4) NOT NULL,
+    compound BOOLEAN NOT NULL,
+    base DECIMAL(15,2) NOT NULL,
+    amount DECIMAL(15,2) NOT NULL
+);
@@ -0,0 +1,50 @@
+-- Discount rules; promotions without a code are applied automatically
+CREATE TABLE promotions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    code VARCHAR(50) UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('percentage', 'fixed_amount', 'buy_x_get_y', 'tier_upgrade')),
+    percent DECIMAL(5,2) CHECK (percent > 0 AND percent <= 100),
+    amount DECIMAL(15,2) CHECK (amount > 0),
+    currency CHAR(3),
+    buy_quantity INTEGER CHECK (buy_quantity > 0),
+    get_quantity INTEGER CHECK (get_quantity > 0),
+    from_product_id UUID REFERENCES products(id),
+    to_product_id UUID REFERENCES products(id),
+    starts_at TIMESTAMP WITH TIME ZONE,
+    ends_at TIMESTAMP WITH TIME ZONE,
+    max_redemptions INTEGER CHECK (max_redemptions > 0),
+    max_per_customer INTEGER CHECK (max_per_customer > 0),
+    stackable BOOLEAN NOT NULL DEFAULT FALSE,
+    priority INTEGER NOT NULL DEFAULT 0,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CHECK (ends_at IS NULL OR starts_at IS NULL OR ends_at > starts_at)
+);
+
+-- Targeting; a promotion with no targets applies to the whole catalog
+CREATE TABLE promotion_products (
+    promotion_id UUID NOT NULL REFERENCES promotions(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id),
+    PRIMARY KEY (promotion_id, product_id)
+);
+
+CREATE TABLE promotion_categories (
+    promotion_id UUID NOT NULL REFERENCES promotions(id) ON DELETE CASCADE,
+    category VARCHAR(100) NOT NULL,
+    PRIMARY KEY (promotion_id, category)
+);
+
+-- One row per promotion applied to a sale; the discount is already in sale_items.discount
+CREATE TABLE promotion_redemptions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    promotion_id UUID NOT NULL REFERENCES promotions(id),
+    sale_id UUID NOT NULL REFERENCES sales(id) ON DELETE CASCADE,
+    customer_id UUID NOT NULL REFERENCES customers(id),
+    discount DECIMAL(15,2) NOT NULL,
+    currency CHAR(3) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (promotion_id, sale_id)
+);
+
+CREATE INDEX idx_promotion_redemptions_customer ON promotion_redemptions(promotion_id, customer_id);
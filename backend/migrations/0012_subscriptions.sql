@@ -0,0 +1,54 @@
+CREATE TYPE billing_interval AS ENUM ('monthly', 'quarterly', 'yearly');
+CREATE TYPE subscription_status AS ENUM ('trialing', 'active', 'canceled');
+
+-- unit_price is the monthly price locked at signup or at the last plan change
+CREATE TABLE subscriptions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    customer_id UUID NOT NULL REFERENCES customers(id),
+    product_id UUID NOT NULL REFERENCES products(id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price DECIMAL(15,2) NOT NULL,
+    currency CHAR(3) NOT NULL,
+    billing_interval billing_interval NOT NULL,
+    payment_method payment_method NOT NULL,
+    status subscription_status NOT NULL,
+    trial_ends_at TIMESTAMP WITH TIME ZONE,
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    current_period_start TIMESTAMP WITH TIME ZONE NOT NULL,
+    current_period_end TIMESTAMP WITH TIME ZONE NOT NULL,
+    cancel_at_period_end BOOLEAN NOT NULL DEFAULT FALSE,
+    canceled_at TIMESTAMP WITH TIME ZONE,
+    ended_at TIMESTAMP WITH TIME ZONE,
+    -- Downgrade credit deducted from the next renewal
+    credit DECIMAL(15,2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_subscriptions_renewal ON subscriptions(current_period_end) WHERE status <> 'canceled';
+
+-- Renewal and proration sales point back to their subscription
+ALTER TABLE sales ADD COLUMN subscription_id UUID REFERENCES subscriptions(id);
+
+CREATE TABLE subscription_changes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    subscription_id UUID NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    from_product_id UUID NOT NULL REFERENCES products(id),
+    to_product_id UUID NOT NULL REFERENCES products(id),
+    from_quantity INTEGER NOT NULL,
+    to_quantity INTEGER NOT NULL,
+    proration DECIMAL(15,2) NOT NULL,
+    sale_id UUID REFERENCES sales(id),
+    changed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- MRR deltas in the base currency; summing up to a date gives the MRR at that date
+CREATE TABLE mrr_movements (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    subscription_id UUID NOT NULL REFERENCES subscriptions(id) ON DELETE CASCADE,
+    customer_id UUID NOT NULL REFERENCES customers(id),
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('new', 'expansion', 'contraction', 'churn')),
+    amount DECIMAL(15,2) NOT NULL,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX idx_mrr_movements_occurred_at ON mrr_movements(occurred_at);
@@ -0,0 +1,41 @@
+CREATE TYPE pipeline_stage AS ENUM ('lead', 'qualified', 'proposal', 'negotiation', 'won', 'lost');
+
+-- Quotes keep negotiated prices without taxes; taxes are computed when converted into a sale
+CREATE TABLE quotes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    number VARCHAR(20) NOT NULL UNIQUE,
+    customer_id UUID NOT NULL REFERENCES customers(id),
+    rep_id UUID NOT NULL REFERENCES users(id),
+    amount DECIMAL(15,2) NOT NULL,
+    currency CHAR(3) NOT NULL,
+    stage pipeline_stage NOT NULL DEFAULT 'lead',
+    probability DECIMAL(5,2) NOT NULL CHECK (probability >= 0 AND probability <= 100),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    notes TEXT,
+    lost_reason TEXT,
+    sale_id UUID REFERENCES sales(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_quotes_stage ON quotes(stage);
+CREATE INDEX idx_quotes_rep ON quotes(rep_id);
+
+CREATE TABLE quote_items (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    quote_id UUID NOT NULL REFERENCES quotes(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price DECIMAL(15,2) NOT NULL,
+    discount DECIMAL(15,2) NOT NULL DEFAULT 0,
+    line_total DECIMAL(15,2) NOT NULL
+);
+
+-- Stage history, used to rebuild the pipeline at past dates
+CREATE TABLE quote_stage_changes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    quote_id UUID NOT NULL REFERENCES quotes(id) ON DELETE CASCADE,
+    stage pipeline_stage NOT NULL,
+    probability DECIMAL(5,2) NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
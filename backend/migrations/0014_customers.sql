@@ -0,0 +1,16 @@
+-- Email identifies the customer (sales import, duplicate checks); compare case-insensitively
+UPDATE customers SET email = LOWER(TRIM(email)) WHERE email IS NOT NULL;
+CREATE UNIQUE INDEX idx_customers_email ON customers(LOWER(email));
+
+ALTER TABLE customers
+    ADD CONSTRAINT customers_status_check CHECK (status IN ('active', 'inactive', 'pending'));
+
+CREATE INDEX idx_customers_segment ON customers(segment);
+CREATE INDEX idx_customers_country_city ON customers(country, city);
+
+UPDATE customers SET address = 'Av. Paulista, 1000', city = 'São Paulo', country = 'Brasil', segment = 'Enterprise'
+WHERE email = 'contact@techcorp.com';
+UPDATE customers SET address = 'Rua XV de Novembro, 250', city = 'Curitiba', country = 'Brasil', segment = 'SMB'
+WHERE email = 'info@startupxyz.com';
+UPDATE customers SET address = '221 Baker Street', city = 'London', country = 'United Kingdom', segment = 'Enterprise'
+WHERE email = 'sales@globalsolutions.com';
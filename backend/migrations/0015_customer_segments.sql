@@ -0,0 +1,25 @@
+-- RFM segment definitions, evaluated in position order; the first match wins
+CREATE TABLE segment_definitions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    position INTEGER NOT NULL UNIQUE,
+    name VARCHAR(50) NOT NULL UNIQUE,
+    description TEXT,
+    recency_min SMALLINT NOT NULL DEFAULT 1 CHECK (recency_min BETWEEN 1 AND 5),
+    recency_max SMALLINT NOT NULL DEFAULT 5 CHECK (recency_max BETWEEN 1 AND 5),
+    frequency_min SMALLINT NOT NULL DEFAULT 1 CHECK (frequency_min BETWEEN 1 AND 5),
+    frequency_max SMALLINT NOT NULL DEFAULT 5 CHECK (frequency_max BETWEEN 1 AND 5),
+    monetary_min SMALLINT NOT NULL DEFAULT 1 CHECK (monetary_min BETWEEN 1 AND 5),
+    monetary_max SMALLINT NOT NULL DEFAULT 5 CHECK (monetary_max BETWEEN 1 AND 5),
+    CHECK (recency_min <= recency_max AND frequency_min <= frequency_max AND monetary_min <= monetary_max)
+);
+
+INSERT INTO segment_definitions (position, name, description, recency_min, recency_max, frequency_min, frequency_max, monetary_min, monetary_max) VALUES
+(1, 'Champions', 'Compraram há pouco, com frequência e alto valor', 4, 5, 4, 5, 4, 5),
+(2, 'Loyal Customers', 'Compram com regularidade', 3, 5, 3, 5, 1, 5),
+(3, 'New Customers', 'Primeira compra recente', 4, 5, 1, 1, 1, 5),
+(4, 'Potential Loyalists', 'Recentes, ainda com poucas compras', 4, 5, 1, 3, 1, 5),
+(5, 'Cannot Lose Them', 'Grandes clientes que pararam de comprar', 1, 1, 4, 5, 4, 5),
+(6, 'At Risk', 'Compravam com frequência, mas não voltam há tempo', 1, 2, 3, 5, 1, 5),
+(7, 'Need Attention', 'Recência média e poucas compras', 3, 3, 1, 2, 1, 5),
+(8, 'Hibernating', 'Poucas compras e sem comprar há muito tempo', 1, 2, 1, 2, 1, 5);
+
@@ -0,0 +1,39 @@
+-- Historical lifetime value comes from net captured sales in the base currency, not seeded figures
+UPDATE customers c SET lifetime_value = COALESCE((
+    SELECT SUM(ROUND((s.amount - s.refunded_amount) * s.exchange_rate, 2))
+    FROM sales s
+    WHERE s.customer_id = c.id AND s.status IN ('completed', 'partially_refunded', 'refunded')
+), 0);
+ALTER TABLE customers ALTER COLUMN lifetime_value TYPE DECIMAL(15, 2);
+
+-- Predicted value over the model horizon (BG/NBD purchases x Gamma-Gamma order value)
+ALTER TABLE customers ADD COLUMN predicted_clv DECIMAL(15, 2);
+ALTER TABLE customers ADD COLUMN predicted_clv_lower DECIMAL(15, 2);
+ALTER TABLE customers ADD COLUMN predicted_clv_upper DECIMAL(15, 2);
+ALTER TABLE customers ADD COLUMN expected_purchases DECIMAL(10, 2);
+ALTER TABLE customers ADD COLUMN expected_order_value DECIMAL(15, 2);
+ALTER TABLE customers ADD COLUMN probability_alive DECIMAL(5, 2);
+ALTER TABLE customers ADD COLUMN clv_computed_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_customers_predicted_clv ON customers(predicted_clv);
+
+-- One row per refresh, keeping fitted parameters for auditing
+CREATE TABLE clv_models (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    computed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    currency CHAR(3) NOT NULL,
+    horizon_months INTEGER NOT NULL,
+    confidence INTEGER NOT NULL,
+    bootstrap_samples INTEGER NOT NULL,
+    customers_fitted INTEGER NOT NULL,
+    repeat_customers INTEGER NOT NULL,
+    bg_nbd_r DOUBLE PRECISION,
+    bg_nbd_alpha DOUBLE PRECISION,
+    bg_nbd_a DOUBLE PRECISION,
+    bg_nbd_b DOUBLE PRECISION,
+    gamma_gamma_p DOUBLE PRECISION,
+    gamma_gamma_q DOUBLE PRECISION,
+    gamma_gamma_gamma DOUBLE PRECISION,
+    total_lifetime_value DECIMAL(15, 2) NOT NULL,
+    total_predicted_value DECIMAL(15, 2) NOT NULL
+);
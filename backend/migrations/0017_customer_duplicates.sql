@@ -0,0 +1,44 @@
+CREATE TABLE customer_notes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    author VARCHAR(255),
+    body TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_customer_notes_customer ON customer_notes(customer_id);
+
+CREATE TYPE duplicate_status AS ENUM ('pending', 'dismissed', 'merged');
+
+-- Review queue of likely duplicate pairs; reviewed pairs are never re-queued
+CREATE TABLE customer_duplicates (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    customer_id UUID NOT NULL,
+    duplicate_id UUID NOT NULL,
+    score DECIMAL(5, 2) NOT NULL CHECK (score >= 0 AND score <= 100),
+    reasons TEXT[] NOT NULL DEFAULT '{}',
+    status duplicate_status NOT NULL DEFAULT 'pending',
+    detected_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    reviewed_at TIMESTAMP WITH TIME ZONE,
+    CHECK (customer_id <> duplicate_id)
+);
+
+CREATE UNIQUE INDEX idx_customer_duplicates_pair
+    ON customer_duplicates(LEAST(customer_id, duplicate_id), GREATEST(customer_id, duplicate_id));
+CREATE INDEX idx_customer_duplicates_status ON customer_duplicates(status, score DESC);
+
+-- Merge audit trail; snapshots and moved record ids allow the merge to be undone
+CREATE TABLE customer_merges (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    survivor_id UUID NOT NULL,
+    merged_id UUID NOT NULL,
+    survivor_before JSONB NOT NULL,
+    merged_customer JSONB NOT NULL,
+    filled_fields TEXT[] NOT NULL DEFAULT '{}',
+    reassigned JSONB NOT NULL,
+    merged_by VARCHAR(255),
+    merged_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    undone_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_customer_merges_survivor ON customer_merges(survivor_id);
@@ -0,0 +1,13 @@
+-- Customer status history; the timeline reads it together with sales, refunds, payments,
+-- subscriptions, quotes, notes and merges
+CREATE TABLE customer_status_changes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    customer_id UUID NOT NULL REFERENCES customers(id) ON DELETE CASCADE,
+    from_status VARCHAR(50) NOT NULL,
+    to_status VARCHAR(50) NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_customer_status_changes_customer ON customer_status_changes(customer_id, changed_at);
+CREATE INDEX idx_sales_customer_created ON sales(customer_id, created_at);
+CREATE INDEX idx_customer_notes_customer_created ON customer_notes(customer_id, created_at);
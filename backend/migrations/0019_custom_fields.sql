@@ -0,0 +1,33 @@
+-- Tenant-defined custom field schemas for customers and products
+CREATE TABLE custom_field_definitions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    entity VARCHAR(20) NOT NULL CHECK (entity IN ('customer', 'product')),
+    key VARCHAR(40) NOT NULL CHECK (key ~ '^[a-z][a-z0-9_]*$'),
+    label VARCHAR(255) NOT NULL,
+    field_type VARCHAR(20) NOT NULL CHECK (field_type IN ('text', 'number', 'date', 'enum', 'boolean')),
+    -- Allowed values for enum fields
+    options TEXT[] NOT NULL DEFAULT '{}',
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (entity, key)
+);
+
+-- Values are validated against the schema on write; tags are lowercase and unique per record
+ALTER TABLE customers
+    ADD COLUMN custom_fields JSONB NOT NULL DEFAULT '{}',
+    ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+ALTER TABLE products
+    ADD COLUMN custom_fields JSONB NOT NULL DEFAULT '{}',
+    ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX idx_customers_custom_fields ON customers USING GIN (custom_fields);
+CREATE INDEX idx_customers_tags ON customers USING GIN (tags);
+CREATE INDEX idx_products_custom_fields ON products USING GIN (custom_fields);
+CREATE INDEX idx_products_tags ON products USING GIN (tags);
+
+INSERT INTO custom_field_definitions (entity, key, label, field_type, options) VALUES
+    ('customer', 'industry', 'Industry', 'enum', ARRAY['Technology', 'Retail', 'Manufacturing', 'Services', 'Healthcare']),
+    ('customer', 'account_owner', 'Account owner', 'text', '{}'),
+    ('customer', 'contract_end_date', 'Contract end date', 'date', '{}'),
+    ('product', 'license_seats', 'License seats', 'number', '{}');
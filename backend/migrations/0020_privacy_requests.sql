@@ -0,0 +1,35 @@
+-- LGPD/GDPR data subject requests (export and erasure) with deadline and completion evidence
+CREATE TABLE privacy_requests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('export', 'erasure')),
+    subject_type VARCHAR(20) NOT NULL CHECK (subject_type IN ('customer', 'user')),
+    -- Customer UUID or numeric user id
+    subject_id VARCHAR(64) NOT NULL,
+    requested_by VARCHAR(255),
+    reason TEXT,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'completed', 'rejected')),
+    received_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    due_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    handled_by VARCHAR(255),
+    rejection_reason TEXT,
+    -- SHA-256 of the subject's email, so the request can be proven without keeping the address
+    subject_fingerprint CHAR(64),
+    -- SHA-256 of the delivered export document
+    document_sha256 CHAR(64),
+    -- Records exported or anonymized per category, and data retained with its legal basis
+    records JSONB NOT NULL DEFAULT '{}',
+    retained JSONB NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX idx_privacy_requests_subject ON privacy_requests(subject_type, subject_id);
+CREATE INDEX idx_privacy_requests_pending_due ON privacy_requests(due_at) WHERE status = 'pending';
+
+-- Export documents are purged when the same subject later asks for erasure
+CREATE TABLE privacy_exports (
+    request_id UUID PRIMARY KEY REFERENCES privacy_requests(id) ON DELETE CASCADE,
+    document BYTEA NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+ALTER TABLE customers ADD COLUMN anonymized_at TIMESTAMP WITH TIME ZONE;
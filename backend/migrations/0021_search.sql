@@ -0,0 +1,30 @@
+-- Accent-insensitive full-text search across customers, products and sales
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+-- unaccent() is only STABLE; generated columns need an IMMUTABLE wrapper
+CREATE OR REPLACE FUNCTION immutable_unaccent(text) RETURNS text AS $$
+    SELECT public.unaccent('public.unaccent', $1)
+$$ LANGUAGE sql IMMUTABLE PARALLEL SAFE STRICT;
+
+-- Weights follow the ranking: name/SKU (A), company/email/ids (B), free text (C)
+ALTER TABLE customers ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', immutable_unaccent(coalesce(name, ''))), 'A') ||
+    setweight(to_tsvector('simple', immutable_unaccent(coalesce(company, ''))), 'B') ||
+    setweight(to_tsvector('simple', immutable_unaccent(coalesce(email, ''))), 'B')
+) STORED;
+
+ALTER TABLE products ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', immutable_unaccent(coalesce(name, ''))), 'A') ||
+    setweight(to_tsvector('simple', coalesce(sku, '')), 'A') ||
+    setweight(to_tsvector('simple', immutable_unaccent(coalesce(description, ''))), 'C')
+) STORED;
+
+ALTER TABLE sales ADD COLUMN search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', id::text), 'B') ||
+    setweight(to_tsvector('simple', coalesce(transaction_id, '')), 'B') ||
+    setweight(to_tsvector('simple', immutable_unaccent(coalesce(notes, ''))), 'C')
+) STORED;
+
+CREATE INDEX idx_customers_search ON customers USING GIN (search_vector);
+CREATE INDEX idx_products_search ON products USING GIN (search_vector);
+CREATE INDEX idx_sales_search ON sales USING GIN (search_vector);
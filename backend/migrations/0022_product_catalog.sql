@@ -0,0 +1,30 @@
+-- Product catalog: case-insensitive unique SKU and price/cost history for margin analytics
+ALTER TABLE products DROP CONSTRAINT IF EXISTS products_sku_key;
+UPDATE products SET sku = UPPER(TRIM(sku)) WHERE sku IS NOT NULL;
+CREATE UNIQUE INDEX idx_products_sku_unique ON products(UPPER(sku));
+CREATE INDEX idx_products_category ON products(category);
+CREATE INDEX idx_products_active ON products(is_active);
+
+CREATE TABLE product_price_history (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    product_id UUID NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    price DECIMAL(10, 2) NOT NULL CHECK (price >= 0),
+    cost DECIMAL(10, 2) CHECK (cost >= 0),
+    currency CHAR(3) NOT NULL,
+    -- (price - cost) / price, in percent; NULL when the cost is unknown
+    margin_percent DECIMAL(7, 2),
+    -- NULL on the product's first entry
+    previous_price DECIMAL(10, 2),
+    previous_cost DECIMAL(10, 2),
+    changed_by VARCHAR(255),
+    changed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_product_price_history_product ON product_price_history(product_id, changed_at);
+
+-- Existing products start their history with the current price
+INSERT INTO product_price_history (product_id, price, cost, currency, margin_percent, changed_at)
+SELECT id, price, cost, currency,
+       CASE WHEN cost IS NOT NULL AND price > 0 THEN ROUND((price - cost) / price * 100, 2) END,
+       created_at
+FROM products;
@@ -0,0 +1,66 @@
+-- Append-only stock ledger: on-hand is the sum of movements, reserved the sum of active reservations
+CREATE TYPE stock_movement_kind AS ENUM ('receipt', 'sale', 'refund_return', 'adjustment', 'transfer');
+CREATE TYPE stock_reservation_status AS ENUM ('active', 'committed', 'released');
+
+CREATE TABLE stock_movements (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    product_id UUID NOT NULL REFERENCES products(id),
+    kind stock_movement_kind NOT NULL,
+    location VARCHAR(40) NOT NULL DEFAULT 'main',
+    -- Positive for stock coming in, negative for stock going out
+    quantity INTEGER NOT NULL CHECK (quantity <> 0),
+    -- Sale, refund or transfer that produced the movement
+    reference_id UUID,
+    reason TEXT,
+    created_by VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CHECK (kind <> 'adjustment' OR reason IS NOT NULL)
+);
+
+CREATE INDEX idx_stock_movements_product ON stock_movements(product_id, location);
+CREATE INDEX idx_stock_movements_reference ON stock_movements(reference_id);
+
+-- The ledger is never rewritten; corrections are new adjustment movements
+CREATE RULE stock_movements_no_update AS ON UPDATE TO stock_movements DO INSTEAD NOTHING;
+CREATE RULE stock_movements_no_delete AS ON DELETE TO stock_movements DO INSTEAD NOTHING;
+
+CREATE TABLE stock_reservations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    sale_id UUID NOT NULL REFERENCES sales(id) ON DELETE CASCADE,
+    product_id UUID NOT NULL REFERENCES products(id),
+    location VARCHAR(40) NOT NULL DEFAULT 'main',
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    status stock_reservation_status NOT NULL DEFAULT 'active',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    resolved_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_stock_reservations_active ON stock_reservations(product_id, location) WHERE status = 'active';
+CREATE UNIQUE INDEX idx_stock_reservations_sale_product ON stock_reservations(sale_id, product_id, location) WHERE status = 'active';
+
+-- Current stock by product and location
+CREATE VIEW stock_levels AS
+SELECT product_id,
+       location,
+       SUM(on_hand)::INTEGER AS on_hand,
+       SUM(reserved)::INTEGER AS reserved,
+       SUM(on_hand - reserved)::INTEGER AS available
+FROM (
+    SELECT product_id, location, quantity AS on_hand, 0 AS reserved FROM stock_movements
+    UNION ALL
+    SELECT product_id, location, 0, quantity FROM stock_reservations WHERE status = 'active'
+) balances
+GROUP BY product_id, location;
+
+-- Existing balances become the opening receipt; stock_quantity/reserved_quantity are now a cache of the ledger
+INSERT INTO stock_movements (product_id, kind, quantity, reason, created_at)
+SELECT id, 'receipt', stock_quantity, 'Initial stock', created_at
+FROM products
+WHERE stock_quantity > 0;
+
+INSERT INTO stock_reservations (sale_id, product_id, quantity, created_at)
+SELECT si.sale_id, si.product_id, SUM(si.quantity), s.created_at
+FROM sale_items si
+JOIN sales s ON s.id = si.sale_id
+WHERE s.status = 'pending' AND si.product_id IS NOT NULL
+GROUP BY si.sale_id, si.product_id, s.created_at;
@@ -0,0 +1,27 @@
+-- Warehouses (stock locations); ledger movements and reservations reference them by code
+CREATE TABLE warehouses (
+    code VARCHAR(40) PRIMARY KEY CHECK (code ~ '^[a-z0-9_-]+$'),
+    name VARCHAR(255) NOT NULL,
+    city VARCHAR(100),
+    country VARCHAR(100),
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    -- The default warehouse is first in automatic fulfillment and cannot be deactivated
+    CHECK (NOT is_default OR is_active)
+);
+
+-- Exactly one default warehouse
+CREATE UNIQUE INDEX idx_warehouses_single_default ON warehouses(is_default) WHERE is_default;
+
+INSERT INTO warehouses (code, name, is_default) VALUES ('main', 'Main warehouse', TRUE);
+
+-- Locations used before warehouses existed become warehouses too
+INSERT INTO warehouses (code, name)
+SELECT DISTINCT location, location FROM stock_movements WHERE location <> 'main'
+ON CONFLICT (code) DO NOTHING;
+
+ALTER TABLE stock_movements
+    ADD CONSTRAINT fk_stock_movements_warehouse FOREIGN KEY (location) REFERENCES warehouses(code);
+ALTER TABLE stock_reservations
+    ADD CONSTRAINT fk_stock_reservations_warehouse FOREIGN KEY (location) REFERENCES warehouses(code);
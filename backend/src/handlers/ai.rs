@@ -1,16 +1,39 @@
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::inventory::{LocationDemand, ReorderOverride, StockMovementKind};
+use crate::models::money::{round_cents, ExchangeRates};
+use crate::services::inventory;
+use chrono::{Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 pub struct PredictionRequest {
     pub data: Vec<f64>,
 }
 
+// Política de reposição por produto, a partir das vendas capturadas de `history_days`, e transferências entre depósitos
+// para a demanda de `horizon_days`
 #[derive(Debug, Deserialize)]
 pub struct OptimizationRequest {
-    pub inventory_levels: Vec<f64>,
-    pub demand_forecast: Vec<f64>,
+    pub history_days: Option<i64>,
+    // Padrões para todos os produtos; `products` muda prazo e nível de serviço de produtos específicos
+    pub lead_time_days: Option<f64>,
+    pub service_level: Option<f64>,
+    // Custo fixo de um pedido de compra, na moeda base
+    pub ordering_cost: Option<Decimal>,
+    pub holding_cost_rate: Option<f64>,
+    #[serde(default)]
+    pub products: Vec<ReorderOverride>,
+    // Período coberto pela previsão; sem `location_demand`, a demanda é a venda do mesmo período anterior
+    pub horizon_days: Option<i64>,
+    #[serde(default)]
+    pub location_demand: Vec<LocationDemand>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,12 +49,6 @@ pub struct PredictionResponse {
     pub confidence: f64,
 }
 
-#[derive(Debug, Serialize)]
-pub struct OptimizationResponse {
-    pub suggested_order: Vec<f64>,
-    pub estimated_cost: f64,
-}
-
 #[derive(Debug, Serialize)]
 pub struct FraudDetectionResponse {
     pub is_fraud: bool,
@@ -53,23 +70,154 @@ pub async fn predict_sales(data: web::Json<PredictionRequest>) -> impl Responder
     })
 }
 
-pub async fn optimize_inventory(data: web::Json<OptimizationRequest>) -> impl Responder {
-    // Simulação de otimização de estoque
-    let suggested_order: Vec<f64> = data.inventory_levels
+fn check_lead_time(days: f64) -> Result<f64, String> {
+    if days > 0.0 && days <= 365.0 {
+        Ok(days)
+    } else {
+        Err("lead_time_days must be greater than 0 and at most 365".to_string())
+    }
+}
+
+fn check_service_level(level: f64) -> Result<f64, String> {
+    if (0.5..=0.999).contains(&level) {
+        Ok(level)
+    } else {
+        Err("service_level must be between 0.5 and 0.999".to_string())
+    }
+}
+
+fn reorder_defaults(data: &OptimizationRequest) -> Result<inventory::ReorderParams, String> {
+    let ordering_cost = data.ordering_cost.unwrap_or(Decimal::from(50));
+    if ordering_cost < Decimal::ZERO {
+        return Err("ordering_cost cannot be negative".to_string());
+    }
+    let holding_cost_rate = data.holding_cost_rate.unwrap_or(0.25);
+    if !(holding_cost_rate > 0.0 && holding_cost_rate <= 5.0) {
+        return Err("holding_cost_rate must be greater than 0 and at most 5".to_string());
+    }
+    Ok(inventory::ReorderParams {
+        lead_time_days: check_lead_time(data.lead_time_days.unwrap_or(7.0))?,
+        service_level: check_service_level(data.service_level.unwrap_or(0.95))?,
+        ordering_cost: ordering_cost.to_f64().unwrap_or_default(),
+        holding_cost_rate,
+    })
+}
+
+fn override_params(entry: &ReorderOverride, defaults: &inventory::ReorderParams) -> Result<inventory::ReorderParams, String> {
+    Ok(inventory::ReorderParams {
+        lead_time_days: check_lead_time(entry.lead_time_days.unwrap_or(defaults.lead_time_days))?,
+        service_level: check_service_level(entry.service_level.unwrap_or(defaults.service_level))?,
+        ..*defaults
+    })
+}
+
+pub async fn optimize_inventory(
+    data: web::Json<OptimizationRequest>,
+    product_state: web::Data<ProductState>,
+    sales_state: web::Data<SalesState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let history_days = data.history_days.unwrap_or(90);
+    let horizon_days = data.horizon_days.unwrap_or(30);
+    if !(7..=365).contains(&history_days) {
+        return HttpResponse::BadRequest().json(json!({ "error": "history_days must be between 7 and 365" }));
+    }
+    if !(1..=365).contains(&horizon_days) {
+        return HttpResponse::BadRequest().json(json!({ "error": "horizon_days must be between 1 and 365" }));
+    }
+    let defaults = match reorder_defaults(&data) {
+        Ok(defaults) => defaults,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    // Unidades vendidas por dia da venda, do dia mais antigo para hoje, descontadas as reembolsadas.
+    // Vem das vendas (inclusive o histórico importado), não do razão, que só data a baixa do pagamento
+    let today = Utc::now().date_naive();
+    let mut daily: HashMap<Uuid, Vec<f64>> = HashMap::new();
+    for sale in sales_state.sales.lock().unwrap().iter().filter(|s| s.status.is_captured()) {
+        let age = (today - sale.created_at.date_naive()).num_days();
+        if !(0..history_days).contains(&age) {
+            continue;
+        }
+        for item in &sale.items {
+            let days = daily.entry(item.product_id).or_insert_with(|| vec![0.0; history_days as usize]);
+            days[(history_days - 1 - age) as usize] += (item.quantity - item.refunded_quantity) as f64;
+        }
+    }
+
+    // O razão dá a posição de estoque e, sem `location_demand`, a demanda por depósito
+    let ledger = product_state.ledger.lock().unwrap();
+    let products = product_state.products.lock().unwrap();
+    let mut params: HashMap<Uuid, inventory::ReorderParams> = HashMap::new();
+    for entry in &data.products {
+        if !products.iter().any(|p| p.id == entry.product_id) {
+            return HttpResponse::BadRequest().json(json!({ "error": format!("Product {} not found", entry.product_id) }));
+        }
+        match override_params(entry, &defaults) {
+            Ok(overridden) => params.insert(entry.product_id, overridden),
+            Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+        };
+    }
+
+    let mut demand: HashMap<(Uuid, String), i32> = HashMap::new();
+    if data.location_demand.is_empty() {
+        let since = Utc::now() - Duration::days(horizon_days);
+        for movement in ledger.movements.iter().filter(|m| m.kind == StockMovementKind::Sale && m.created_at >= since) {
+            *demand.entry((movement.product_id, movement.location.clone())).or_insert(0) -= movement.quantity;
+        }
+    } else {
+        for entry in &data.location_demand {
+            if entry.quantity < 0 {
+                return HttpResponse::BadRequest().json(json!({ "error": "Demand cannot be negative" }));
+            }
+            let location = match ledger.check_location(&entry.location, false) {
+                Ok(location) => location,
+                Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+            };
+            if !products.iter().any(|p| p.id == entry.product_id) {
+                return HttpResponse::BadRequest().json(json!({ "error": format!("Product {} not found", entry.product_id) }));
+            }
+            *demand.entry((entry.product_id, location)).or_insert(0) += entry.quantity;
+        }
+    }
+
+    let active: Vec<_> = products.iter().filter(|p| p.is_active).cloned().collect();
+    let (transfers, uncovered) = inventory::rebalance(&ledger, &active, &demand);
+    let default_location = ledger.default_location();
+    let no_sales = vec![0.0; history_days as usize];
+    let recommendations: Vec<_> = active
         .iter()
-        .zip(data.demand_forecast.iter())
-        .map(|(inv, demand)| {
-            let needed = demand - inv;
-            if needed > 0.0 { needed } else { 0.0 }
+        .map(|product| {
+            // A compra vai para o depósito com a maior falta que as transferências não cobrem
+            let location = uncovered
+                .iter()
+                .filter(|u| u.product_id == product.id)
+                .max_by(|a, b| a.quantity.cmp(&b.quantity).then(b.location.cmp(&a.location)))
+                .map(|u| u.location.clone())
+                .unwrap_or_else(|| default_location.clone());
+            let unit_cost = product.cost.as_ref().and_then(|cost| rates.convert(cost, &rates.base));
+            inventory::reorder_recommendation(
+                product,
+                daily.get(&product.id).unwrap_or(&no_sales),
+                ledger.available(product.id, None),
+                unit_cost,
+                params.get(&product.id).unwrap_or(&defaults),
+                &location,
+            )
         })
         .collect();
+    // Pedidos de produtos sem custo cadastrado ficam de fora
+    let estimated_cost: Decimal = recommendations.iter().filter_map(|r| r.order_cost.as_ref()).map(|cost| cost.amount).sum();
 
-    let estimated_cost = suggested_order.iter().sum::<f64>() * 10.0;
-
-    HttpResponse::Ok().json(OptimizationResponse {
-        suggested_order,
-        estimated_cost,
-    })
+    HttpResponse::Ok().json(json!({
+        "history_days": history_days,
+        "horizon_days": horizon_days,
+        "demand_source": if data.location_demand.is_empty() { "sales" } else { "request" },
+        "recommendations": recommendations,
+        "transfers": transfers,
+        "currency": rates.base,
+        "estimated_cost": round_cents(estimated_cost),
+    }))
 }
 
 pub async fn detect_fraud(data: web::Json<FraudDetectionRequest>) -> impl Responder {
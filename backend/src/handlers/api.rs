@@ -1,6 +1,10 @@
-use actix_web::{HttpResponse, Responder};
+use actix_web::{web, HttpResponse, Responder};
 use serde_json::json;
-use chrono::Utc;
+use chrono::{Duration, Utc};
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::{revenue_by_product, revenue_totals, SalesState};
+use crate::handlers::subscriptions::SubscriptionState;
+use crate::models::money::ExchangeRates;
 
 pub async fn health() -> impl Responder {
     HttpResponse::Ok().json(json!({
@@ -39,10 +43,40 @@ pub async fn get_config() -> impl Responder {
     }))
 }
 
-pub async fn get_dashboard() -> impl Responder {
+pub async fn get_dashboard(
+    sales_state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+    subscription_state: web::Data<SubscriptionState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let now = Utc::now();
+    let recurring = subscription_state.metrics(&rates, now - Duration::days(30), now);
+    let (totals, top_products) = {
+        let sales = sales_state.sales.lock().unwrap();
+        let products = product_state.products.lock().unwrap();
+        let top_products: Vec<_> = revenue_by_product(&sales, &products, &rates)
+            .into_iter()
+            .take(5)
+            .map(|p| json!({ "id": p.product_id, "name": p.name, "sales": p.quantity, "revenue": p.revenue.amount }))
+            .collect();
+        (revenue_totals(&sales, &rates), top_products)
+    };
+
     HttpResponse::Ok().json(json!({
         "stats": {
-            "totalRevenue": 125430.50,
+            "currency": rates.base,
+            "totalRevenue": totals.total.amount,
+            "grossRevenue": totals.gross.amount,
+            "totalRefunds": totals.refunds.amount,
+            "totalTax": totals.tax.amount,
+            "netRevenue": totals.net.amount,
+            "mrr": recurring.mrr,
+            "arr": recurring.arr,
+            "activeSubscriptions": recurring.active_subscriptions,
+            "mrrChurnRate": recurring.mrr_churn_rate,
+            "customerChurnRate": recurring.customer_churn_rate,
+            "expansionMrr": recurring.expansion_mrr,
+            "netNewMrr": recurring.net_new_mrr,
             "totalSales": 2345,
             "activeCustomers": 1567,
             "conversionRate": 3.2,
@@ -58,13 +92,7 @@ pub async fn get_dashboard() -> impl Responder {
             { "id": 4, "customer": "Ana Oliveira", "amount": 567.25, "date": "2024-01-14", "status": "completed" },
             { "id": 5, "customer": "Carlos Mendes", "amount": 1234.00, "date": "2024-01-13", "status": "shipped" }
         ],
-        "topProducts": [
-            { "id": 1, "name": "Produto A", "sales": 245, "revenue": 24500.00 },
-            { "id": 2, "name": "Produto B", "sales": 189, "revenue": 18900.00 },
-            { "id": 3, "name": "Produto C", "sales": 156, "revenue": 15600.00 },
-            { "id": 4, "name": "Produto D", "sales": 134, "revenue": 13400.00 },
-            { "id": 5, "name": "Produto E", "sales": 98, "revenue": 9800.00 }
-        ],
+        "topProducts": top_products,
         "aiInsights": {
             "predictedGrowth": "15%",
             "riskLevel": "Low",
@@ -76,37 +104,3 @@ pub async fn get_dashboard() -> impl Responder {
         }
     }))
 }
-
-pub async fn get_sales() -> impl Responder {
-    HttpResponse::Ok().json(json!({
-        "sales": [
-            { "id": 1, "product": "Produto A", "quantity": 10, "amount": 1000.00, "date": "2024-01-15", "customer": "João Silva", "status": "completed" },
-            { "id": 2, "product": "Produto B", "quantity": 5, "amount": 500.00, "date": "2024-01-15", "customer": "Maria Santos", "status": "pending" },
-            { "id": 3, "product": "Produto C", "quantity": 8, "amount": 800.00, "date": "2024-01-14", "customer": "Pedro Costa", "status": "completed" },
-            { "id": 4, "product": "Produto D", "quantity": 12, "amount": 1200.00, "date": "2024-01-14", "customer": "Ana Oliveira", "status": "completed" },
-            { "id": 5, "product": "Produto E", "quantity": 3, "amount": 300.00, "date": "2024-01-13", "customer": "Carlos Mendes", "status": "shipped" }
-        ],
-        "summary": {
-            "totalAmount": 3800.00,
-            "averageSale": 760.00,
-            "itemsSold": 38
-        }
-    }))
-}
-
-pub async fn get_customers() -> impl Responder {
-    HttpResponse::Ok().json(json!({
-        "customers": [
-            { "id": 1, "name": "João Silva", "email": "joao@empresa.com", "phone": "(11) 99999-9999", "totalPurchases": 1250.00, "lastPurchase": "2024-01-15", "status": "active" },
-            { "id": 2, "name": "Maria Santos", "email": "maria@empresa.com", "phone": "(11) 98888-8888", "totalPurchases": 890.50, "lastPurchase": "2024-01-15", "status": "active" },
-            { "id": 3, "name": "Pedro Costa", "email": "pedro@empresa.com", "phone": "(11) 97777-7777", "totalPurchases": 2345.75, "lastPurchase": "2024-01-14", "status": "active" },
-            { "id": 4, "name": "Ana Oliveira", "email": "ana@empresa.com", "phone": "(11) 96666-6666", "totalPurchases": 567.25, "lastPurchase": "2024-01-14", "status": "active" },
-            { "id": 5, "name": "Carlos Mendes", "email": "carlos@empresa.com", "phone": "(11) 95555-5555", "totalPurchases": 1234.00, "lastPurchase": "2024-01-13", "status": "active" }
-        ],
-        "summary": {
-            "totalCustomers": 5,
-            "activeCustomers": 5,
-            "avgPurchaseValue": 1257.50
-        }
-    }))
-}
@@ -1,4 +1,4 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
 use chrono::{Utc, Duration};
@@ -84,6 +84,16 @@ impl AppState {
     }
 }
 
+// Claims do header `Authorization: Bearer ...`; None sem token, com token inválido ou de conta removida
+pub fn bearer_claims(req: &HttpRequest, state: &AppState) -> Option<Claims> {
+    let header = req.headers().get("Authorization")?.to_str().ok()?;
+    let token = header.strip_prefix("Bearer ")?.trim();
+    let claims = decode::<Claims>(token, &DecodingKey::from_secret(state.jwt_secret.as_ref()), &Validation::default())
+        .ok()?
+        .claims;
+    state.users.lock().unwrap().iter().any(|u| u.email == claims.sub).then_some(claims)
+}
+
 pub async fn login(
     login_data: web::Json<LoginRequest>,
     state: web::Data<AppState>,
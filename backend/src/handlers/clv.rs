@@ -0,0 +1,159 @@
+use actix_web::error::BlockingError;
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::sales::SalesState;
+use crate::models::clv::{ClvEstimate, ClvModel};
+use crate::models::money::{round_cents, ExchangeRates, Money};
+use crate::services::clv;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const BOOTSTRAP_SAMPLES: usize = 50;
+const CONFIDENCE: u32 = 90;
+const WEEKS_PER_MONTH: f64 = 365.25 / 12.0 / 7.0;
+
+pub struct ClvState {
+    pub horizon_months: u32,
+    pub model: Mutex<Option<ClvModel>>,
+    // Pedido de recálculo (após mesclagens); o agendador atende na próxima passada
+    refresh_requested: AtomicBool,
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).map(round_cents).unwrap_or_default()
+}
+
+impl ClvState {
+    pub fn new(horizon_months: u32) -> Self {
+        ClvState {
+            horizon_months: horizon_months.max(1),
+            model: Mutex::new(None),
+            refresh_requested: AtomicBool::new(false),
+        }
+    }
+
+    pub fn request_refresh(&self) {
+        self.refresh_requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn take_refresh_request(&self) -> bool {
+        self.refresh_requested.swap(false, Ordering::SeqCst)
+    }
+
+    // Recalcula o LTV histórico (vendas líquidas de reembolso) e a previsão de CLV de cada cliente
+    pub fn refresh(
+        &self,
+        customer_state: &CustomerState,
+        sales_state: &SalesState,
+        rates: &ExchangeRates,
+        now: DateTime<Utc>,
+    ) -> ClvModel {
+        let mut lifetime_values: HashMap<Uuid, Decimal> = HashMap::new();
+        let transactions: Vec<(Uuid, DateTime<Utc>, f64)> = {
+            let sales = sales_state.sales.lock().unwrap();
+            sales
+                .iter()
+                .filter(|s| s.status.is_captured() && s.created_at <= now)
+                .filter_map(|sale| {
+                    let net = sale.to_base(sale.net_amount().ok()?.amount);
+                    *lifetime_values.entry(sale.customer_id).or_default() += net;
+                    Some((sale.customer_id, sale.created_at, net.to_f64().unwrap_or_default()))
+                })
+                .collect()
+        };
+
+        let histories = clv::histories(&transactions, now);
+        let horizon_weeks = f64::from(self.horizon_months) * WEEKS_PER_MONTH;
+        let fit = clv::fit(&histories, None);
+        let bounds = fit
+            .as_ref()
+            .map(|fit| clv::bootstrap_bounds(&histories, fit, horizon_weeks, BOOTSTRAP_SAMPLES, CONFIDENCE))
+            .unwrap_or_default();
+
+        let base = |value: f64| Money::new(decimal(value), rates.base.clone());
+        let estimates: HashMap<Uuid, ClvEstimate> = fit
+            .as_ref()
+            .map(|fit| {
+                histories
+                    .iter()
+                    .map(|history| {
+                        let prediction = clv::predict(fit, history, horizon_weeks);
+                        let (lower, upper) = bounds
+                            .get(&history.customer_id)
+                            .copied()
+                            .unwrap_or((prediction.value, prediction.value));
+                        let estimate = ClvEstimate {
+                            expected_purchases: decimal(prediction.expected_purchases),
+                            expected_order_value: base(prediction.expected_order_value),
+                            probability_alive: decimal(prediction.probability_alive * 100.0),
+                            value: base(prediction.value),
+                            lower: base(lower),
+                            upper: base(upper),
+                            horizon_months: self.horizon_months,
+                            computed_at: now,
+                        };
+                        (history.customer_id, estimate)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut customers = customer_state.customers.lock().unwrap();
+        for customer in customers.iter_mut() {
+            let lifetime_value = lifetime_values.get(&customer.id).copied().unwrap_or_default();
+            customer.lifetime_value = Money::new(lifetime_value, rates.base.clone());
+            customer.predicted_clv = estimates.get(&customer.id).cloned();
+        }
+
+        let model = ClvModel {
+            computed_at: now,
+            currency: rates.base.clone(),
+            horizon_months: self.horizon_months,
+            confidence: CONFIDENCE,
+            bootstrap_samples: BOOTSTRAP_SAMPLES,
+            customers_fitted: histories.len(),
+            repeat_customers: histories.iter().filter(|h| h.frequency > 0.0).count(),
+            bg_nbd: fit.map(|f| f.bg_nbd),
+            gamma_gamma: fit.and_then(|f| f.gamma_gamma),
+            total_lifetime_value: lifetime_values.values().sum(),
+            total_predicted_value: estimates.values().map(|e| e.value.amount).sum(),
+        };
+        *self.model.lock().unwrap() = Some(model.clone());
+        model
+    }
+}
+
+// O ajuste (e os refits do bootstrap) é pesado: roda no pool de threads bloqueantes, fora dos workers
+pub async fn refresh_blocking(
+    state: web::Data<ClvState>,
+    customer_state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    rates: web::Data<ExchangeRates>,
+) -> Result<ClvModel, BlockingError> {
+    web::block(move || state.refresh(&customer_state, &sales_state, &rates, Utc::now())).await
+}
+
+pub async fn get_clv_model(state: web::Data<ClvState>) -> impl Responder {
+    match &*state.model.lock().unwrap() {
+        Some(model) => HttpResponse::Ok().json(model),
+        None => HttpResponse::NotFound().json(json!({ "error": "CLV model has not been computed yet" })),
+    }
+}
+
+pub async fn refresh_clv(
+    state: web::Data<ClvState>,
+    customer_state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    match refresh_blocking(state, customer_state, sales_state, rates).await {
+        Ok(model) => HttpResponse::Ok().json(model),
+        Err(err) => HttpResponse::InternalServerError().json(json!({ "error": err.to_string() })),
+    }
+}
@@ -0,0 +1,175 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::products::ProductState;
+use crate::models::custom_field::{
+    AttributesUpdate, CustomFieldDefinition, CustomFieldDefinitionCreate, CustomFieldEntity, CustomFieldType,
+};
+use crate::services::custom_fields;
+use chrono::Utc;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub struct CustomFieldState {
+    pub definitions: Mutex<Vec<CustomFieldDefinition>>,
+}
+
+fn definition(
+    entity: CustomFieldEntity,
+    key: &str,
+    label: &str,
+    field_type: CustomFieldType,
+    options: &[&str],
+) -> CustomFieldDefinition {
+    CustomFieldDefinition {
+        id: Uuid::new_v4(),
+        entity,
+        key: key.to_string(),
+        label: label.to_string(),
+        field_type,
+        options: options.iter().map(|o| o.to_string()).collect(),
+        required: false,
+        created_at: Utc::now(),
+    }
+}
+
+impl CustomFieldState {
+    pub fn new() -> Self {
+        let definitions = vec![
+            definition(
+                CustomFieldEntity::Customer,
+                "industry",
+                "Industry",
+                CustomFieldType::Enum,
+                &["Technology", "Retail", "Manufacturing", "Services", "Healthcare"],
+            ),
+            definition(CustomFieldEntity::Customer, "account_owner", "Account owner", CustomFieldType::Text, &[]),
+            definition(CustomFieldEntity::Customer, "contract_end_date", "Contract end date", CustomFieldType::Date, &[]),
+            definition(CustomFieldEntity::Product, "license_seats", "License seats", CustomFieldType::Number, &[]),
+        ];
+
+        CustomFieldState {
+            definitions: Mutex::new(definitions),
+        }
+    }
+
+    // Esquema de uma entidade, na ordem de criação (usado nas colunas das exportações)
+    pub fn schema(&self, entity: CustomFieldEntity) -> Vec<CustomFieldDefinition> {
+        self.definitions.lock().unwrap().iter().filter(|d| d.entity == entity).cloned().collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CustomFieldFilter {
+    pub entity: Option<CustomFieldEntity>,
+}
+
+pub async fn get_custom_fields(
+    query: web::Query<CustomFieldFilter>,
+    state: web::Data<CustomFieldState>,
+) -> impl Responder {
+    let definitions: Vec<CustomFieldDefinition> = state.definitions.lock().unwrap()
+        .iter()
+        .filter(|d| query.entity.is_none_or(|entity| d.entity == entity))
+        .cloned()
+        .collect();
+    HttpResponse::Ok().json(definitions)
+}
+
+pub async fn create_custom_field(
+    field_req: web::Json<CustomFieldDefinitionCreate>,
+    state: web::Data<CustomFieldState>,
+) -> impl Responder {
+    let req = field_req.into_inner();
+    let mut definitions = state.definitions.lock().unwrap();
+    let (key, options) = match custom_fields::validate_definition(&req, &definitions) {
+        Ok(validated) => validated,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    // Campos obrigatórios valem a partir da próxima gravação de cada registro
+    let definition = CustomFieldDefinition {
+        id: Uuid::new_v4(),
+        entity: req.entity,
+        key,
+        label: req.label.trim().to_string(),
+        field_type: req.field_type,
+        options,
+        required: req.required,
+        created_at: Utc::now(),
+    };
+    definitions.push(definition.clone());
+    HttpResponse::Created().json(definition)
+}
+
+// Campo com valores gravados não é removido; limpe os valores antes
+pub async fn delete_custom_field(
+    path: web::Path<Uuid>,
+    state: web::Data<CustomFieldState>,
+    customer_state: web::Data<CustomerState>,
+    product_state: web::Data<ProductState>,
+) -> impl Responder {
+    let field_id = path.into_inner();
+    let mut definitions = state.definitions.lock().unwrap();
+    let Some(definition) = definitions.iter().find(|d| d.id == field_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Custom field not found" }));
+    };
+
+    let in_use = match definition.entity {
+        CustomFieldEntity::Customer => customer_state.customers.lock().unwrap()
+            .iter()
+            .filter(|c| c.custom_fields.contains_key(&definition.key))
+            .count(),
+        CustomFieldEntity::Product => product_state.products.lock().unwrap()
+            .iter()
+            .filter(|p| p.custom_fields.contains_key(&definition.key))
+            .count(),
+    };
+    if in_use > 0 {
+        return HttpResponse::Conflict().json(json!({
+            "error": format!("Custom field {} has values on {} {} records", definition.key, in_use, definition.entity.as_str())
+        }));
+    }
+
+    definitions.retain(|d| d.id != field_id);
+    HttpResponse::NoContent().finish()
+}
+
+// Campos personalizados e tags de um produto
+pub async fn update_product_attributes(
+    path: web::Path<Uuid>,
+    attributes_req: web::Json<AttributesUpdate>,
+    state: web::Data<CustomFieldState>,
+    product_state: web::Data<ProductState>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    let req = attributes_req.into_inner();
+    let definitions = state.definitions.lock().unwrap().clone();
+    let mut products = product_state.products.lock().unwrap();
+    let Some(product) = products.iter_mut().find(|p| p.id == product_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Product not found" }));
+    };
+
+    let validated = (|| {
+        Ok::<_, String>((
+            custom_fields::apply_values(
+                &definitions,
+                CustomFieldEntity::Product,
+                &product.custom_fields,
+                &req.custom_fields.unwrap_or_default(),
+            )?,
+            req.tags.as_deref().map(custom_fields::normalize_tags).transpose()?,
+        ))
+    })();
+    let (values, tags) = match validated {
+        Ok(validated) => validated,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    product.custom_fields = values;
+    if let Some(tags) = tags {
+        product.tags = tags;
+    }
+
+    HttpResponse::Ok().json(product.clone())
+}
@@ -1,47 +1,552 @@
-use actix_web::{HttpResponse, Responder};
+use actix_web::{web, HttpResponse, Responder};
 use serde_json::json;
-use crate::models::Customer;
+use crate::handlers::custom_fields::CustomFieldState;
+use crate::handlers::payments::PaymentState;
+use crate::handlers::products::ProductState;
+use crate::handlers::quotes::QuoteState;
+use crate::handlers::sales::SalesState;
+use crate::handlers::search::SearchState;
+use crate::handlers::subscriptions::SubscriptionState;
+use crate::models::custom_field::CustomFieldEntity;
+use crate::models::duplicate::{CustomerMerge, DuplicateCandidate, DuplicateStatus};
+use crate::models::fiscal::TaxId;
+use crate::models::money::{Currency, ExchangeRates, Money};
+use crate::models::query::ListQuery;
+use crate::models::timeline::CustomerStatusChange;
+use crate::models::{Customer, CustomerCreate, CustomerNote, CustomerNoteCreate, CustomerUpdate, Sale};
+use crate::services::{custom_fields, dedup};
 use chrono::Utc;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 use uuid::Uuid;
 
-pub async fn get_customers() -> impl Responder {
-    let customers = vec![
-        Customer {
-            id: Uuid::new_v4(),
-            name: "TechCorp Inc.".to_string(),
-            email: "contact@techcorp.com".to_string(),
-            phone: "+1-555-123-4567".to_string(),
-            company: "TechCorp".to_string(),
-            lifetime_value: 125000.00,
-            status: "active".to_string(),
-            created_at: Utc::now(),
-        },
-        Customer {
-            id: Uuid::new_v4(),
-            name: "Startup XYZ".to_string(),
-            email: "info@startupxyz.com".to_string(),
-            phone: "+1-555-234-5678".to_string(),
-            company: "Startup XYZ".to_string(),
-            lifetime_value: 89000.00,
-            status: "active".to_string(),
-            created_at: Utc::now(),
-        },
-        Customer {
-            id: Uuid::new_v4(),
-            name: "Global Solutions".to_string(),
-            email: "sales@globalsolutions.com".to_string(),
-            phone: "+44-20-1234-5678".to_string(),
-            company: "Global Solutions".to_string(),
-            lifetime_value: 210000.00,
-            status: "active".to_string(),
-            created_at: Utc::now(),
-        },
-    ];
-    
-    HttpResponse::Ok().json(json!({
-        "customers": customers,
-        "total": customers.len(),
-        "average_lifetime_value": customers.iter().map(|c| c.lifetime_value).sum::<f64>() / customers.len() as f64,
-        "active_customers": customers.iter().filter(|c| c.status == "active").count()
-    }))
+pub struct CustomerState {
+    pub customers: Mutex<Vec<Customer>>,
+    pub notes: Mutex<Vec<CustomerNote>>,
+    pub duplicates: Mutex<Vec<DuplicateCandidate>>,
+    pub merges: Mutex<Vec<CustomerMerge>>,
+    pub status_changes: Mutex<Vec<CustomerStatusChange>>,
+}
+
+fn seed_fields(values: &[(&str, serde_json::Value)]) -> BTreeMap<String, serde_json::Value> {
+    values.iter().map(|(key, value)| (key.to_string(), value.clone())).collect()
+}
+
+fn seed_tags(values: &[&str]) -> Vec<String> {
+    values.iter().map(|tag| tag.to_string()).collect()
+}
+
+impl CustomerState {
+    pub fn new(currency: &Currency) -> Self {
+        let now = Utc::now();
+        let customers = vec![
+            Customer {
+                id: Uuid::new_v4(),
+                name: "TechCorp Inc.".to_string(),
+                email: "contact@techcorp.com".to_string(),
+                phone: "+1-555-123-4567".to_string(),
+                company: "TechCorp".to_string(),
+                tax_id: TaxId::parse("11.222.333/0001-81").ok(),
+                address: Some("Av. Paulista, 1000".to_string()),
+                city: Some("São Paulo".to_string()),
+                country: Some("Brasil".to_string()),
+                segment: Some("Enterprise".to_string()),
+                lifetime_value: Money::zero(currency.clone()),
+                predicted_clv: None,
+                status: "active".to_string(),
+                custom_fields: seed_fields(&[("industry", json!("Technology")), ("account_owner", json!("Ana Souza")), ("contract_end_date", json!("2027-03-31"))]),
+                tags: seed_tags(&["enterprise", "vip"]),
+                anonymized_at: None,
+                created_at: now,
+                updated_at: now,
+            },
+            Customer {
+                id: Uuid::new_v4(),
+                name: "Startup XYZ".to_string(),
+                email: "info@startupxyz.com".to_string(),
+                phone: "+1-555-234-5678".to_string(),
+                company: "Startup XYZ".to_string(),
+                tax_id: TaxId::parse("45.278.190/0001-41").ok(),
+                address: Some("Rua XV de Novembro, 250".to_string()),
+                city: Some("Curitiba".to_string()),
+                country: Some("Brasil".to_string()),
+                segment: Some("SMB".to_string()),
+                lifetime_value: Money::zero(currency.clone()),
+                predicted_clv: None,
+                status: "active".to_string(),
+                custom_fields: seed_fields(&[("industry", json!("Technology")), ("account_owner", json!("Bruno Lima"))]),
+                tags: seed_tags(&["startup"]),
+                anonymized_at: None,
+                created_at: now,
+                updated_at: now,
+            },
+            Customer {
+                id: Uuid::new_v4(),
+                name: "Global Solutions".to_string(),
+                email: "sales@globalsolutions.com".to_string(),
+                phone: "+44-20-1234-5678".to_string(),
+                company: "Global Solutions".to_string(),
+                tax_id: None,
+                address: Some("221 Baker Street".to_string()),
+                city: Some("London".to_string()),
+                country: Some("United Kingdom".to_string()),
+                segment: Some("Enterprise".to_string()),
+                lifetime_value: Money::zero(currency.clone()),
+                predicted_clv: None,
+                status: "active".to_string(),
+                custom_fields: seed_fields(&[("industry", json!("Services")), ("contract_end_date", json!("2026-12-31"))]),
+                tags: seed_tags(&["enterprise", "international"]),
+                anonymized_at: None,
+                created_at: now,
+                updated_at: now,
+            },
+        ];
+
+        CustomerState {
+            customers: Mutex::new(customers),
+            notes: Mutex::new(Vec::new()),
+            duplicates: Mutex::new(Vec::new()),
+            merges: Mutex::new(Vec::new()),
+            status_changes: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Compara os clientes informados com os demais e enfileira pares novos; pares já revisados não voltam
+    pub fn detect_duplicates(&self, customer_ids: &[Uuid]) -> Vec<DuplicateCandidate> {
+        let customers = self.customers.lock().unwrap();
+        let mut duplicates = self.duplicates.lock().unwrap();
+        let now = Utc::now();
+        let mut found = Vec::new();
+
+        // Cadastros anonimizados não entram na comparação
+        let active = || customers.iter().filter(|c| c.anonymized_at.is_none());
+        for customer in active().filter(|c| customer_ids.contains(&c.id)) {
+            for other in active().filter(|o| o.id != customer.id) {
+                // Na varredura completa cada par aparece duas vezes
+                if customer_ids.contains(&other.id) && other.id < customer.id {
+                    continue;
+                }
+                if duplicates.iter().any(|d| d.involves(customer.id, other.id)) {
+                    continue;
+                }
+                let Some((score, reasons)) = dedup::score(customer, other) else {
+                    continue;
+                };
+                let candidate = DuplicateCandidate {
+                    id: Uuid::new_v4(),
+                    customer_id: other.id,
+                    duplicate_id: customer.id,
+                    score,
+                    reasons,
+                    status: DuplicateStatus::Pending,
+                    detected_at: now,
+                    reviewed_at: None,
+                };
+                duplicates.push(candidate.clone());
+                found.push(candidate);
+            }
+        }
+        found
+    }
+}
+
+const STATUSES: [&str; 3] = ["active", "inactive", "pending"];
+
+fn validate_email(email: &str) -> Result<String, String> {
+    let email = email.trim().to_lowercase();
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !email.chars().any(char::is_whitespace)
+        }
+        None => false,
+    };
+    if !valid {
+        return Err(format!("Invalid email: {}", email));
+    }
+    Ok(email)
+}
+
+// Aceita formatos como "+55 (11) 99999-9999"; o número completo tem de 8 a 15 dígitos (E.164)
+fn validate_phone(phone: &str) -> Result<String, String> {
+    let phone = phone.trim();
+    let digits = phone.chars().filter(|c| c.is_ascii_digit()).count();
+    let allowed = phone
+        .char_indices()
+        .all(|(i, c)| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')' | '.') || (c == '+' && i == 0));
+    if phone.is_empty() {
+        return Ok(String::new());
+    }
+    if !allowed || !(8..=15).contains(&digits) {
+        return Err(format!("Invalid phone number: {}", phone));
+    }
+    Ok(phone.to_string())
+}
+
+fn validate_status(status: &str) -> Result<String, String> {
+    let status = status.trim().to_lowercase();
+    if !STATUSES.contains(&status.as_str()) {
+        return Err(format!("Status must be one of {:?}", STATUSES));
+    }
+    Ok(status)
+}
+
+fn validate_tax_id(raw: &str) -> Result<Option<TaxId>, String> {
+    if raw.trim().is_empty() {
+        return Ok(None);
+    }
+    TaxId::parse(raw).map(Some)
+}
+
+// Texto vazio limpa o campo
+fn optional_text(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+pub async fn get_customers(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<CustomerState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<Customer>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let customers = state.customers.lock().unwrap();
+    HttpResponse::Ok().json(query.apply(&customers))
+}
+
+// CSV com os mesmos filtros e ordenação da listagem, sem paginação; uma coluna por campo personalizado
+pub async fn export_customers(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<CustomerState>,
+    field_state: web::Data<CustomFieldState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<Customer>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    let schema = field_state.schema(CustomFieldEntity::Customer);
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut header: Vec<String> = [
+        "id", "name", "email", "phone", "company", "tax_id", "address", "city", "country", "segment", "status",
+        "lifetime_value", "currency", "tags", "created_at",
+    ]
+    .iter()
+    .map(|column| column.to_string())
+    .collect();
+    header.extend(schema.iter().map(|d| format!("custom.{}", d.key)));
+    let _ = writer.write_record(&header);
+
+    let customers = state.customers.lock().unwrap();
+    for customer in query.matching(&customers) {
+        let mut record = vec![
+            customer.id.to_string(),
+            customer.name.clone(),
+            customer.email.clone(),
+            customer.phone.clone(),
+            customer.company.clone(),
+            customer.tax_id.as_ref().map(|t| t.formatted()).unwrap_or_default(),
+            customer.address.clone().unwrap_or_default(),
+            customer.city.clone().unwrap_or_default(),
+            customer.country.clone().unwrap_or_default(),
+            customer.segment.clone().unwrap_or_default(),
+            customer.status.clone(),
+            customer.lifetime_value.amount.to_string(),
+            customer.lifetime_value.currency.code().to_string(),
+            customer.tags.join(";"),
+            customer.created_at.to_rfc3339(),
+        ];
+        record.extend(schema.iter().map(|d| custom_fields::export_value(customer.custom_fields.get(&d.key))));
+        let _ = writer.write_record(&record);
+    }
+
+    match writer.into_inner() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(("Content-Disposition", "attachment; filename=\"customers.csv\""))
+            .body(body),
+        Err(err) => HttpResponse::InternalServerError().json(json!({ "error": err.to_string() })),
+    }
+}
+
+pub async fn get_customer(path: web::Path<Uuid>, state: web::Data<CustomerState>) -> impl Responder {
+    let customer_id = path.into_inner();
+    let customers = state.customers.lock().unwrap();
+    match customers.iter().find(|c| c.id == customer_id) {
+        Some(customer) => HttpResponse::Ok().json(customer),
+        None => HttpResponse::NotFound().json(json!({ "error": "Customer not found" })),
+    }
+}
+
+pub async fn create_customer(
+    customer_req: web::Json<CustomerCreate>,
+    state: web::Data<CustomerState>,
+    field_state: web::Data<CustomFieldState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let req = customer_req.into_inner();
+    let definitions = field_state.definitions.lock().unwrap().clone();
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "Customer name is required" }));
+    }
+    let validated = (|| {
+        Ok::<_, String>((
+            validate_email(&req.email)?,
+            validate_phone(req.phone.as_deref().unwrap_or_default())?,
+            validate_tax_id(req.tax_id.as_deref().unwrap_or_default())?,
+            validate_status(req.status.as_deref().unwrap_or("active"))?,
+            custom_fields::apply_values(&definitions, CustomFieldEntity::Customer, &BTreeMap::new(), &req.custom_fields)?,
+            custom_fields::normalize_tags(&req.tags)?,
+        ))
+    })();
+    let (email, phone, tax_id, status, values, tags) = match validated {
+        Ok(validated) => validated,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let mut customers = state.customers.lock().unwrap();
+    // O e-mail identifica o cliente, inclusive na importação de vendas
+    if customers.iter().any(|c| c.email.eq_ignore_ascii_case(&email)) {
+        return HttpResponse::Conflict().json(json!({ "error": format!("Email {} is already registered", email) }));
+    }
+
+    let now = Utc::now();
+    let customer = Customer {
+        id: Uuid::new_v4(),
+        company: optional_text(req.company).unwrap_or_else(|| name.clone()),
+        name,
+        email,
+        phone,
+        tax_id,
+        address: optional_text(req.address),
+        city: optional_text(req.city),
+        country: optional_text(req.country),
+        segment: optional_text(req.segment),
+        lifetime_value: Money::zero(rates.base.clone()),
+        predicted_clv: None,
+        status,
+        custom_fields: values,
+        tags,
+        anonymized_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+    customers.push(customer.clone());
+    drop(customers);
+
+    // Possíveis duplicatas vão para a fila de revisão
+    state.detect_duplicates(&[customer.id]);
+
+    HttpResponse::Created().json(customer)
+}
+
+pub async fn update_customer(
+    path: web::Path<Uuid>,
+    customer_req: web::Json<CustomerUpdate>,
+    state: web::Data<CustomerState>,
+    field_state: web::Data<CustomFieldState>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+    let req = customer_req.into_inner();
+    let definitions = field_state.definitions.lock().unwrap().clone();
+
+    // Validação antes da trava: entrada inválida nunca chega a segurar a lista de clientes
+    let validated = (|| {
+        Ok::<_, String>((
+            req.email.as_deref().map(validate_email).transpose()?,
+            req.phone.as_deref().map(validate_phone).transpose()?,
+            req.tax_id.as_deref().map(validate_tax_id).transpose()?,
+            req.status.as_deref().map(validate_status).transpose()?,
+            req.tags.as_deref().map(custom_fields::normalize_tags).transpose()?,
+        ))
+    })();
+    let (email, phone, tax_id, status, tags) = match validated {
+        Ok(validated) => validated,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    if req.name.as_ref().is_some_and(|name| name.trim().is_empty()) {
+        return HttpResponse::BadRequest().json(json!({ "error": "Customer name is required" }));
+    }
+
+    let mut customers = state.customers.lock().unwrap();
+    if let Some(email) = &email {
+        if customers.iter().any(|c| c.id != customer_id && c.email.eq_ignore_ascii_case(email)) {
+            return HttpResponse::Conflict().json(json!({ "error": format!("Email {} is already registered", email) }));
+        }
+    }
+
+    let Some(customer) = customers.iter_mut().find(|c| c.id == customer_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Customer not found" }));
+    };
+    if customer.anonymized_at.is_some() {
+        return HttpResponse::Conflict().json(json!({ "error": "Customer data was erased and can no longer be edited" }));
+    }
+    let values = match custom_fields::apply_values(
+        &definitions,
+        CustomFieldEntity::Customer,
+        &customer.custom_fields,
+        &req.custom_fields.unwrap_or_default(),
+    ) {
+        Ok(values) => values,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    if let Some(name) = &req.name {
+        customer.name = name.trim().to_string();
+    }
+
+    if let Some(email) = email {
+        customer.email = email;
+    }
+    if let Some(phone) = phone {
+        customer.phone = phone;
+    }
+    if let Some(company) = optional_text(req.company) {
+        customer.company = company;
+    }
+    if let Some(tax_id) = tax_id {
+        customer.tax_id = tax_id;
+    }
+    if req.address.is_some() {
+        customer.address = optional_text(req.address);
+    }
+    if req.city.is_some() {
+        customer.city = optional_text(req.city);
+    }
+    if req.country.is_some() {
+        customer.country = optional_text(req.country);
+    }
+    if req.segment.is_some() {
+        customer.segment = optional_text(req.segment);
+    }
+    customer.custom_fields = values;
+    if let Some(tags) = tags {
+        customer.tags = tags;
+    }
+    let now = Utc::now();
+    let status_change = status.filter(|s| *s != customer.status).map(|status| CustomerStatusChange {
+        id: Uuid::new_v4(),
+        customer_id,
+        from: std::mem::replace(&mut customer.status, status.clone()),
+        to: status,
+        changed_at: now,
+    });
+    customer.updated_at = now;
+    let customer = customer.clone();
+    drop(customers);
+
+    if let Some(change) = status_change {
+        state.status_changes.lock().unwrap().push(change);
+    }
+
+    state.detect_duplicates(&[customer.id]);
+
+    HttpResponse::Ok().json(customer)
+}
+
+// Cliente com histórico não é apagado; deve ser marcado como inativo
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_customer(
+    path: web::Path<Uuid>,
+    state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    subscription_state: web::Data<SubscriptionState>,
+    quote_state: web::Data<QuoteState>,
+    search_state: web::Data<SearchState>,
+    product_state: web::Data<ProductState>,
+    payment_state: web::Data<PaymentState>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+    // O cadastro fica travado entre a verificação e a remoção; quem cria vendas
+    // ou converte cotações trava o cadastro primeiro, na mesma ordem
+    let mut customers = state.customers.lock().unwrap();
+    if !customers.iter().any(|c| c.id == customer_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Customer not found" }));
+    }
+    let referenced = sales_state.sales.lock().unwrap().iter().any(|s| s.customer_id == customer_id)
+        || subscription_state.subscriptions.lock().unwrap().iter().any(|s| s.customer_id == customer_id)
+        || quote_state.quotes.lock().unwrap().iter().any(|q| q.customer_id == customer_id);
+    if referenced {
+        return HttpResponse::Conflict().json(json!({
+            "error": "Customer has sales, subscriptions or quotes; set the status to inactive instead"
+        }));
+    }
+    customers.retain(|c| c.id != customer_id);
+    state.notes.lock().unwrap().retain(|n| n.customer_id != customer_id);
+    state.status_changes.lock().unwrap().retain(|c| c.customer_id != customer_id);
+    state.duplicates.lock().unwrap().retain(|d| d.customer_id != customer_id && d.duplicate_id != customer_id);
+    drop(customers);
+    search_state.refresh(&state, &product_state, &sales_state, &payment_state);
+
+    HttpResponse::NoContent().finish()
+}
+
+pub async fn get_customer_sales(
+    path: web::Path<Uuid>,
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+    if !state.customers.lock().unwrap().iter().any(|c| c.id == customer_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Customer not found" }));
+    }
+    let query = match ListQuery::parse::<Sale>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let sales: Vec<Sale> = sales_state.sales.lock().unwrap()
+        .iter()
+        .filter(|s| s.customer_id == customer_id)
+        .cloned()
+        .collect();
+    HttpResponse::Ok().json(query.apply(&sales))
+}
+
+pub async fn get_customer_notes(path: web::Path<Uuid>, state: web::Data<CustomerState>) -> impl Responder {
+    let customer_id = path.into_inner();
+    if !state.customers.lock().unwrap().iter().any(|c| c.id == customer_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Customer not found" }));
+    }
+    let mut notes: Vec<CustomerNote> = state.notes.lock().unwrap()
+        .iter()
+        .filter(|n| n.customer_id == customer_id)
+        .cloned()
+        .collect();
+    notes.sort_by_key(|n| Reverse(n.created_at));
+    HttpResponse::Ok().json(notes)
+}
+
+pub async fn create_customer_note(
+    path: web::Path<Uuid>,
+    note_req: web::Json<CustomerNoteCreate>,
+    state: web::Data<CustomerState>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+    let req = note_req.into_inner();
+    if req.body.trim().is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "Note body is required" }));
+    }
+    if !state.customers.lock().unwrap().iter().any(|c| c.id == customer_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Customer not found" }));
+    }
+
+    let note = CustomerNote {
+        id: Uuid::new_v4(),
+        customer_id,
+        author: optional_text(req.author),
+        body: req.body.trim().to_string(),
+        created_at: Utc::now(),
+    };
+    state.notes.lock().unwrap().push(note.clone());
+
+    HttpResponse::Created().json(note)
 }
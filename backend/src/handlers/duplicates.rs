@@ -0,0 +1,347 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use crate::handlers::clv::ClvState;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::payments::PaymentState;
+use crate::handlers::products::ProductState;
+use crate::handlers::promotions::PromotionState;
+use crate::handlers::quotes::QuoteState;
+use crate::handlers::sales::SalesState;
+use crate::handlers::search::SearchState;
+use crate::handlers::subscriptions::SubscriptionState;
+use crate::models::duplicate::{CustomerMerge, DuplicateCandidate, DuplicateStatus, MergeReassignment, MergeRequest};
+use crate::models::Customer;
+use chrono::Utc;
+use std::cmp::Reverse;
+use uuid::Uuid;
+
+// Estados com registros ligados a clientes
+pub struct CustomerRecords<'a> {
+    pub sales: &'a SalesState,
+    pub subscriptions: &'a SubscriptionState,
+    pub quotes: &'a QuoteState,
+    pub promotions: &'a PromotionState,
+}
+
+impl CustomerRecords<'_> {
+    // Move os registros de `from` para `to`; com `only`, apenas os ids listados
+    fn reassign(&self, from: Uuid, to: Uuid, only: Option<&MergeReassignment>) -> MergeReassignment {
+        let pick = |ids: Option<&Vec<Uuid>>, id: Uuid| ids.is_none_or(|ids| ids.contains(&id));
+        let mut moved = MergeReassignment::default();
+
+        for sale in self.sales.sales.lock().unwrap().iter_mut() {
+            if sale.customer_id == from && pick(only.map(|o| &o.sales), sale.id) {
+                sale.customer_id = to;
+                moved.sales.push(sale.id);
+            }
+        }
+        for subscription in self.subscriptions.subscriptions.lock().unwrap().iter_mut() {
+            if subscription.customer_id == from && pick(only.map(|o| &o.subscriptions), subscription.id) {
+                subscription.customer_id = to;
+                moved.subscriptions.push(subscription.id);
+            }
+        }
+        for movement in self.subscriptions.movements.lock().unwrap().iter_mut() {
+            if movement.customer_id == from && pick(only.map(|o| &o.mrr_movements), movement.id) {
+                movement.customer_id = to;
+                moved.mrr_movements.push(movement.id);
+            }
+        }
+        for quote in self.quotes.quotes.lock().unwrap().iter_mut() {
+            if quote.customer_id == from && pick(only.map(|o| &o.quotes), quote.id) {
+                quote.customer_id = to;
+                moved.quotes.push(quote.id);
+            }
+        }
+        for redemption in self.promotions.redemptions.lock().unwrap().iter_mut() {
+            if redemption.customer_id == from && pick(only.map(|o| &o.promotion_redemptions), redemption.id) {
+                redemption.customer_id = to;
+                moved.promotion_redemptions.push(redemption.id);
+            }
+        }
+        moved
+    }
+}
+
+// Preenche os campos vazios do sobrevivente com os do absorvido.
+// Devolve os campos preenchidos e as chaves personalizadas e tags acrescentadas
+fn fill_from(survivor: &mut Customer, merged: &Customer) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut filled = Vec::new();
+    if survivor.phone.is_empty() && !merged.phone.is_empty() {
+        survivor.phone = merged.phone.clone();
+        filled.push("phone".to_string());
+    }
+    if survivor.tax_id.is_none() && merged.tax_id.is_some() {
+        survivor.tax_id = merged.tax_id.clone();
+        filled.push("tax_id".to_string());
+    }
+    for (field, target, source) in [
+        ("address", &mut survivor.address, &merged.address),
+        ("city", &mut survivor.city, &merged.city),
+        ("country", &mut survivor.country, &merged.country),
+        ("segment", &mut survivor.segment, &merged.segment),
+    ] {
+        if target.is_none() && source.is_some() {
+            *target = source.clone();
+            filled.push(field.to_string());
+        }
+    }
+    // Campos personalizados ausentes e tags que o sobrevivente não tem
+    let mut added_fields = Vec::new();
+    for (key, value) in &merged.custom_fields {
+        if !survivor.custom_fields.contains_key(key) {
+            survivor.custom_fields.insert(key.clone(), value.clone());
+            added_fields.push(key.clone());
+        }
+    }
+    if !added_fields.is_empty() {
+        filled.push("custom_fields".to_string());
+    }
+    let mut added_tags = Vec::new();
+    for tag in &merged.tags {
+        if !survivor.tags.contains(tag) && !added_tags.contains(tag) {
+            added_tags.push(tag.clone());
+        }
+    }
+    if !added_tags.is_empty() {
+        survivor.tags.extend(added_tags.iter().cloned());
+        filled.push("tags".to_string());
+    }
+    (filled, added_fields, added_tags)
+}
+
+// Desfaz só o que a fusão trouxe; valores alterados depois da fusão são mantidos
+fn restore_filled(survivor: &mut Customer, merge: &CustomerMerge) {
+    let (before, merged) = (&merge.survivor_before, &merge.merged_customer);
+    for field in &merge.filled_fields {
+        match field.as_str() {
+            "phone" if survivor.phone == merged.phone => survivor.phone = before.phone.clone(),
+            "tax_id" if survivor.tax_id == merged.tax_id => survivor.tax_id = before.tax_id.clone(),
+            "address" if survivor.address == merged.address => survivor.address = before.address.clone(),
+            "city" if survivor.city == merged.city => survivor.city = before.city.clone(),
+            "country" if survivor.country == merged.country => survivor.country = before.country.clone(),
+            "segment" if survivor.segment == merged.segment => survivor.segment = before.segment.clone(),
+            _ => {}
+        }
+    }
+    for key in &merge.added_custom_fields {
+        if survivor.custom_fields.get(key) == merged.custom_fields.get(key) {
+            survivor.custom_fields.remove(key);
+        }
+    }
+    survivor.tags.retain(|tag| !merge.added_tags.contains(tag));
+}
+
+fn set_pair_status(state: &CustomerState, a: Uuid, b: Uuid, status: DuplicateStatus) {
+    let now = Utc::now();
+    for candidate in state.duplicates.lock().unwrap().iter_mut().filter(|d| d.involves(a, b)) {
+        candidate.status = status;
+        candidate.reviewed_at = (status != DuplicateStatus::Pending).then_some(now);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicateQuery {
+    pub status: Option<DuplicateStatus>,
+}
+
+// Fila de revisão, do par mais provável para o menos provável
+pub async fn get_duplicates(query: web::Query<DuplicateQuery>, state: web::Data<CustomerState>) -> impl Responder {
+    let status = query.status.unwrap_or(DuplicateStatus::Pending);
+    let customers = state.customers.lock().unwrap();
+    let duplicates = state.duplicates.lock().unwrap();
+    let find = |id: Uuid| customers.iter().find(|c| c.id == id);
+
+    let mut queue: Vec<_> = duplicates
+        .iter()
+        .filter(|d| d.status == status)
+        .filter_map(|d| {
+            let (customer, duplicate) = (find(d.customer_id), find(d.duplicate_id));
+            // Pendentes com um dos clientes já removido ou absorvido saem da fila
+            if status == DuplicateStatus::Pending && (customer.is_none() || duplicate.is_none()) {
+                return None;
+            }
+            Some((d, customer, duplicate))
+        })
+        .collect();
+    queue.sort_by(|a, b| b.0.score.cmp(&a.0.score).then(b.0.detected_at.cmp(&a.0.detected_at)));
+
+    let queue: Vec<_> = queue
+        .into_iter()
+        .map(|(candidate, customer, duplicate)| json!({
+            "candidate": candidate,
+            "customer": customer,
+            "duplicate": duplicate
+        }))
+        .collect();
+    HttpResponse::Ok().json(queue)
+}
+
+// Compara todos os clientes entre si (útil após importações)
+pub async fn scan_duplicates(state: web::Data<CustomerState>) -> impl Responder {
+    let ids: Vec<Uuid> = state.customers.lock().unwrap().iter().map(|c| c.id).collect();
+    let found: Vec<DuplicateCandidate> = state.detect_duplicates(&ids);
+    HttpResponse::Ok().json(json!({
+        "scanned_customers": ids.len(),
+        "new_candidates": found.len(),
+        "candidates": found
+    }))
+}
+
+pub async fn dismiss_duplicate(path: web::Path<Uuid>, state: web::Data<CustomerState>) -> impl Responder {
+    let candidate_id = path.into_inner();
+    let mut duplicates = state.duplicates.lock().unwrap();
+    let Some(candidate) = duplicates.iter_mut().find(|d| d.id == candidate_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Duplicate candidate not found" }));
+    };
+    if candidate.status != DuplicateStatus::Pending {
+        return HttpResponse::Conflict().json(json!({ "error": "Duplicate candidate was already reviewed" }));
+    }
+    candidate.status = DuplicateStatus::Dismissed;
+    candidate.reviewed_at = Some(Utc::now());
+
+    HttpResponse::Ok().json(&*candidate)
+}
+
+// Absorve `merged_id` em `survivor_id`: move vendas, assinaturas, cotações, resgates e notas e recalcula LTV/CLV
+#[allow(clippy::too_many_arguments)]
+pub async fn merge_customers(
+    merge_req: web::Json<MergeRequest>,
+    state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    subscription_state: web::Data<SubscriptionState>,
+    quote_state: web::Data<QuoteState>,
+    promotion_state: web::Data<PromotionState>,
+    clv_state: web::Data<ClvState>,
+    search_state: web::Data<SearchState>,
+    product_state: web::Data<ProductState>,
+    payment_state: web::Data<PaymentState>,
+) -> impl Responder {
+    let req = merge_req.into_inner();
+    if req.survivor_id == req.merged_id {
+        return HttpResponse::BadRequest().json(json!({ "error": "Cannot merge a customer into itself" }));
+    }
+
+    let (survivor_before, merged_customer, (filled_fields, added_custom_fields, added_tags)) = {
+        let mut customers = state.customers.lock().unwrap();
+        let Some(merged) = customers.iter().find(|c| c.id == req.merged_id).cloned() else {
+            return HttpResponse::NotFound().json(json!({ "error": format!("Customer {} not found", req.merged_id) }));
+        };
+        let Some(survivor) = customers.iter_mut().find(|c| c.id == req.survivor_id) else {
+            return HttpResponse::NotFound().json(json!({ "error": format!("Customer {} not found", req.survivor_id) }));
+        };
+        let before = survivor.clone();
+        let filled = fill_from(survivor, &merged);
+        survivor.updated_at = Utc::now();
+        customers.retain(|c| c.id != req.merged_id);
+        (before, merged, filled)
+    };
+
+    let records = CustomerRecords {
+        sales: &sales_state,
+        subscriptions: &subscription_state,
+        quotes: &quote_state,
+        promotions: &promotion_state,
+    };
+    let mut reassigned = records.reassign(req.merged_id, req.survivor_id, None);
+    for note in state.notes.lock().unwrap().iter_mut().filter(|n| n.customer_id == req.merged_id) {
+        note.customer_id = req.survivor_id;
+        reassigned.notes.push(note.id);
+    }
+    set_pair_status(&state, req.survivor_id, req.merged_id, DuplicateStatus::Merged);
+
+    let merge = CustomerMerge {
+        id: Uuid::new_v4(),
+        survivor_id: req.survivor_id,
+        merged_id: req.merged_id,
+        survivor_before,
+        merged_customer,
+        filled_fields,
+        added_custom_fields,
+        added_tags,
+        reassigned,
+        merged_by: req.merged_by.map(|by| by.trim().to_string()).filter(|by| !by.is_empty()),
+        merged_at: Utc::now(),
+        undone_at: None,
+    };
+    state.merges.lock().unwrap().push(merge.clone());
+
+    // LTV e previsões passam a refletir o histórico unificado no próximo recálculo, fora da requisição
+    clv_state.request_refresh();
+    search_state.refresh(&state, &product_state, &sales_state, &payment_state);
+
+    HttpResponse::Created().json(merge)
+}
+
+pub async fn get_merges(state: web::Data<CustomerState>) -> impl Responder {
+    let mut merges = state.merges.lock().unwrap().clone();
+    merges.sort_by_key(|m| Reverse(m.merged_at));
+    HttpResponse::Ok().json(merges)
+}
+
+// Recria o cliente absorvido e devolve a ele exatamente os registros movidos na fusão
+#[allow(clippy::too_many_arguments)]
+pub async fn undo_merge(
+    path: web::Path<Uuid>,
+    state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    subscription_state: web::Data<SubscriptionState>,
+    quote_state: web::Data<QuoteState>,
+    promotion_state: web::Data<PromotionState>,
+    clv_state: web::Data<ClvState>,
+    search_state: web::Data<SearchState>,
+    product_state: web::Data<ProductState>,
+    payment_state: web::Data<PaymentState>,
+) -> impl Responder {
+    let merge_id = path.into_inner();
+    let Some(merge) = state.merges.lock().unwrap().iter().find(|m| m.id == merge_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Merge not found" }));
+    };
+    if merge.undone_at.is_some() {
+        return HttpResponse::Conflict().json(json!({ "error": "Merge was already undone" }));
+    }
+
+    {
+        let mut customers = state.customers.lock().unwrap();
+        let email = &merge.merged_customer.email;
+        if customers.iter().any(|c| c.email.eq_ignore_ascii_case(email)) {
+            return HttpResponse::Conflict().json(json!({
+                "error": format!("Email {} now belongs to another customer", email)
+            }));
+        }
+        let Some(survivor) = customers.iter_mut().find(|c| c.id == merge.survivor_id) else {
+            return HttpResponse::Conflict().json(json!({
+                "error": "Surviving customer no longer exists; undo the later merge first"
+            }));
+        };
+        restore_filled(survivor, &merge);
+        survivor.updated_at = Utc::now();
+        customers.push(merge.merged_customer.clone());
+    }
+
+    let records = CustomerRecords {
+        sales: &sales_state,
+        subscriptions: &subscription_state,
+        quotes: &quote_state,
+        promotions: &promotion_state,
+    };
+    records.reassign(merge.survivor_id, merge.merged_id, Some(&merge.reassigned));
+    for note in state.notes.lock().unwrap().iter_mut().filter(|n| merge.reassigned.notes.contains(&n.id)) {
+        note.customer_id = merge.merged_id;
+    }
+    set_pair_status(&state, merge.survivor_id, merge.merged_id, DuplicateStatus::Pending);
+
+    let undone = {
+        let mut merges = state.merges.lock().unwrap();
+        let Some(stored) = merges.iter_mut().find(|m| m.id == merge_id) else {
+            return HttpResponse::NotFound().json(json!({ "error": "Merge not found" }));
+        };
+        stored.undone_at = Some(Utc::now());
+        stored.clone()
+    };
+    clv_state.request_refresh();
+    search_state.refresh(&state, &product_state, &sales_state, &payment_state);
+
+    HttpResponse::Ok().json(undone)
+}
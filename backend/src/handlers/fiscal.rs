@@ -0,0 +1,108 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::fiscal::{FiscalIssuer, NfeDocument};
+use crate::models::money::ExchangeRates;
+use crate::models::SaleStatus;
+use crate::services::nfe;
+use chrono::Utc;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub struct FiscalState {
+    pub issuer: Option<FiscalIssuer>,
+    pub documents: Mutex<Vec<NfeDocument>>,
+}
+
+impl FiscalState {
+    pub fn new(issuer: Option<FiscalIssuer>) -> Self {
+        FiscalState {
+            issuer,
+            documents: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn find_by_sale(&self, sale_id: Uuid) -> Option<NfeDocument> {
+        self.documents.lock().unwrap().iter().find(|d| d.sale_id == sale_id).cloned()
+    }
+}
+
+pub async fn issue_nfe(
+    path: web::Path<Uuid>,
+    state: web::Data<FiscalState>,
+    sales_state: web::Data<SalesState>,
+    customer_state: web::Data<CustomerState>,
+    product_state: web::Data<ProductState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let sale_id = path.into_inner();
+
+    let Some(issuer) = &state.issuer else {
+        return HttpResponse::ServiceUnavailable().json(json!({ "error": "Fiscal issuer is not configured" }));
+    };
+    if rates.base.code() != "BRL" {
+        return HttpResponse::Conflict().json(json!({ "error": "NF-e requires BRL as the base currency" }));
+    }
+
+    let Some(sale) = sales_state.sales.lock().unwrap().iter().find(|s| s.id == sale_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Sale not found" }));
+    };
+    // Venda totalmente reembolsada não tem mais operação a documentar
+    if !matches!(sale.status, SaleStatus::Completed | SaleStatus::PartiallyRefunded) {
+        return HttpResponse::Conflict().json(json!({
+            "error": format!("Sale with status '{}' cannot be invoiced", sale.status.as_str())
+        }));
+    }
+
+    let Some(customer) = customer_state.customers.lock().unwrap().iter().find(|c| c.id == sale.customer_id).cloned() else {
+        return HttpResponse::Conflict().json(json!({ "error": format!("Customer {} not found", sale.customer_id) }));
+    };
+    let products = product_state.products.lock().unwrap().clone();
+
+    // O lock cobre numeração e gravação para manter a série sem buracos
+    let mut documents = state.documents.lock().unwrap();
+    if let Some(existing) = documents.iter().find(|d| d.sale_id == sale_id) {
+        return HttpResponse::Ok().json(existing);
+    }
+    let number = documents.iter().map(|d| d.number).max().unwrap_or(0) + 1;
+
+    match nfe::generate(issuer, &sale, &customer, &products, number, Utc::now()) {
+        Ok(document) => {
+            documents.push(document.clone());
+            HttpResponse::Created().json(document)
+        }
+        Err(errors) => HttpResponse::UnprocessableEntity().json(json!({
+            "error": "NF-e failed validation",
+            "errors": errors
+        })),
+    }
+}
+
+pub async fn get_nfe(
+    path: web::Path<Uuid>,
+    state: web::Data<FiscalState>,
+) -> impl Responder {
+    match state.find_by_sale(path.into_inner()) {
+        Some(document) => HttpResponse::Ok().json(document),
+        None => HttpResponse::NotFound().json(json!({ "error": "NF-e not found" })),
+    }
+}
+
+pub async fn get_nfe_xml(
+    path: web::Path<Uuid>,
+    state: web::Data<FiscalState>,
+) -> impl Responder {
+    let Some(document) = state.find_by_sale(path.into_inner()) else {
+        return HttpResponse::NotFound().json(json!({ "error": "NF-e not found" }));
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/xml")
+        .insert_header((
+            "Content-Disposition",
+            format!("inline; filename=\"NFe{}.xml\"", document.access_key),
+        ))
+        .body(document.xml)
+}
@@ -0,0 +1,739 @@
+use actix_multipart::Multipart;
+use actix_web::{web, HttpResponse, Responder};
+use calamine::{open_workbook_from_rs, Data, DataType, Reader, Xlsx};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::str::FromStr;
+use std::sync::Mutex;
+use uuid::Uuid;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::money::{Currency, ExchangeRates, Money, MAX_UNIT_PRICE};
+use crate::models::tax::TaxTable;
+use crate::models::{
+    ImportJob, ImportRowError, ImportStatus, InvoiceCustomer, PaymentMethod, ProductSnapshot, Sale, SaleItem, SaleSnapshot,
+    SaleStatus, SalesImportMapping,
+};
+
+// Limite dos campos de texto do formulário (`mapping`, `dry_run`)
+const MAX_FIELD_BYTES: usize = 64 * 1024;
+
+pub struct ImportState {
+    pub jobs: Mutex<Vec<ImportJob>>,
+    // Tamanho máximo do arquivo enviado; o upload é lido em memória antes do processamento
+    pub max_file_bytes: usize,
+}
+
+impl ImportState {
+    pub fn new(max_file_bytes: usize) -> Self {
+        ImportState {
+            jobs: Mutex::new(Vec::new()),
+            max_file_bytes,
+        }
+    }
+
+    // Tolera lock envenenado para ainda conseguir marcar o job como falho depois de um pânico
+    fn update(&self, job_id: Uuid, change: impl FnOnce(&mut ImportJob)) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(job) = jobs.iter_mut().find(|j| j.id == job_id) {
+            change(job);
+        }
+    }
+
+    fn fail(&self, job_id: Uuid, message: String) {
+        self.update(job_id, |job| {
+            job.status = ImportStatus::Failed;
+            job.message = Some(message);
+            job.finished_at = Some(Utc::now());
+        });
+    }
+}
+
+// Estados usados pelo processamento em segundo plano
+#[derive(Clone)]
+struct ImportContext {
+    imports: web::Data<ImportState>,
+    sales: web::Data<SalesState>,
+    customers: web::Data<CustomerState>,
+    products: web::Data<ProductState>,
+    rates: web::Data<ExchangeRates>,
+    taxes: web::Data<TaxTable>,
+}
+
+enum FileFormat {
+    Csv,
+    Xlsx,
+}
+
+impl FileFormat {
+    fn detect(file_name: &str, bytes: &[u8]) -> Self {
+        let lower = file_name.to_lowercase();
+        if lower.ends_with(".xlsx") || (!lower.ends_with(".csv") && bytes.starts_with(b"PK")) {
+            FileFormat::Xlsx
+        } else {
+            FileFormat::Csv
+        }
+    }
+}
+
+type RowIter = Box<dyn Iterator<Item = Result<Vec<String>, String>> + Send>;
+
+// Abre o arquivo como um iterador de linhas; o total só é conhecido de antemão no XLSX
+fn open_rows(format: FileFormat, bytes: Vec<u8>) -> Result<(RowIter, Option<usize>), String> {
+    match format {
+        FileFormat::Csv => {
+            // Planilhas exportadas em pt-BR costumam usar ';' como separador
+            let first_line = bytes.split(|b| *b == b'\n').next().unwrap_or_default();
+            let semicolons = first_line.iter().filter(|b| **b == b';').count();
+            let commas = first_line.iter().filter(|b| **b == b',').count();
+            let delimiter = if semicolons > commas { b';' } else { b',' };
+
+            let records = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .delimiter(delimiter)
+                .from_reader(Cursor::new(bytes))
+                .into_records()
+                .map(|record| {
+                    record
+                        .map(|r| r.iter().map(str::to_string).collect())
+                        .map_err(|e| e.to_string())
+                });
+            Ok((Box::new(records), None))
+        }
+        FileFormat::Xlsx => {
+            let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes))
+                .map_err(|e| format!("Invalid XLSX file: {}", e))?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or_else(|| "Workbook has no sheets".to_string())?
+                .map_err(|e| format!("Invalid XLSX file: {}", e))?;
+
+            let rows: Vec<Vec<String>> = range
+                .rows()
+                .map(|row| row.iter().map(cell_to_string).collect())
+                .collect();
+            let total = rows.len().saturating_sub(1);
+            Ok((Box::new(rows.into_iter().map(Ok)), Some(total)))
+        }
+    }
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::Float(value) if value.fract() == 0.0 => format!("{}", *value as i64),
+        Data::DateTime(_) | Data::DateTimeIso(_) => cell
+            .as_datetime()
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default(),
+        _ => cell.to_string().trim().to_string(),
+    }
+}
+
+// Milhar em grupos de 3 dígitos: "1", "12" ou "123" seguido de grupos de exatamente 3
+fn valid_grouping(integer: &str, separator: char) -> bool {
+    let mut groups = integer.split(separator);
+    let first = groups.next().unwrap_or_default();
+    let digits = |group: &str| group.chars().all(|c| c.is_ascii_digit());
+    (1..=3).contains(&first.len()) && digits(first) && !first.starts_with('0') && groups.all(|g| g.len() == 3 && digits(g))
+}
+
+// Aceita "1234.56", "1234,56", "1.234,56", "1,234.56", "1.234.567" e "1,234,567"; o separador decimal é
+// o último "." ou "," que aparece. Um único separador seguido de exatamente 3 dígitos ("1,234" ou "1.234")
+// pode ser milhar ou decimal, então é recusado
+fn parse_decimal(raw: &str) -> Result<Decimal, String> {
+    let raw = raw.trim().trim_start_matches("R$").trim();
+    let (sign, digits) = match raw.strip_prefix('-') {
+        Some(rest) => ("-", rest.trim()),
+        None => ("", raw),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | ',')) {
+        return Err("not a number".to_string());
+    }
+
+    let last = digits.rfind(['.', ',']);
+    let normalized = match last {
+        None => digits.to_string(),
+        Some(position) => {
+            let decimal = digits[position..].chars().next().unwrap_or('.');
+            let thousands = if decimal == '.' { ',' } else { '.' };
+            let (integer, fraction) = (&digits[..position], &digits[position + 1..]);
+            let repeated = integer.contains(decimal);
+            if repeated || integer.contains(thousands) {
+                // "1.234.567": só separadores de milhar; "1.234,56": milhar e decimal
+                let (integer, fraction) = if repeated { (digits, "") } else { (integer, fraction) };
+                let separator = if repeated { decimal } else { thousands };
+                if !valid_grouping(integer, separator) {
+                    return Err("inconsistent thousands separators".to_string());
+                }
+                let integer = integer.replace(separator, "");
+                if fraction.is_empty() { integer } else { format!("{}.{}", integer, fraction) }
+            } else if fraction.len() == 3 && !integer.is_empty() && !integer.starts_with('0') {
+                return Err(format!("ambiguous: '{}' may be a thousands or a decimal separator", decimal));
+            } else {
+                format!("{}.{}", integer, fraction)
+            }
+        }
+    };
+    Decimal::from_str(&format!("{}{}", sign, normalized)).map_err(|_| "not a number".to_string())
+}
+
+fn parse_date(raw: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(raw) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S") {
+        return Some(time.and_utc());
+    }
+    ["%Y-%m-%d", "%d/%m/%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+struct Columns {
+    customer_email: usize,
+    sku: usize,
+    quantity: usize,
+    unit_price: Option<usize>,
+    discount: Option<usize>,
+    currency: Option<usize>,
+    date: Option<usize>,
+    payment_method: Option<usize>,
+    status: Option<usize>,
+    order_ref: Option<usize>,
+}
+
+impl Columns {
+    fn resolve(header: &[String], mapping: &SalesImportMapping) -> Result<Self, String> {
+        let find = |name: &str| -> Result<usize, String> {
+            header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| format!("Column '{}' not found in file header", name))
+        };
+        let find_optional = |name: &Option<String>| -> Result<Option<usize>, String> {
+            name.as_deref().map(find).transpose()
+        };
+
+        Ok(Columns {
+            customer_email: find(&mapping.customer_email)?,
+            sku: find(&mapping.sku)?,
+            quantity: find(&mapping.quantity)?,
+            unit_price: find_optional(&mapping.unit_price)?,
+            discount: find_optional(&mapping.discount)?,
+            currency: find_optional(&mapping.currency)?,
+            date: find_optional(&mapping.date)?,
+            payment_method: find_optional(&mapping.payment_method)?,
+            status: find_optional(&mapping.status)?,
+            order_ref: find_optional(&mapping.order_ref)?,
+        })
+    }
+}
+
+struct ImportedLine {
+    order_ref: Option<String>,
+    customer_id: Uuid,
+    customer: InvoiceCustomer,
+    product: ProductSnapshot,
+    currency: Currency,
+    exchange_rate: Decimal,
+    created_at: DateTime<Utc>,
+    payment_method: PaymentMethod,
+    status: SaleStatus,
+    item: SaleItem,
+}
+
+// Valida uma linha com as mesmas regras de SaleCreate
+fn validate_row(
+    row: usize,
+    cells: &[String],
+    columns: &Columns,
+    mapping: &SalesImportMapping,
+    ctx: &ImportContext,
+) -> Result<ImportedLine, Vec<ImportRowError>> {
+    let mut errors = Vec::new();
+    let cell = |index: usize| cells.get(index).map(|c| c.trim()).unwrap_or("");
+    let optional = |index: Option<usize>| index.map(cell).filter(|c| !c.is_empty());
+    let mut error = |column: &str, message: String| {
+        errors.push(ImportRowError { row, column: Some(column.to_string()), message });
+    };
+
+    let email = cell(columns.customer_email).to_lowercase();
+    let customer = ctx.customers.customers.lock().unwrap()
+        .iter()
+        .find(|c| c.email.to_lowercase() == email)
+        .map(InvoiceCustomer::from);
+    let customer_id = customer.as_ref().map(|c| c.id);
+    if customer_id.is_none() {
+        error(&mapping.customer_email, format!("No customer with email '{}'", email));
+    }
+
+    let sku = cell(columns.sku);
+    let product = ctx.products.products.lock().unwrap()
+        .iter()
+        .find(|p| p.sku.eq_ignore_ascii_case(sku))
+        .cloned();
+    if product.is_none() {
+        error(&mapping.sku, format!("No product with SKU '{}'", sku));
+    }
+
+    let quantity = cell(columns.quantity).parse::<i32>().ok();
+    if quantity.is_none() {
+        error(&mapping.quantity, format!("Invalid quantity '{}'", cell(columns.quantity)));
+    }
+
+    let currency = match optional(columns.currency) {
+        Some(code) => Currency::new(code).ok(),
+        None => Some(ctx.rates.base.clone()),
+    };
+    let exchange_rate = currency.as_ref().and_then(|c| ctx.rates.rate(c));
+    if exchange_rate.is_none() {
+        let column = mapping.currency.as_deref().unwrap_or("currency");
+        error(column, format!("Unsupported currency '{}'", optional(columns.currency).unwrap_or_default()));
+    }
+
+    let unit_price = match optional(columns.unit_price) {
+        Some(raw) => {
+            parse_decimal(raw)
+                .and_then(|price| {
+                    if price > MAX_UNIT_PRICE { Err(format!("exceeds {}", MAX_UNIT_PRICE)) } else { Ok(price) }
+                })
+                .map_err(|reason| {
+                    error(mapping.unit_price.as_deref().unwrap_or_default(), format!("Invalid unit price '{}': {}", raw, reason))
+                })
+                .ok()
+        }
+        None => match (&product, &currency) {
+            (Some(product), Some(currency)) => ctx.rates.convert(&product.price, currency).map(|p| p.amount),
+            _ => None,
+        },
+    };
+
+    let discount = match optional(columns.discount) {
+        Some(raw) => {
+            parse_decimal(raw)
+                .map_err(|reason| {
+                    error(mapping.discount.as_deref().unwrap_or_default(), format!("Invalid discount '{}': {}", raw, reason))
+                })
+                .ok()
+        }
+        None => Some(Decimal::ZERO),
+    };
+
+    let created_at = match optional(columns.date) {
+        Some(raw) => {
+            let date = parse_date(raw);
+            if date.is_none() {
+                error(mapping.date.as_deref().unwrap_or_default(), format!("Invalid date '{}'", raw));
+            }
+            date
+        }
+        None => Some(Utc::now()),
+    };
+
+    // Importação é de histórico: apenas vendas concluídas ou que falharam
+    let status = match optional(columns.status).map(str::to_lowercase).as_deref() {
+        None | Some("completed") => Some(SaleStatus::Completed),
+        Some("failed") => Some(SaleStatus::Failed),
+        Some(other) => {
+            error(mapping.status.as_deref().unwrap_or_default(), format!("Unsupported status '{}'", other));
+            None
+        }
+    };
+
+    let payment_method = match optional(columns.payment_method) {
+        None => Some(PaymentMethod::Other),
+        Some(raw) => match PaymentMethod::parse(raw) {
+            Ok(method) => Some(method),
+            Err(message) => {
+                error(mapping.payment_method.as_deref().unwrap_or_default(), message);
+                None
+            }
+        },
+    };
+
+    let item = match (&product, quantity, unit_price, discount) {
+        (Some(product), Some(quantity), Some(unit_price), Some(discount)) => {
+            match SaleItem::new(product.id, quantity, unit_price, discount) {
+                // Valores históricos são o que foi cobrado: o imposto é extraído, não somado
+                Ok(mut item) => {
+                    let taxes = ctx.taxes.calculate(item.line_total, &product.category, &ctx.taxes.default_region, true);
+                    item.apply_taxes(taxes, true);
+                    Some(item)
+                }
+                Err(message) => {
+                    errors.push(ImportRowError { row, column: None, message });
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    match (customer, product, currency, exchange_rate, created_at, status, payment_method, item) {
+        (
+            Some(customer),
+            Some(product),
+            Some(currency),
+            Some(exchange_rate),
+            Some(created_at),
+            Some(status),
+            Some(payment_method),
+            Some(item),
+        ) if errors.is_empty() => {
+            Ok(ImportedLine {
+                order_ref: optional(columns.order_ref).map(str::to_string),
+                customer_id: customer.id,
+                customer,
+                product: ProductSnapshot::from(&product),
+                currency,
+                exchange_rate,
+                created_at,
+                payment_method,
+                status,
+                item,
+            })
+        }
+        _ => Err(errors),
+    }
+}
+
+fn run_import(job_id: Uuid, format: FileFormat, bytes: Vec<u8>, mapping: SalesImportMapping, ctx: ImportContext) {
+    ctx.imports.update(job_id, |job| job.status = ImportStatus::Running);
+
+    let (mut rows, total_rows) = match open_rows(format, bytes) {
+        Ok(rows) => rows,
+        Err(err) => return ctx.imports.fail(job_id, err),
+    };
+    ctx.imports.update(job_id, |job| job.total_rows = total_rows);
+
+    let header = match rows.next() {
+        Some(Ok(header)) => header,
+        Some(Err(err)) => return ctx.imports.fail(job_id, err),
+        None => return ctx.imports.fail(job_id, "File is empty".to_string()),
+    };
+    let columns = match Columns::resolve(&header, &mapping) {
+        Ok(columns) => columns,
+        Err(err) => return ctx.imports.fail(job_id, err),
+    };
+
+    // Linhas agrupadas por referência do pedido (ou uma venda por linha)
+    let mut orders: Vec<Vec<(usize, ImportedLine)>> = Vec::new();
+    let mut order_index: HashMap<String, usize> = HashMap::new();
+
+    // A linha 1 é o cabeçalho
+    for (index, record) in rows.enumerate() {
+        let row = index + 2;
+        let result = match record {
+            Ok(cells) if cells.iter().all(|c| c.trim().is_empty()) => continue,
+            Ok(cells) => validate_row(row, &cells, &columns, &mapping, &ctx),
+            Err(message) => Err(vec![ImportRowError { row, column: None, message }]),
+        };
+
+        match result {
+            Ok(line) => {
+                match line.order_ref.clone().and_then(|key| order_index.get(&key).copied()) {
+                    Some(position) => orders[position].push((row, line)),
+                    None => {
+                        if let Some(key) = line.order_ref.clone() {
+                            order_index.insert(key, orders.len());
+                        }
+                        orders.push(vec![(row, line)]);
+                    }
+                }
+                ctx.imports.update(job_id, |job| {
+                    job.processed_rows += 1;
+                    job.valid_rows += 1;
+                });
+            }
+            Err(errors) => ctx.imports.update(job_id, |job| {
+                job.processed_rows += 1;
+                job.error_rows += 1;
+                job.errors.extend(errors);
+            }),
+        }
+    }
+
+    let mut sales = Vec::new();
+    let mut order_errors = Vec::new();
+
+    for lines in orders {
+        let (_, first) = &lines[0];
+        let mismatched: Vec<usize> = lines
+            .iter()
+            .filter(|(_, line)| line.customer_id != first.customer_id || line.currency != first.currency)
+            .map(|(row, _)| *row)
+            .collect();
+
+        if !mismatched.is_empty() {
+            for row in mismatched {
+                order_errors.push(ImportRowError {
+                    row,
+                    column: mapping.order_ref.clone(),
+                    message: "All rows of an order must share customer and currency".to_string(),
+                });
+            }
+            continue;
+        }
+
+        let items: Vec<SaleItem> = lines.iter().map(|(_, line)| line.item.clone()).collect();
+        let mut snapshot = SaleSnapshot { customer: first.customer.clone(), products: Vec::new() };
+        for (_, line) in &lines {
+            snapshot.add_product(line.product.clone());
+        }
+        sales.push(Sale {
+            id: Uuid::new_v4(),
+            customer_id: first.customer_id,
+            amount: Sale::total_from(&items, first.currency.clone()),
+            refunded_amount: Money::zero(first.currency.clone()),
+            exchange_rate: first.exchange_rate,
+            tax_region: ctx.taxes.default_region.clone(),
+            prices_include_tax: true,
+            promotions: Vec::new(),
+            subscription_id: None,
+            notes: first.order_ref.as_ref().map(|order| format!("Imported order {}", order)),
+            snapshot,
+            items,
+            status: first.status,
+            payment_method: first.payment_method,
+            created_at: first.created_at,
+        });
+    }
+
+    let dry_run = ctx.imports.jobs.lock().unwrap()
+        .iter()
+        .find(|j| j.id == job_id)
+        .map(|j| j.dry_run)
+        .unwrap_or(true);
+
+    let valid_sales = sales.len();
+    let sales_created = if dry_run {
+        0
+    } else {
+        // Vendas históricas não reservam estoque
+        ctx.sales.sales.lock().unwrap().extend(sales);
+        valid_sales
+    };
+
+    ctx.imports.update(job_id, |job| {
+        job.error_rows += order_errors.len();
+        job.valid_rows -= order_errors.len();
+        job.errors.extend(order_errors);
+        job.errors.sort_by_key(|e| e.row);
+        job.valid_sales = valid_sales;
+        job.sales_created = sales_created;
+        job.status = ImportStatus::Completed;
+        job.finished_at = Some(Utc::now());
+    });
+}
+
+pub async fn import_sales(
+    mut payload: Multipart,
+    import_state: web::Data<ImportState>,
+    sales_state: web::Data<SalesState>,
+    customer_state: web::Data<CustomerState>,
+    product_state: web::Data<ProductState>,
+    rates: web::Data<ExchangeRates>,
+    taxes: web::Data<TaxTable>,
+) -> impl Responder {
+    let mut file: Option<(String, Vec<u8>)> = None;
+    let mut mapping: Option<SalesImportMapping> = None;
+    let mut dry_run = false;
+
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err.to_string() })),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+        let file_name = field.content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .unwrap_or("upload")
+            .to_string();
+
+        // Para de ler assim que o campo passa do limite
+        let limit = if name == "file" { import_state.max_file_bytes } else { MAX_FIELD_BYTES };
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            match chunk {
+                Ok(chunk) if bytes.len() + chunk.len() > limit => {
+                    return HttpResponse::PayloadTooLarge().json(json!({
+                        "error": format!("Field '{}' exceeds the limit of {} bytes", name, limit)
+                    }));
+                }
+                Ok(chunk) => bytes.extend_from_slice(&chunk),
+                Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err.to_string() })),
+            }
+        }
+
+        match name.as_str() {
+            "file" => file = Some((file_name, bytes)),
+            "mapping" => match serde_json::from_slice(&bytes) {
+                Ok(parsed) => mapping = Some(parsed),
+                Err(err) => return HttpResponse::BadRequest().json(json!({
+                    "error": format!("Invalid mapping: {}", err)
+                })),
+            },
+            "dry_run" => dry_run = matches!(String::from_utf8_lossy(&bytes).trim(), "true" | "1"),
+            _ => {}
+        }
+    }
+
+    let Some((file_name, bytes)) = file else {
+        return HttpResponse::BadRequest().json(json!({ "error": "Missing 'file' field" }));
+    };
+    let Some(mapping) = mapping else {
+        return HttpResponse::BadRequest().json(json!({ "error": "Missing 'mapping' field" }));
+    };
+
+    let job = ImportJob {
+        id: Uuid::new_v4(),
+        file_name: file_name.clone(),
+        dry_run,
+        status: ImportStatus::Queued,
+        total_rows: None,
+        processed_rows: 0,
+        valid_rows: 0,
+        error_rows: 0,
+        valid_sales: 0,
+        sales_created: 0,
+        message: None,
+        errors: Vec::new(),
+        created_at: Utc::now(),
+        finished_at: None,
+    };
+    import_state.jobs.lock().unwrap().push(job.clone());
+
+    let ctx = ImportContext {
+        imports: import_state,
+        sales: sales_state,
+        customers: customer_state,
+        products: product_state,
+        rates,
+        taxes,
+    };
+    let format = FileFormat::detect(&file_name, &bytes);
+    let job_id = job.id;
+    let imports = ctx.imports.clone();
+    let worker = actix_web::rt::task::spawn_blocking(move || run_import(job_id, format, bytes, mapping, ctx));
+    // Se o processamento terminar em pânico, o job não pode ficar em `running` para sempre
+    actix_web::rt::spawn(async move {
+        if worker.await.is_err() {
+            imports.fail(job_id, "Import stopped unexpectedly".to_string());
+        }
+    });
+
+    HttpResponse::Accepted().json(json!({
+        "message": "Import started",
+        "job": job,
+        "status_url": format!("/api/v1/import/jobs/{}", job.id),
+        "errors_url": format!("/api/v1/import/jobs/{}/errors", job.id)
+    }))
+}
+
+pub async fn get_import_job(
+    path: web::Path<Uuid>,
+    state: web::Data<ImportState>,
+) -> impl Responder {
+    let job_id = path.into_inner();
+    let jobs = state.jobs.lock().unwrap();
+
+    match jobs.iter().find(|j| j.id == job_id) {
+        Some(job) => {
+            let progress = job.total_rows
+                .filter(|total| *total > 0)
+                .map(|total| job.processed_rows as f64 / total as f64 * 100.0);
+            HttpResponse::Ok().json(json!({
+                "job": job,
+                "progress": progress
+            }))
+        }
+        None => HttpResponse::NotFound().json(json!({ "error": "Import job not found" })),
+    }
+}
+
+// Relatório de erros por linha em CSV para download
+pub async fn get_import_errors(
+    path: web::Path<Uuid>,
+    state: web::Data<ImportState>,
+) -> impl Responder {
+    let job_id = path.into_inner();
+    let jobs = state.jobs.lock().unwrap();
+
+    let Some(job) = jobs.iter().find(|j| j.id == job_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Import job not found" }));
+    };
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let _ = writer.write_record(["row", "column", "message"]);
+    for error in &job.errors {
+        let _ = writer.write_record([
+            error.row.to_string(),
+            error.column.clone().unwrap_or_default(),
+            error.message.clone(),
+        ]);
+    }
+
+    match writer.into_inner() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((
+                "Content-Disposition",
+                format!("attachment; filename=\"import-{}-errors.csv\"", job.id),
+            ))
+            .body(body),
+        Err(err) => HttpResponse::InternalServerError().json(json!({ "error": err.to_string() })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_decimal;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn parses_plain_and_single_separator_decimals() {
+        assert_eq!(parse_decimal("1234"), Ok(dec!(1234)));
+        assert_eq!(parse_decimal("1234.56"), Ok(dec!(1234.56)));
+        assert_eq!(parse_decimal("1234,56"), Ok(dec!(1234.56)));
+        assert_eq!(parse_decimal("0,125"), Ok(dec!(0.125)));
+        assert_eq!(parse_decimal("R$ 19,9"), Ok(dec!(19.9)));
+        assert_eq!(parse_decimal("-5.5"), Ok(dec!(-5.5)));
+    }
+
+    #[test]
+    fn parses_pt_br_grouping() {
+        assert_eq!(parse_decimal("1.234,56"), Ok(dec!(1234.56)));
+        assert_eq!(parse_decimal("R$ 1.234.567,8"), Ok(dec!(1234567.8)));
+        assert_eq!(parse_decimal("1.234.567"), Ok(dec!(1234567)));
+    }
+
+    #[test]
+    fn parses_en_us_grouping() {
+        assert_eq!(parse_decimal("1,234.56"), Ok(dec!(1234.56)));
+        assert_eq!(parse_decimal("1,234,567.5"), Ok(dec!(1234567.5)));
+        assert_eq!(parse_decimal("1,234,567"), Ok(dec!(1234567)));
+    }
+
+    #[test]
+    fn rejects_ambiguous_and_malformed_values() {
+        assert!(parse_decimal("1,234").is_err());
+        assert!(parse_decimal("1.234").is_err());
+        assert!(parse_decimal("12,34,56").is_err());
+        assert!(parse_decimal("1.234.56").is_err());
+        assert!(parse_decimal("1,23.45").is_err());
+        assert!(parse_decimal("1.234,567.8").is_err());
+        assert!(parse_decimal("1,2.4,567").is_err());
+        assert!(parse_decimal("abc").is_err());
+        assert!(parse_decimal("").is_err());
+    }
+}
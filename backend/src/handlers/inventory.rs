@@ -0,0 +1,287 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::products::ProductState;
+use crate::models::inventory::{
+    StockLevel, StockMovement, StockMovementCreate, StockMovementKind, StockReservation,
+    StockTransferCreate, Warehouse, WarehouseCreate, WarehouseUpdate,
+};
+use crate::models::query::ListQuery;
+use crate::services::inventory::validate_location;
+use chrono::Utc;
+use uuid::Uuid;
+
+fn optional_text(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+// Saldo, reservado e disponível de cada produto, no total e por local
+pub async fn get_inventory(state: web::Data<ProductState>) -> impl Responder {
+    let ledger = state.ledger.lock().unwrap();
+    let products = state.products.lock().unwrap();
+    let mut levels: Vec<StockLevel> = products
+        .iter()
+        .map(|product| StockLevel {
+            product_id: product.id,
+            sku: product.sku.clone(),
+            name: product.name.clone(),
+            on_hand: ledger.on_hand(product.id, None),
+            reserved: ledger.reserved(product.id, None),
+            available: ledger.available(product.id, None),
+            locations: ledger.locations(product.id),
+        })
+        .collect();
+    levels.sort_by(|a, b| a.name.cmp(&b.name));
+    HttpResponse::Ok().json(levels)
+}
+
+pub async fn get_stock_movements(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<ProductState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<StockMovement>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let ledger = state.ledger.lock().unwrap();
+    HttpResponse::Ok().json(query.apply(&ledger.movements))
+}
+
+// Recebimentos e ajustes; vendas e devoluções são lançadas pelo próprio fluxo de venda e reembolso
+pub async fn create_stock_movement(
+    movement_req: web::Json<StockMovementCreate>,
+    state: web::Data<ProductState>,
+) -> impl Responder {
+    let req = movement_req.into_inner();
+    let reason = optional_text(req.reason);
+    match req.kind {
+        StockMovementKind::Receipt if req.quantity <= 0 => {
+            return HttpResponse::BadRequest().json(json!({ "error": "Receipt quantity must be greater than zero" }));
+        }
+        StockMovementKind::Receipt => {}
+        StockMovementKind::Adjustment if req.quantity == 0 => {
+            return HttpResponse::BadRequest().json(json!({ "error": "Adjustment quantity cannot be zero" }));
+        }
+        StockMovementKind::Adjustment if reason.is_none() => {
+            return HttpResponse::BadRequest().json(json!({ "error": "Adjustments require a reason" }));
+        }
+        StockMovementKind::Adjustment => {}
+        StockMovementKind::Transfer => {
+            return HttpResponse::BadRequest().json(json!({ "error": "Use /inventory/transfers to move stock between locations" }));
+        }
+        StockMovementKind::Sale | StockMovementKind::RefundReturn => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": format!("{} movements are recorded by sales and refunds", req.kind.as_str())
+            }));
+        }
+    }
+    // Sem local, vai para o depósito padrão; só depósitos ativos recebem entradas
+    let location = {
+        let ledger = state.ledger.lock().unwrap();
+        let code = req.location.clone().unwrap_or_else(|| ledger.default_location());
+        match ledger.check_location(&code, req.quantity > 0) {
+            Ok(location) => location,
+            Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+        }
+    };
+    if !state.products.lock().unwrap().iter().any(|p| p.id == req.product_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Product not found" }));
+    }
+
+    let movement = StockMovement {
+        id: Uuid::new_v4(),
+        product_id: req.product_id,
+        kind: req.kind,
+        location,
+        quantity: req.quantity,
+        reference_id: None,
+        reason,
+        created_by: optional_text(req.created_by),
+        created_at: Utc::now(),
+    };
+    match state.record_movements(vec![movement]) {
+        Ok(mut movements) => HttpResponse::Created().json(movements.remove(0)),
+        Err(err) => HttpResponse::Conflict().json(json!({ "error": err })),
+    }
+}
+
+// Saída de um local e entrada no outro, gravadas juntas com o mesmo `reference_id`
+pub async fn create_stock_transfer(
+    transfer_req: web::Json<StockTransferCreate>,
+    state: web::Data<ProductState>,
+) -> impl Responder {
+    let req = transfer_req.into_inner();
+    if req.quantity <= 0 {
+        return HttpResponse::BadRequest().json(json!({ "error": "Transfer quantity must be greater than zero" }));
+    }
+    // Um depósito inativo ainda pode ser esvaziado, mas não recebe transferências
+    let locations = {
+        let ledger = state.ledger.lock().unwrap();
+        (ledger.check_location(&req.from_location, false), ledger.check_location(&req.to_location, true))
+    };
+    let (from, to) = match locations {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(err), _) | (_, Err(err)) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    if from == to {
+        return HttpResponse::BadRequest().json(json!({ "error": "Source and destination locations must differ" }));
+    }
+    if !state.products.lock().unwrap().iter().any(|p| p.id == req.product_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Product not found" }));
+    }
+
+    let transfer_id = Uuid::new_v4();
+    let now = Utc::now();
+    let reason = optional_text(req.reason);
+    let created_by = optional_text(req.created_by);
+    let movement = |location: String, quantity: i32| StockMovement {
+        id: Uuid::new_v4(),
+        product_id: req.product_id,
+        kind: StockMovementKind::Transfer,
+        location,
+        quantity,
+        reference_id: Some(transfer_id),
+        reason: reason.clone(),
+        created_by: created_by.clone(),
+        created_at: now,
+    };
+    match state.record_movements(vec![movement(from, -req.quantity), movement(to, req.quantity)]) {
+        Ok(movements) => HttpResponse::Created().json(json!({ "transfer_id": transfer_id, "movements": movements })),
+        Err(err) => HttpResponse::Conflict().json(json!({ "error": err })),
+    }
+}
+
+pub async fn get_stock_reservations(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<ProductState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<StockReservation>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let ledger = state.ledger.lock().unwrap();
+    HttpResponse::Ok().json(query.apply(&ledger.reservations))
+}
+
+pub async fn get_warehouses(state: web::Data<ProductState>) -> impl Responder {
+    let ledger = state.ledger.lock().unwrap();
+    let mut warehouses = ledger.warehouses.clone();
+    warehouses.sort_by(|a, b| b.is_default.cmp(&a.is_default).then(a.code.cmp(&b.code)));
+    HttpResponse::Ok().json(warehouses)
+}
+
+pub async fn create_warehouse(
+    warehouse_req: web::Json<WarehouseCreate>,
+    state: web::Data<ProductState>,
+) -> impl Responder {
+    let req = warehouse_req.into_inner();
+    let code = match validate_location(&req.code) {
+        Ok(code) => code,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "Warehouse name is required" }));
+    }
+
+    let mut ledger = state.ledger.lock().unwrap();
+    if ledger.warehouse(&code).is_some() {
+        return HttpResponse::Conflict().json(json!({ "error": format!("Warehouse {} already exists", code) }));
+    }
+    let is_default = req.is_default.unwrap_or(false);
+    if is_default {
+        ledger.warehouses.iter_mut().for_each(|w| w.is_default = false);
+    }
+    let warehouse = Warehouse {
+        code,
+        name,
+        city: optional_text(req.city),
+        country: optional_text(req.country),
+        is_default,
+        is_active: true,
+        created_at: Utc::now(),
+    };
+    ledger.warehouses.push(warehouse.clone());
+
+    HttpResponse::Created().json(warehouse)
+}
+
+pub async fn update_warehouse(
+    path: web::Path<String>,
+    warehouse_req: web::Json<WarehouseUpdate>,
+    state: web::Data<ProductState>,
+) -> impl Responder {
+    let code = path.into_inner().trim().to_lowercase();
+    let req = warehouse_req.into_inner();
+    let mut ledger = state.ledger.lock().unwrap();
+    let Some(current) = ledger.warehouse(&code).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Warehouse not found" }));
+    };
+
+    // Sempre há exatamente um depósito padrão, e ele precisa estar ativo
+    let is_default = req.is_default.unwrap_or(current.is_default);
+    let is_active = req.is_active.unwrap_or(current.is_active);
+    if current.is_default && !is_default {
+        return HttpResponse::BadRequest().json(json!({ "error": "Mark another warehouse as default instead" }));
+    }
+    if is_default && !is_active {
+        return HttpResponse::Conflict().json(json!({ "error": "The default warehouse cannot be inactive" }));
+    }
+    if let Some(name) = &req.name {
+        if name.trim().is_empty() {
+            return HttpResponse::BadRequest().json(json!({ "error": "Warehouse name is required" }));
+        }
+    }
+
+    if is_default && !current.is_default {
+        ledger.warehouses.iter_mut().for_each(|w| w.is_default = false);
+    }
+    let Some(warehouse) = ledger.warehouses.iter_mut().find(|w| w.code == code) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Warehouse not found" }));
+    };
+    if let Some(name) = req.name {
+        warehouse.name = name.trim().to_string();
+    }
+    if req.city.is_some() {
+        warehouse.city = optional_text(req.city);
+    }
+    if req.country.is_some() {
+        warehouse.country = optional_text(req.country);
+    }
+    warehouse.is_default = is_default;
+    warehouse.is_active = is_active;
+
+    HttpResponse::Ok().json(warehouse.clone())
+}
+
+// Saldo, reservado e disponível de cada produto no depósito
+pub async fn get_warehouse_stock(path: web::Path<String>, state: web::Data<ProductState>) -> impl Responder {
+    let code = path.into_inner().trim().to_lowercase();
+    let ledger = state.ledger.lock().unwrap();
+    if ledger.warehouse(&code).is_none() {
+        return HttpResponse::NotFound().json(json!({ "error": "Warehouse not found" }));
+    }
+
+    let products = state.products.lock().unwrap();
+    let mut levels: Vec<(&str, serde_json::Value)> = products
+        .iter()
+        .map(|product| (product, ledger.on_hand(product.id, Some(&code)), ledger.reserved(product.id, Some(&code))))
+        .filter(|(_, on_hand, reserved)| *on_hand != 0 || *reserved != 0)
+        .map(|(product, on_hand, reserved)| {
+            (product.name.as_str(), json!({
+                "product_id": product.id,
+                "sku": product.sku,
+                "name": product.name,
+                "on_hand": on_hand,
+                "reserved": reserved,
+                "available": on_hand - reserved,
+            }))
+        })
+        .collect();
+    levels.sort_by(|a, b| a.0.cmp(b.0));
+    HttpResponse::Ok().json(json!({
+        "warehouse": code,
+        "products": levels.into_iter().map(|(_, level)| level).collect::<Vec<_>>()
+    }))
+}
@@ -0,0 +1,183 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::sales::SalesState;
+use crate::models::money::{round_cents, Money};
+use crate::models::{Invoice, InvoiceLine, Sale};
+use crate::services::{pdf, template};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const INVOICE_TEMPLATE: &str = include_str!("../../templates/invoice.txt");
+
+pub struct InvoiceState {
+    pub tenant_id: String,
+    pub prefix: String,
+    // Próximo número por tenant; só avança quando a fatura é de fato emitida
+    pub sequences: Mutex<HashMap<String, u64>>,
+    pub invoices: Mutex<Vec<Invoice>>,
+}
+
+impl InvoiceState {
+    pub fn new(tenant_id: &str, prefix: &str) -> Self {
+        InvoiceState {
+            tenant_id: tenant_id.to_string(),
+            prefix: prefix.to_string(),
+            sequences: Mutex::new(HashMap::new()),
+            invoices: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn find_by_sale(&self, sale_id: Uuid) -> Option<Invoice> {
+        self.invoices.lock().unwrap().iter().find(|i| i.sale_id == sale_id).cloned()
+    }
+
+    fn next_number(&self, tenant_id: &str) -> String {
+        let mut sequences = self.sequences.lock().unwrap();
+        let next = sequences.entry(tenant_id.to_string()).or_insert(0);
+        *next += 1;
+        format!("{}-{:06}", self.prefix, next)
+    }
+}
+
+// Monta a fatura com o retrato gravado na venda, não com o cadastro atual
+fn build_invoice(sale: &Sale) -> Result<Invoice, String> {
+    if !sale.status.is_captured() {
+        return Err(format!("Sale with status '{}' cannot be invoiced", sale.status.as_str()));
+    }
+
+    let lines = sale.items.iter()
+        .map(|item| {
+            let product = sale.snapshot.product(item.product_id)
+                .ok_or_else(|| format!("Product {} not found", item.product_id))?;
+            Ok(InvoiceLine {
+                product_id: item.product_id,
+                sku: product.sku.clone(),
+                description: product.name.clone(),
+                quantity: item.quantity,
+                unit_price: item.unit_price,
+                discount: item.discount,
+                tax_amount: item.tax_amount,
+                line_total: item.line_total,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let subtotal: Decimal = lines.iter()
+        .map(|line| round_cents(line.unit_price * Decimal::from(line.quantity)))
+        .sum();
+    let discount_total: Decimal = lines.iter().map(|line| line.discount).sum();
+    let currency = sale.currency().clone();
+
+    Ok(Invoice {
+        id: Uuid::new_v4(),
+        number: String::new(),
+        tenant_id: String::new(),
+        sale_id: sale.id,
+        customer: sale.snapshot.customer.clone(),
+        lines,
+        subtotal: Money::new(subtotal, currency.clone()),
+        discount_total: Money::new(discount_total, currency),
+        tax_total: sale.tax_amount(),
+        prices_include_tax: sale.prices_include_tax,
+        total: sale.amount.clone(),
+        payment_method: sale.payment_method,
+        sale_date: sale.created_at,
+        issued_at: Utc::now(),
+    })
+}
+
+fn render_pdf(invoice: &Invoice) -> Vec<u8> {
+    let currency = invoice.total.currency.code();
+    let values: template::Values = HashMap::from([
+        ("number", invoice.number.clone()),
+        ("issued_at", invoice.issued_at.format("%d/%m/%Y %H:%M UTC").to_string()),
+        ("sale_date", invoice.sale_date.format("%d/%m/%Y").to_string()),
+        ("sale_id", invoice.sale_id.to_string()),
+        ("customer_name", invoice.customer.name.clone()),
+        ("customer_company", invoice.customer.company.clone()),
+        ("customer_email", invoice.customer.email.clone()),
+        ("customer_phone", invoice.customer.phone.clone()),
+        ("subtotal", invoice.subtotal.to_string()),
+        ("discount_total", invoice.discount_total.to_string()),
+        ("tax_total", invoice.tax_total.to_string()),
+        ("tax_label", if invoice.prices_include_tax { "Impostos inclusos:" } else { "Impostos:" }.to_string()),
+        ("total", invoice.total.to_string()),
+        ("payment_method", invoice.payment_method.as_str().to_string()),
+    ]);
+
+    let rows = invoice.lines.iter()
+        .map(|line| HashMap::from([
+            ("sku", line.sku.clone()),
+            ("description", line.description.clone()),
+            ("quantity", line.quantity.to_string()),
+            ("unit_price", format!("{} {:.2}", currency, line.unit_price)),
+            ("discount", format!("{:.2}", line.discount)),
+            ("line_total", format!("{:.2}", line.line_total)),
+        ]))
+        .collect();
+
+    let lines = template::render(INVOICE_TEMPLATE, &values, &HashMap::from([("lines", rows)]));
+    pdf::render_text_pdf(&lines)
+}
+
+pub async fn issue_invoice(
+    path: web::Path<Uuid>,
+    state: web::Data<InvoiceState>,
+    sales_state: web::Data<SalesState>,
+) -> impl Responder {
+    let sale_id = path.into_inner();
+
+    let Some(sale) = sales_state.sales.lock().unwrap().iter().find(|s| s.id == sale_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Sale not found" }));
+    };
+
+    if let Some(invoice) = state.find_by_sale(sale_id) {
+        return HttpResponse::Ok().json(invoice);
+    }
+
+    let mut invoice = match build_invoice(&sale) {
+        Ok(invoice) => invoice,
+        Err(err) => return HttpResponse::Conflict().json(json!({ "error": err })),
+    };
+
+    // Confere de novo sob o lock para que duas emissões simultâneas não gastem dois números
+    let mut invoices = state.invoices.lock().unwrap();
+    if let Some(existing) = invoices.iter().find(|i| i.sale_id == sale_id) {
+        return HttpResponse::Ok().json(existing);
+    }
+    invoice.tenant_id = state.tenant_id.clone();
+    invoice.number = state.next_number(&state.tenant_id);
+    invoices.push(invoice.clone());
+
+    HttpResponse::Created().json(invoice)
+}
+
+pub async fn get_invoice(
+    path: web::Path<Uuid>,
+    state: web::Data<InvoiceState>,
+) -> impl Responder {
+    match state.find_by_sale(path.into_inner()) {
+        Some(invoice) => HttpResponse::Ok().json(invoice),
+        None => HttpResponse::NotFound().json(json!({ "error": "Invoice not found" })),
+    }
+}
+
+pub async fn get_invoice_pdf(
+    path: web::Path<Uuid>,
+    state: web::Data<InvoiceState>,
+) -> impl Responder {
+    let Some(invoice) = state.find_by_sale(path.into_inner()) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Invoice not found" }));
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/pdf")
+        .insert_header((
+            "Content-Disposition",
+            format!("inline; filename=\"{}.pdf\"", invoice.number),
+        ))
+        .body(render_pdf(&invoice))
+}
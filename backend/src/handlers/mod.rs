@@ -3,7 +3,24 @@ pub mod api;
 pub mod ai;
 pub mod users;
 pub mod sales;
+pub mod products;
+pub mod inventory;
+pub mod refunds;
+pub mod invoices;
+pub mod fiscal;
+pub mod payments;
+pub mod promotions;
+pub mod quotes;
+pub mod subscriptions;
+pub mod segments;
+pub mod custom_fields;
+pub mod imports;
 pub mod customers;
+pub mod clv;
+pub mod duplicates;
+pub mod timeline;
+pub mod privacy;
+pub mod search;
 pub mod dashboard;
 pub mod config;
 
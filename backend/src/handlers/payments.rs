@@ -0,0 +1,181 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::{PaymentEvent, PaymentIntent, PaymentIntentCreate, PaymentIntentStatus, PaymentSimulation, SaleStatus};
+use crate::services::payments::{PaymentGateway, SIGNATURE_HEADER};
+use chrono::Utc;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub struct PaymentState {
+    pub gateway: Box<dyn PaymentGateway>,
+    pub intents: Mutex<Vec<PaymentIntent>>,
+    // Gateways reenviam webhooks; cada evento só é aplicado uma vez
+    pub processed_events: Mutex<HashSet<Uuid>>,
+}
+
+impl PaymentState {
+    pub fn new(gateway: Box<dyn PaymentGateway>) -> Self {
+        PaymentState {
+            gateway,
+            intents: Mutex::new(Vec::new()),
+            processed_events: Mutex::new(HashSet::new()),
+        }
+    }
+}
+
+pub async fn create_payment(
+    path: web::Path<Uuid>,
+    payment_req: web::Json<PaymentIntentCreate>,
+    state: web::Data<PaymentState>,
+    sales_state: web::Data<SalesState>,
+) -> impl Responder {
+    let sale_id = path.into_inner();
+
+    // Mesma ordem de `cancel_pending` (pagamentos, depois vendas): com os pagamentos travados,
+    // a venda não pode ser cancelada entre a conferência do status e a criação da cobrança
+    let mut intents = state.intents.lock().unwrap();
+    let Some(sale) = sales_state.sales.lock().unwrap().iter().find(|s| s.id == sale_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Sale not found" }));
+    };
+    if sale.status != SaleStatus::Pending {
+        return HttpResponse::Conflict().json(json!({
+            "error": format!("Sale with status '{}' cannot receive payments", sale.status.as_str())
+        }));
+    }
+
+    if let Some(open) = intents.iter().find(|i| i.sale_id == sale_id && i.status == PaymentIntentStatus::Pending) {
+        return HttpResponse::Conflict().json(json!({
+            "error": "Sale already has a pending payment",
+            "payment_id": open.id
+        }));
+    }
+
+    let method = payment_req.method.unwrap_or(sale.payment_method);
+    match state.gateway.create_intent(&sale, method) {
+        Ok(intent) => {
+            intents.push(intent.clone());
+            HttpResponse::Created().json(intent)
+        }
+        Err(err) => HttpResponse::BadRequest().json(json!({ "error": err })),
+    }
+}
+
+pub async fn get_sale_payments(
+    path: web::Path<Uuid>,
+    state: web::Data<PaymentState>,
+) -> impl Responder {
+    let sale_id = path.into_inner();
+    let intents: Vec<PaymentIntent> = state.intents.lock().unwrap()
+        .iter()
+        .filter(|i| i.sale_id == sale_id)
+        .cloned()
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "payments": intents,
+        "total": intents.len()
+    }))
+}
+
+pub async fn get_payment(
+    path: web::Path<Uuid>,
+    state: web::Data<PaymentState>,
+) -> impl Responder {
+    let payment_id = path.into_inner();
+    match state.intents.lock().unwrap().iter().find(|i| i.id == payment_id) {
+        Some(intent) => HttpResponse::Ok().json(intent),
+        None => HttpResponse::NotFound().json(json!({ "error": "Payment not found" })),
+    }
+}
+
+pub async fn simulate_payment(
+    path: web::Path<Uuid>,
+    simulation: web::Json<PaymentSimulation>,
+    state: web::Data<PaymentState>,
+) -> impl Responder {
+    let payment_id = path.into_inner();
+
+    let Some(intent) = state.intents.lock().unwrap().iter().find(|i| i.id == payment_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Payment not found" }));
+    };
+
+    match state.gateway.simulate(&intent, &simulation) {
+        Ok(event) => HttpResponse::Accepted().json(json!({
+            "message": "Webhook scheduled",
+            "event": event
+        })),
+        Err(err) => HttpResponse::BadRequest().json(json!({ "error": err })),
+    }
+}
+
+// Aplica o evento ao pagamento e, se a venda ainda estiver pendente, conclui ou falha a venda
+fn apply_event(
+    event: &PaymentEvent,
+    state: &PaymentState,
+    sales_state: &SalesState,
+    product_state: &ProductState,
+) -> Result<PaymentIntent, String> {
+    let mut intents = state.intents.lock().unwrap();
+    let intent = intents.iter_mut()
+        .find(|i| i.id == event.intent_id)
+        .ok_or_else(|| format!("Payment {} not found", event.intent_id))?;
+
+    if intent.status != PaymentIntentStatus::Pending {
+        return Ok(intent.clone());
+    }
+    intent.status = event.status;
+    intent.failure_reason = event.failure_reason.clone();
+    intent.updated_at = Utc::now();
+
+    let mut sales = sales_state.sales.lock().unwrap();
+    if let Some(sale) = sales.iter_mut().find(|s| s.id == intent.sale_id && s.status == SaleStatus::Pending) {
+        match event.status {
+            PaymentIntentStatus::Succeeded => {
+                sale.status = SaleStatus::Completed;
+                sale.payment_method = intent.method;
+                product_state.commit_stock(sale.id);
+            }
+            PaymentIntentStatus::Failed => {
+                sale.status = SaleStatus::Failed;
+                product_state.release_stock(sale.id);
+            }
+            PaymentIntentStatus::Pending => {}
+        }
+    }
+
+    Ok(intent.clone())
+}
+
+pub async fn receive_webhook(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    state: web::Data<PaymentState>,
+    sales_state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+) -> impl Responder {
+    if path.into_inner() != state.gateway.name() {
+        return HttpResponse::NotFound().json(json!({ "error": "Unknown payment gateway" }));
+    }
+
+    let signature = req.headers().get(SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+    let event = match state.gateway.parse_webhook(signature, &body) {
+        Ok(event) => event,
+        Err(err) => return HttpResponse::Unauthorized().json(json!({ "error": err })),
+    };
+
+    if state.processed_events.lock().unwrap().contains(&event.id) {
+        return HttpResponse::Ok().json(json!({ "received": true, "duplicate": true }));
+    }
+
+    match apply_event(&event, &state, &sales_state, &product_state) {
+        Ok(intent) => {
+            state.processed_events.lock().unwrap().insert(event.id);
+            HttpResponse::Ok().json(json!({ "received": true, "payment": intent }))
+        }
+        Err(err) => HttpResponse::NotFound().json(json!({ "error": err })),
+    }
+}
@@ -0,0 +1,549 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::auth::AppState as AuthState;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::fiscal::FiscalState;
+use crate::handlers::invoices::InvoiceState;
+use crate::handlers::payments::PaymentState;
+use crate::handlers::products::ProductState;
+use crate::handlers::quotes::QuoteState;
+use crate::handlers::sales::SalesState;
+use crate::handlers::search::SearchState;
+use crate::handlers::subscriptions::SubscriptionState;
+use crate::handlers::users::UserState;
+use crate::models::InvoiceCustomer;
+use crate::models::privacy::{
+    DataSubjectType, PrivacyEvidence, PrivacyRequest, PrivacyRequestAction, PrivacyRequestCreate, PrivacyRequestKind,
+    PrivacyRequestStatus,
+};
+use crate::models::query::ListQuery;
+use crate::models::subscription::SubscriptionStatus;
+use crate::models::timeline::CustomerStatusChange;
+use crate::services::privacy;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub struct PrivacyState {
+    pub deadline_days: i64,
+    pub requests: Mutex<Vec<PrivacyRequest>>,
+    // Documentos exportados, guardados byte a byte para o hash do comprovante conferir
+    pub exports: Mutex<HashMap<Uuid, Vec<u8>>>,
+}
+
+impl PrivacyState {
+    pub fn new(deadline_days: i64) -> Self {
+        PrivacyState {
+            deadline_days,
+            requests: Mutex::new(Vec::new()),
+            exports: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+// Fontes de dados pessoais consultadas ao atender um pedido
+struct PersonalData<'a> {
+    customers: &'a CustomerState,
+    sales: &'a SalesState,
+    payments: &'a PaymentState,
+    subscriptions: &'a SubscriptionState,
+    quotes: &'a QuoteState,
+    invoices: &'a InvoiceState,
+    fiscal: &'a FiscalState,
+    users: &'a UserState,
+    auth: &'a AuthState,
+}
+
+// Resultado do atendimento: e-mail original do titular, contagens por categoria, dados mantidos e documento exportado
+struct Fulfillment {
+    email: String,
+    records: BTreeMap<String, usize>,
+    retained: Vec<String>,
+    document: Option<serde_json::Value>,
+}
+
+fn not_found(subject_type: DataSubjectType) -> HttpResponse {
+    let error = match subject_type {
+        DataSubjectType::Customer => "Customer not found",
+        DataSubjectType::User => "User not found",
+    };
+    HttpResponse::NotFound().json(json!({ "error": error }))
+}
+
+fn parse_customer_id(raw: &str) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(raw.trim()).map_err(|_| not_found(DataSubjectType::Customer))
+}
+
+fn parse_user_id(raw: &str) -> Result<u64, HttpResponse> {
+    raw.trim().parse().map_err(|_| not_found(DataSubjectType::User))
+}
+
+impl PersonalData<'_> {
+    fn export_customer(&self, raw_id: &str) -> Result<Fulfillment, HttpResponse> {
+        let customer_id = parse_customer_id(raw_id)?;
+        let Some(customer) = self.customers.customers.lock().unwrap().iter().find(|c| c.id == customer_id).cloned() else {
+            return Err(not_found(DataSubjectType::Customer));
+        };
+
+        let notes: Vec<_> = self.customers.notes.lock().unwrap().iter().filter(|n| n.customer_id == customer_id).cloned().collect();
+        let status_changes: Vec<_> = self.customers.status_changes.lock().unwrap()
+            .iter()
+            .filter(|c| c.customer_id == customer_id)
+            .cloned()
+            .collect();
+        let sales: Vec<_> = self.sales.sales.lock().unwrap().iter().filter(|s| s.customer_id == customer_id).cloned().collect();
+        let sale_ids: HashSet<Uuid> = sales.iter().map(|s| s.id).collect();
+        let refunds: Vec<_> = self.sales.refunds.lock().unwrap().iter().filter(|r| sale_ids.contains(&r.sale_id)).cloned().collect();
+        let payments: Vec<_> = self.payments.intents.lock().unwrap().iter().filter(|i| sale_ids.contains(&i.sale_id)).cloned().collect();
+        let subscriptions: Vec<_> = self.subscriptions.subscriptions.lock().unwrap()
+            .iter()
+            .filter(|s| s.customer_id == customer_id)
+            .cloned()
+            .collect();
+        let quotes: Vec<_> = self.quotes.quotes.lock().unwrap().iter().filter(|q| q.customer_id == customer_id).cloned().collect();
+        let invoices: Vec<_> = self.invoices.invoices.lock().unwrap().iter().filter(|i| sale_ids.contains(&i.sale_id)).cloned().collect();
+        let fiscal_documents: Vec<_> = self.fiscal.documents.lock().unwrap()
+            .iter()
+            .filter(|d| sale_ids.contains(&d.sale_id))
+            .cloned()
+            .collect();
+
+        let records = BTreeMap::from([
+            ("profile".to_string(), 1),
+            ("notes".to_string(), notes.len()),
+            ("status_changes".to_string(), status_changes.len()),
+            ("sales".to_string(), sales.len()),
+            ("refunds".to_string(), refunds.len()),
+            ("payments".to_string(), payments.len()),
+            ("subscriptions".to_string(), subscriptions.len()),
+            ("quotes".to_string(), quotes.len()),
+            ("invoices".to_string(), invoices.len()),
+            ("fiscal_documents".to_string(), fiscal_documents.len()),
+        ]);
+        let document = json!({
+            "profile": customer,
+            "notes": notes,
+            "status_changes": status_changes,
+            "sales": sales,
+            "refunds": refunds,
+            "payments": payments,
+            "subscriptions": subscriptions,
+            "quotes": quotes,
+            "invoices": invoices,
+            "fiscal_documents": fiscal_documents
+        });
+        Ok(Fulfillment { email: customer.email, records, retained: Vec::new(), document: Some(document) })
+    }
+
+    fn export_user(&self, raw_id: &str) -> Result<Fulfillment, HttpResponse> {
+        let user_id = parse_user_id(raw_id)?;
+        let Some(user) = self.users.users.lock().unwrap().iter().find(|u| u.id == user_id).cloned() else {
+            return Err(not_found(DataSubjectType::User));
+        };
+        let authored = |value: &Option<String>| {
+            value.as_deref().is_some_and(|v| v.eq_ignore_ascii_case(&user.email) || v == user.name)
+        };
+
+        // A senha (hash) não faz parte da exportação
+        let accounts: Vec<_> = self.auth.users.lock().unwrap()
+            .iter()
+            .filter(|a| a.email.eq_ignore_ascii_case(&user.email))
+            .map(|a| json!({ "id": a.id, "name": a.name, "email": a.email, "role": a.role }))
+            .collect();
+        let notes: Vec<_> = self.customers.notes.lock().unwrap().iter().filter(|n| authored(&n.author)).cloned().collect();
+        let merges: Vec<_> = self.customers.merges.lock().unwrap()
+            .iter()
+            .filter(|m| authored(&m.merged_by))
+            .map(|m| json!({ "id": m.id, "survivor_id": m.survivor_id, "merged_id": m.merged_id, "merged_at": m.merged_at }))
+            .collect();
+        let quotes: Vec<_> = self.quotes.quotes.lock().unwrap()
+            .iter()
+            .filter(|q| q.rep_id == user_id)
+            .map(|q| json!({ "id": q.id, "number": q.number, "customer_id": q.customer_id, "stage": q.stage, "created_at": q.created_at }))
+            .collect();
+
+        let records = BTreeMap::from([
+            ("profile".to_string(), 1),
+            ("login_accounts".to_string(), accounts.len()),
+            ("notes_authored".to_string(), notes.len()),
+            ("merges_performed".to_string(), merges.len()),
+            ("quotes_as_rep".to_string(), quotes.len()),
+        ]);
+        let document = json!({
+            "profile": user,
+            "login_accounts": accounts,
+            "notes_authored": notes,
+            "merges_performed": merges,
+            "quotes_as_rep": quotes
+        });
+        Ok(Fulfillment { email: user.email, records, retained: Vec::new(), document: Some(document) })
+    }
+
+    // Anonimiza o cadastro; vendas, reembolsos, pagamentos e assinaturas seguem ligados ao id, sem dados pessoais
+    fn erase_customer(&self, raw_id: &str, now: DateTime<Utc>) -> Result<Fulfillment, HttpResponse> {
+        let customer_id = parse_customer_id(raw_id)?;
+        let open_subscriptions = self.subscriptions.subscriptions.lock().unwrap()
+            .iter()
+            .any(|s| s.customer_id == customer_id && s.status != SubscriptionStatus::Canceled);
+        if open_subscriptions {
+            return Err(HttpResponse::Conflict().json(json!({
+                "error": "Customer has active subscriptions; cancel them before erasing"
+            })));
+        }
+
+        let (email, status_change, anonymized) = {
+            let mut customers = self.customers.customers.lock().unwrap();
+            let Some(customer) = customers.iter_mut().find(|c| c.id == customer_id) else {
+                return Err(not_found(DataSubjectType::Customer));
+            };
+            if customer.anonymized_at.is_some() {
+                return Err(HttpResponse::Conflict().json(json!({ "error": "Customer data was already erased" })));
+            }
+            let email = customer.email.clone();
+            privacy::anonymize_customer(customer, now);
+            let status_change = (customer.status != "inactive").then(|| CustomerStatusChange {
+                id: Uuid::new_v4(),
+                customer_id,
+                from: std::mem::replace(&mut customer.status, "inactive".to_string()),
+                to: "inactive".to_string(),
+                changed_at: now,
+            });
+            (email, status_change, customer.clone())
+        };
+        if let Some(change) = status_change {
+            self.customers.status_changes.lock().unwrap().push(change);
+        }
+
+        let mut records = BTreeMap::from([("profile".to_string(), 1)]);
+        {
+            let mut notes = self.customers.notes.lock().unwrap();
+            let before = notes.len();
+            notes.retain(|n| n.customer_id != customer_id);
+            records.insert("notes".to_string(), before - notes.len());
+        }
+        {
+            let mut duplicates = self.customers.duplicates.lock().unwrap();
+            let before = duplicates.len();
+            duplicates.retain(|d| d.customer_id != customer_id && d.duplicate_id != customer_id);
+            records.insert("duplicate_candidates".to_string(), before - duplicates.len());
+        }
+        // As cópias guardadas para desfazer fusões também têm dados pessoais
+        let mut snapshots = 0;
+        for merge in self.customers.merges.lock().unwrap().iter_mut() {
+            for snapshot in [&mut merge.survivor_before, &mut merge.merged_customer] {
+                if snapshot.id == customer_id {
+                    privacy::anonymize_customer(snapshot, now);
+                    snapshots += 1;
+                }
+            }
+        }
+        records.insert("merge_snapshots".to_string(), snapshots);
+        let mut quote_notes = 0;
+        for quote in self.quotes.quotes.lock().unwrap().iter_mut().filter(|q| q.customer_id == customer_id) {
+            if quote.notes.take().is_some() {
+                quote_notes += 1;
+            }
+        }
+        records.insert("quote_notes".to_string(), quote_notes);
+
+        // Observações e a cópia do cliente nas vendas também identificam o titular;
+        // faturas já emitidas guardam a própria cópia e não são alteradas
+        let mut sale_ids = HashSet::new();
+        let mut sale_notes = 0;
+        for sale in self.sales.sales.lock().unwrap().iter_mut().filter(|s| s.customer_id == customer_id) {
+            sale_ids.insert(sale.id);
+            if sale.notes.take().is_some() {
+                sale_notes += 1;
+            }
+            sale.snapshot.customer = InvoiceCustomer::from(&anonymized);
+        }
+        records.insert("sale_notes".to_string(), sale_notes);
+        records.insert("sale_snapshots".to_string(), sale_ids.len());
+        let invoices = self.invoices.invoices.lock().unwrap().iter().filter(|i| sale_ids.contains(&i.sale_id)).count();
+        let fiscal_documents = self.fiscal.documents.lock().unwrap().iter().filter(|d| sale_ids.contains(&d.sale_id)).count();
+        let mut retained = Vec::new();
+        if !sale_ids.is_empty() {
+            retained.push(format!(
+                "{} sales with refunds, payments and subscriptions: kept under the pseudonymous customer id for aggregate reporting",
+                sale_ids.len()
+            ));
+        }
+        if invoices + fiscal_documents > 0 {
+            retained.push(format!(
+                "{} invoices and {} NF-e documents: fiscal record retention (LGPD art. 16, I)",
+                invoices, fiscal_documents
+            ));
+        }
+
+        Ok(Fulfillment { email, records, retained, document: None })
+    }
+
+    // Anonimiza o usuário e remove o acesso; cotações mantêm o id numérico do vendedor
+    fn erase_user(&self, raw_id: &str) -> Result<Fulfillment, HttpResponse> {
+        let user_id = parse_user_id(raw_id)?;
+        let (email, name) = {
+            let mut users = self.users.users.lock().unwrap();
+            let Some(user) = users.iter_mut().find(|u| u.id == user_id) else {
+                return Err(not_found(DataSubjectType::User));
+            };
+            if user.name == privacy::ANONYMIZED_USER {
+                return Err(HttpResponse::Conflict().json(json!({ "error": "User data was already erased" })));
+            }
+            let original = (user.email.clone(), user.name.clone());
+            user.name = privacy::ANONYMIZED_USER.to_string();
+            user.email = privacy::erased_email(&user_id.to_string());
+            user.role = "disabled".to_string();
+            original
+        };
+        let authored = |value: &Option<String>| value.as_deref().is_some_and(|v| v.eq_ignore_ascii_case(&email) || v == name);
+
+        let mut records = BTreeMap::from([("profile".to_string(), 1)]);
+        {
+            let mut accounts = self.auth.users.lock().unwrap();
+            let before = accounts.len();
+            accounts.retain(|a| !a.email.eq_ignore_ascii_case(&email));
+            records.insert("login_accounts".to_string(), before - accounts.len());
+        }
+        let mut notes = 0;
+        for note in self.customers.notes.lock().unwrap().iter_mut().filter(|n| authored(&n.author)) {
+            note.author = None;
+            notes += 1;
+        }
+        records.insert("note_authorship".to_string(), notes);
+        let mut merges = 0;
+        for merge in self.customers.merges.lock().unwrap().iter_mut().filter(|m| authored(&m.merged_by)) {
+            merge.merged_by = None;
+            merges += 1;
+        }
+        records.insert("merge_authorship".to_string(), merges);
+
+        let quotes = self.quotes.quotes.lock().unwrap().iter().filter(|q| q.rep_id == user_id).count();
+        let retained = if quotes > 0 {
+            vec![format!("{} quotes: keep the numeric rep id for pipeline reports", quotes)]
+        } else {
+            Vec::new()
+        };
+
+        Ok(Fulfillment { email, records, retained, document: None })
+    }
+}
+
+pub async fn get_privacy_requests(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<PrivacyState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<PrivacyRequest>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let requests = state.requests.lock().unwrap();
+    HttpResponse::Ok().json(query.apply(&requests))
+}
+
+pub async fn get_privacy_request(path: web::Path<Uuid>, state: web::Data<PrivacyState>) -> impl Responder {
+    let request_id = path.into_inner();
+    let requests = state.requests.lock().unwrap();
+    match requests.iter().find(|r| r.id == request_id) {
+        Some(request) => HttpResponse::Ok().json(request),
+        None => HttpResponse::NotFound().json(json!({ "error": "Privacy request not found" })),
+    }
+}
+
+// Registra o pedido com o prazo legal; o atendimento é feito em `/complete`
+pub async fn create_privacy_request(
+    request_req: web::Json<PrivacyRequestCreate>,
+    state: web::Data<PrivacyState>,
+    customer_state: web::Data<CustomerState>,
+    user_state: web::Data<UserState>,
+) -> impl Responder {
+    let req = request_req.into_inner();
+    let subject_id = req.subject_id.trim().to_string();
+    let exists = match req.subject_type {
+        DataSubjectType::Customer => Uuid::parse_str(&subject_id)
+            .is_ok_and(|id| customer_state.customers.lock().unwrap().iter().any(|c| c.id == id)),
+        DataSubjectType::User => subject_id
+            .parse::<u64>()
+            .is_ok_and(|id| user_state.users.lock().unwrap().iter().any(|u| u.id == id)),
+    };
+    if !exists {
+        return not_found(req.subject_type);
+    }
+
+    let mut requests = state.requests.lock().unwrap();
+    let open = requests.iter().any(|r| {
+        r.status == PrivacyRequestStatus::Pending
+            && r.kind == req.kind
+            && r.subject_type == req.subject_type
+            && r.subject_id == subject_id
+    });
+    if open {
+        return HttpResponse::Conflict().json(json!({
+            "error": format!("There is already a pending {} request for this {}", req.kind.as_str(), req.subject_type.as_str())
+        }));
+    }
+
+    let now = Utc::now();
+    let request = PrivacyRequest {
+        id: Uuid::new_v4(),
+        kind: req.kind,
+        subject_type: req.subject_type,
+        subject_id,
+        requested_by: req.requested_by.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()),
+        reason: req.reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()),
+        status: PrivacyRequestStatus::Pending,
+        received_at: now,
+        due_at: now + Duration::days(state.deadline_days),
+        completed_at: None,
+        handled_by: None,
+        rejection_reason: None,
+        evidence: None,
+    };
+    requests.push(request.clone());
+    HttpResponse::Created().json(request)
+}
+
+// Executa a exportação ou a anonimização e guarda o comprovante no pedido
+#[allow(clippy::too_many_arguments)]
+pub async fn complete_privacy_request(
+    path: web::Path<Uuid>,
+    action_req: web::Json<PrivacyRequestAction>,
+    state: web::Data<PrivacyState>,
+    customer_state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    payment_state: web::Data<PaymentState>,
+    subscription_state: web::Data<SubscriptionState>,
+    quote_state: web::Data<QuoteState>,
+    invoice_state: web::Data<InvoiceState>,
+    fiscal_state: web::Data<FiscalState>,
+    user_state: web::Data<UserState>,
+    auth_state: web::Data<AuthState>,
+    search_state: web::Data<SearchState>,
+    product_state: web::Data<ProductState>,
+) -> impl Responder {
+    let request_id = path.into_inner();
+    let action = action_req.into_inner();
+    // O registro fica travado durante o atendimento para o mesmo pedido não rodar duas vezes
+    let mut requests = state.requests.lock().unwrap();
+    let Some(index) = requests.iter().position(|r| r.id == request_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Privacy request not found" }));
+    };
+    let request = requests[index].clone();
+    if request.status != PrivacyRequestStatus::Pending {
+        return HttpResponse::Conflict().json(json!({ "error": format!("Privacy request is already {}", request.status.as_str()) }));
+    }
+
+    let data = PersonalData {
+        customers: &customer_state,
+        sales: &sales_state,
+        payments: &payment_state,
+        subscriptions: &subscription_state,
+        quotes: &quote_state,
+        invoices: &invoice_state,
+        fiscal: &fiscal_state,
+        users: &user_state,
+        auth: &auth_state,
+    };
+    let now = Utc::now();
+    let fulfilled = match (request.kind, request.subject_type) {
+        (PrivacyRequestKind::Export, DataSubjectType::Customer) => data.export_customer(&request.subject_id),
+        (PrivacyRequestKind::Export, DataSubjectType::User) => data.export_user(&request.subject_id),
+        (PrivacyRequestKind::Erasure, DataSubjectType::Customer) => data.erase_customer(&request.subject_id, now),
+        (PrivacyRequestKind::Erasure, DataSubjectType::User) => data.erase_user(&request.subject_id),
+    };
+    let Fulfillment { email, mut records, retained, document } = match fulfilled {
+        Ok(fulfillment) => fulfillment,
+        Err(response) => return response,
+    };
+    // Nome e e-mail apagados saem da busca na hora, sem esperar o agendador
+    if request.kind == PrivacyRequestKind::Erasure && request.subject_type == DataSubjectType::Customer {
+        search_state.refresh(&customer_state, &product_state, &sales_state, &payment_state);
+    }
+
+    let mut exports = state.exports.lock().unwrap();
+    let document_sha256 = document.map(|data| {
+        let document = json!({
+            "request_id": request.id,
+            "generated_at": now,
+            "subject": { "type": request.subject_type, "id": request.subject_id },
+            "data": data
+        });
+        let bytes = serde_json::to_vec_pretty(&document).unwrap_or_default();
+        let digest = privacy::sha256_hex(&bytes);
+        exports.insert(request.id, bytes);
+        digest
+    });
+    // Exportações anteriores do mesmo titular também saem da base
+    if request.kind == PrivacyRequestKind::Erasure {
+        let purged = requests
+            .iter()
+            .filter(|r| r.subject_type == request.subject_type && r.subject_id == request.subject_id)
+            .filter(|r| exports.remove(&r.id).is_some())
+            .count();
+        records.insert("export_documents".to_string(), purged);
+    }
+
+    let request = &mut requests[index];
+    request.status = PrivacyRequestStatus::Completed;
+    request.completed_at = Some(now);
+    request.handled_by = action.performed_by.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    request.evidence = Some(PrivacyEvidence {
+        subject_fingerprint: privacy::subject_fingerprint(&email),
+        document_sha256,
+        records,
+        retained,
+    });
+
+    HttpResponse::Ok().json(&*request)
+}
+
+// Recusa com justificativa (ex.: titular não confirmou a identidade)
+pub async fn reject_privacy_request(
+    path: web::Path<Uuid>,
+    action_req: web::Json<PrivacyRequestAction>,
+    state: web::Data<PrivacyState>,
+) -> impl Responder {
+    let request_id = path.into_inner();
+    let action = action_req.into_inner();
+    let Some(reason) = action.reason.map(|r| r.trim().to_string()).filter(|r| !r.is_empty()) else {
+        return HttpResponse::BadRequest().json(json!({ "error": "A reason is required to reject a request" }));
+    };
+
+    let mut requests = state.requests.lock().unwrap();
+    let Some(request) = requests.iter_mut().find(|r| r.id == request_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Privacy request not found" }));
+    };
+    if request.status != PrivacyRequestStatus::Pending {
+        return HttpResponse::Conflict().json(json!({ "error": format!("Privacy request is already {}", request.status.as_str()) }));
+    }
+    request.status = PrivacyRequestStatus::Rejected;
+    request.completed_at = Some(Utc::now());
+    request.handled_by = action.performed_by.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    request.rejection_reason = Some(reason);
+
+    HttpResponse::Ok().json(&*request)
+}
+
+// Documento JSON entregue ao titular; o SHA-256 destes bytes está em `evidence.document_sha256`
+pub async fn get_privacy_export(path: web::Path<Uuid>, state: web::Data<PrivacyState>) -> impl Responder {
+    let request_id = path.into_inner();
+    let Some(request) = state.requests.lock().unwrap().iter().find(|r| r.id == request_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Privacy request not found" }));
+    };
+    if request.kind != PrivacyRequestKind::Export || request.status != PrivacyRequestStatus::Completed {
+        return HttpResponse::Conflict().json(json!({ "error": "Only completed export requests have a document" }));
+    }
+    let Some(body) = state.exports.lock().unwrap().get(&request_id).cloned() else {
+        // Removido quando o titular pediu a eliminação depois da exportação
+        return HttpResponse::Gone().json(json!({ "error": "Export document was purged after an erasure request" }));
+    };
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"privacy-export-{}.json\"", request_id),
+        ))
+        .body(body)
+}
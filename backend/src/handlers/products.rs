@@ -0,0 +1,593 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::custom_fields::CustomFieldState;
+use crate::models::custom_field::CustomFieldEntity;
+use crate::models::inventory::{ReservationStatus, StockAllocation, StockMovement, StockMovementKind, Warehouse, DEFAULT_LOCATION};
+use crate::models::money::{round_cents, Currency, ExchangeRates, Money, MAX_UNIT_PRICE};
+use crate::models::query::ListQuery;
+use crate::models::{margin_percent, Product, ProductCreate, ProductPriceChange, ProductUpdate, RefundLine, SaleItem};
+use crate::services::custom_fields;
+use crate::services::inventory::StockLedger;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub struct ProductState {
+    pub products: Mutex<Vec<Product>>,
+    pub price_history: Mutex<Vec<ProductPriceChange>>,
+    // Travar sempre o razão antes da lista de produtos
+    pub ledger: Mutex<StockLedger>,
+}
+
+// Registro do histórico quando preço ou custo mudam
+fn price_change(product: &Product, previous: Option<&Product>, changed_by: Option<String>, now: DateTime<Utc>) -> ProductPriceChange {
+    ProductPriceChange {
+        id: Uuid::new_v4(),
+        product_id: product.id,
+        price: product.price.clone(),
+        cost: product.cost.clone(),
+        margin_percent: product.margin_percent(),
+        previous_price: previous.map(|p| p.price.clone()),
+        previous_cost: previous.and_then(|p| p.cost.clone()),
+        changed_by,
+        changed_at: now,
+    }
+}
+
+impl ProductState {
+    pub fn new(currency: &Currency) -> Self {
+        let now = Utc::now();
+        let products = vec![
+            Product {
+                id: Uuid::new_v4(),
+                name: "Enterprise Plan".to_string(),
+                sku: "ENT-001".to_string(),
+                category: "Software".to_string(),
+                description: Some("Unlimited users, dedicated support and custom AI models".to_string()),
+                ncm: "85235190".to_string(),
+                price: Money::new(dec!(4999.99), currency.clone()),
+                cost: Some(Money::new(dec!(1850.00), currency.clone())),
+                stock_quantity: 100,
+                reserved_quantity: 0,
+                is_active: true,
+                custom_fields: BTreeMap::new(),
+                tags: Vec::new(),
+                created_at: now,
+                updated_at: now,
+            },
+            Product {
+                id: Uuid::new_v4(),
+                name: "Business Suite".to_string(),
+                sku: "BUS-002".to_string(),
+                category: "Software".to_string(),
+                description: Some("Sales, inventory and analytics for growing teams".to_string()),
+                ncm: "85235190".to_string(),
+                price: Money::new(dec!(899.99), currency.clone()),
+                cost: Some(Money::new(dec!(320.00), currency.clone())),
+                stock_quantity: 250,
+                reserved_quantity: 0,
+                is_active: true,
+                custom_fields: BTreeMap::new(),
+                tags: Vec::new(),
+                created_at: now,
+                updated_at: now,
+            },
+            Product {
+                id: Uuid::new_v4(),
+                name: "Basic Plan".to_string(),
+                sku: "BAS-003".to_string(),
+                category: "Software".to_string(),
+                description: Some("Essential sales tracking for small businesses".to_string()),
+                ncm: "85235190".to_string(),
+                price: Money::new(dec!(99.99), currency.clone()),
+                cost: Some(Money::new(dec!(24.50), currency.clone())),
+                stock_quantity: 1000,
+                reserved_quantity: 0,
+                is_active: true,
+                custom_fields: BTreeMap::new(),
+                tags: Vec::new(),
+                created_at: now,
+                updated_at: now,
+            },
+        ];
+
+        let mut products = products;
+        let price_history = products.iter().map(|p| price_change(p, None, None, now)).collect();
+        let warehouse = |code: &str, name: &str, city: &str, is_default: bool| Warehouse {
+            code: code.to_string(),
+            name: name.to_string(),
+            city: Some(city.to_string()),
+            country: Some("Brasil".to_string()),
+            is_default,
+            is_active: true,
+            created_at: now,
+        };
+        let mut ledger = StockLedger {
+            warehouses: vec![
+                warehouse(DEFAULT_LOCATION, "CD São Paulo", "São Paulo", true),
+                warehouse("rio", "CD Rio de Janeiro", "Rio de Janeiro", false),
+            ],
+            ..StockLedger::default()
+        };
+        // O estoque inicial entra no razão como recebimento: o saldo do cadastro no depósito padrão e 20% dele no Rio
+        for product in products.iter_mut() {
+            let initial = Some("Initial stock".to_string());
+            ledger.record(product.id, StockMovementKind::Receipt, DEFAULT_LOCATION, product.stock_quantity, None, initial.clone(), None, now);
+            ledger.record(product.id, StockMovementKind::Receipt, "rio", product.stock_quantity / 5, None, initial, None, now);
+            product.stock_quantity = ledger.on_hand(product.id, None);
+        }
+
+        ProductState {
+            products: Mutex::new(products),
+            price_history: Mutex::new(price_history),
+            ledger: Mutex::new(ledger),
+        }
+    }
+
+    // Atualiza o saldo e o reservado guardados no produto a partir do razão
+    fn sync_stock(&self, ledger: &StockLedger, product_ids: &[Uuid]) {
+        let mut products = self.products.lock().unwrap();
+        for product in products.iter_mut().filter(|p| product_ids.contains(&p.id)) {
+            product.stock_quantity = ledger.on_hand(product.id, None);
+            product.reserved_quantity = ledger.reserved(product.id, None);
+        }
+    }
+
+    // Depósito ativo escolhido para atender uma venda
+    pub fn check_warehouse(&self, code: &str) -> Result<String, String> {
+        self.ledger.lock().unwrap().check_location(code, true)
+    }
+
+    // Reserva o estoque de todas as linhas ou de nenhuma; o razão fica travado entre a conferência e a reserva,
+    // então vendas simultâneas não vendem a mesma unidade
+    pub fn reserve_stock(&self, sale_id: Uuid, items: &[SaleItem], warehouse: Option<&str>) -> Result<Vec<StockAllocation>, String> {
+        let mut ledger = self.ledger.lock().unwrap();
+        let warehouse = warehouse.map(|code| ledger.check_location(code, true)).transpose()?;
+
+        let mut requested: HashMap<Uuid, i32> = HashMap::new();
+        for item in items {
+            *requested.entry(item.product_id).or_insert(0) += item.quantity;
+        }
+        {
+            let products = self.products.lock().unwrap();
+            for product_id in requested.keys() {
+                if !products.iter().any(|p| p.id == *product_id) {
+                    return Err(format!("Product {} not found", product_id));
+                }
+            }
+        }
+        let allocations = match ledger.allocate(&requested, warehouse.as_deref()) {
+            Ok(allocations) => allocations,
+            Err(product_id) => {
+                let products = self.products.lock().unwrap();
+                let name = products.iter().find(|p| p.id == product_id).map(|p| p.name.clone()).unwrap_or_default();
+                return Err(match warehouse {
+                    Some(code) => format!("Insufficient stock for product {} at {}", name, code),
+                    None => format!("Insufficient stock for product {}", name),
+                });
+            }
+        };
+        ledger.reserve(sale_id, &allocations, Utc::now());
+        let product_ids: Vec<Uuid> = requested.into_keys().collect();
+        self.sync_stock(&ledger, &product_ids);
+        Ok(allocations)
+    }
+
+    // Depósitos e quantidades reservados ou baixados para uma venda
+    pub fn sale_allocations(&self, sale_id: Uuid) -> Vec<StockAllocation> {
+        let ledger = self.ledger.lock().unwrap();
+        ledger
+            .reservations
+            .iter()
+            .filter(|r| r.sale_id == sale_id)
+            .map(|r| StockAllocation { product_id: r.product_id, location: r.location.clone(), quantity: r.quantity })
+            .collect()
+    }
+
+    // Pagamento confirmado: a reserva vira baixa no razão
+    pub fn commit_stock(&self, sale_id: Uuid) {
+        let mut ledger = self.ledger.lock().unwrap();
+        let product_ids = ledger.commit(sale_id, Utc::now());
+        self.sync_stock(&ledger, &product_ids);
+    }
+
+    // Pagamento recusado: as unidades reservadas voltam a ficar disponíveis
+    pub fn release_stock(&self, sale_id: Uuid) {
+        let mut ledger = self.ledger.lock().unwrap();
+        let product_ids = ledger.release(sale_id, Utc::now());
+        self.sync_stock(&ledger, &product_ids);
+    }
+
+    // Vendas com reserva ativa feita antes do corte
+    pub fn expired_reservations(&self, cutoff: DateTime<Utc>) -> Vec<Uuid> {
+        let ledger = self.ledger.lock().unwrap();
+        let mut sale_ids: Vec<Uuid> = ledger
+            .reservations
+            .iter()
+            .filter(|r| r.status == ReservationStatus::Active && r.created_at < cutoff)
+            .map(|r| r.sale_id)
+            .collect();
+        sale_ids.sort();
+        sale_ids.dedup();
+        sale_ids
+    }
+
+    // Devolve ao estoque as quantidades reembolsadas, no local de onde saíram, limitadas ao que a venda
+    // baixou menos as devoluções de `earlier_refunds`. Retorna quantas unidades voltaram
+    pub fn restock(&self, sale_id: Uuid, refund_id: Uuid, earlier_refunds: &[Uuid], lines: &[RefundLine]) -> i32 {
+        let mut ledger = self.ledger.lock().unwrap();
+        let now = Utc::now();
+        let mut product_ids = Vec::new();
+        let mut restocked = 0;
+        for line in lines.iter().filter(|l| l.quantity > 0) {
+            let mut remaining = line.quantity;
+            for (location, returnable) in ledger.returnable(sale_id, earlier_refunds, line.product_id) {
+                let quantity = remaining.min(returnable);
+                if quantity == 0 {
+                    break;
+                }
+                ledger.record(line.product_id, StockMovementKind::RefundReturn, &location, quantity, Some(refund_id), None, None, now);
+                remaining -= quantity;
+                restocked += quantity;
+            }
+            if remaining < line.quantity {
+                product_ids.push(line.product_id);
+            }
+        }
+        self.sync_stock(&ledger, &product_ids);
+        restocked
+    }
+
+    // Lançamentos manuais (recebimento, ajuste e transferência) passam por aqui
+    pub fn record_movements(&self, movements: Vec<StockMovement>) -> Result<Vec<StockMovement>, String> {
+        let mut ledger = self.ledger.lock().unwrap();
+        // Confere o efeito líquido de cada produto e local antes de gravar qualquer lançamento
+        let too_large = || "Stock quantity is too large".to_string();
+        let mut changes: HashMap<(Uuid, &str), i32> = HashMap::new();
+        let mut totals: HashMap<Uuid, i32> = HashMap::new();
+        for movement in &movements {
+            let change = changes.entry((movement.product_id, movement.location.as_str())).or_insert(0);
+            *change = change.checked_add(movement.quantity).ok_or_else(too_large)?;
+            let total = totals.entry(movement.product_id).or_insert(0);
+            *total = total.checked_add(movement.quantity).ok_or_else(too_large)?;
+        }
+        // O saldo do produto soma todos os locais, então também precisa caber em i32
+        for (product_id, change) in &totals {
+            ledger.on_hand(*product_id, None).checked_add(*change).ok_or_else(too_large)?;
+        }
+        for ((product_id, location), change) in &changes {
+            let on_hand = ledger.on_hand(*product_id, Some(location));
+            let reserved = ledger.reserved(*product_id, Some(location));
+            let after = on_hand.checked_add(*change).ok_or_else(too_large)?;
+            if after < reserved {
+                return Err(format!(
+                    "Not enough stock at {}: {} on hand, {} reserved, change of {}",
+                    location, on_hand, reserved, change
+                ));
+            }
+        }
+
+        let mut product_ids = Vec::new();
+        for movement in &movements {
+            ledger.movements.push(movement.clone());
+            product_ids.push(movement.product_id);
+        }
+        self.sync_stock(&ledger, &product_ids);
+        Ok(movements)
+    }
+}
+
+// SKU em maiúsculas; a importação de vendas também compara sem diferenciar maiúsculas
+fn validate_sku(raw: &str) -> Result<String, String> {
+    let sku = raw.trim().to_uppercase();
+    if sku.is_empty() {
+        return Err("SKU is required".to_string());
+    }
+    if sku.len() > 64 || !sku.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+        return Err(format!("Invalid SKU: {} (letters, digits, '-', '_' and '.', up to 64 characters)", sku));
+    }
+    Ok(sku)
+}
+
+// Aceita "8523.51.90"; sem NCM, usa 00000000, o código de serviços na NF-e
+fn validate_ncm(raw: Option<&str>) -> Result<String, String> {
+    let ncm: String = raw.unwrap_or("00000000").chars().filter(|c| *c != '.').collect();
+    let ncm = ncm.trim().to_string();
+    if ncm.len() != 8 || !ncm.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("NCM must have 8 digits: {}", ncm));
+    }
+    Ok(ncm)
+}
+
+fn validate_amount(label: &str, amount: Decimal) -> Result<Decimal, String> {
+    if amount.is_sign_negative() {
+        return Err(format!("{} cannot be negative", label));
+    }
+    let amount = round_cents(amount);
+    if amount > MAX_UNIT_PRICE {
+        return Err(format!("{} cannot exceed {}", label, MAX_UNIT_PRICE));
+    }
+    Ok(amount)
+}
+
+// Reaproveita a grafia de uma categoria já usada ("software" vira "Software")
+fn canonical_category(products: &[Product], raw: &str) -> Result<String, String> {
+    let category = raw.trim();
+    if category.is_empty() {
+        return Err("Product category is required".to_string());
+    }
+    if category.chars().count() > 100 {
+        return Err("Product category must have at most 100 characters".to_string());
+    }
+    Ok(products
+        .iter()
+        .find(|p| p.category.eq_ignore_ascii_case(category))
+        .map(|p| p.category.clone())
+        .unwrap_or_else(|| category.to_string()))
+}
+
+fn optional_text(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+pub async fn get_products(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<ProductState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<Product>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let products = state.products.lock().unwrap();
+    HttpResponse::Ok().json(query.apply(&products))
+}
+
+pub async fn get_product(path: web::Path<Uuid>, state: web::Data<ProductState>) -> impl Responder {
+    let product_id = path.into_inner();
+    let products = state.products.lock().unwrap();
+    match products.iter().find(|p| p.id == product_id) {
+        Some(product) => HttpResponse::Ok().json(product),
+        None => HttpResponse::NotFound().json(json!({ "error": "Product not found" })),
+    }
+}
+
+pub async fn create_product(
+    product_req: web::Json<ProductCreate>,
+    state: web::Data<ProductState>,
+    field_state: web::Data<CustomFieldState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let req = product_req.into_inner();
+    let definitions = field_state.definitions.lock().unwrap().clone();
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "Product name is required" }));
+    }
+    let currency = req.currency.clone().unwrap_or_else(|| rates.base.clone());
+    if rates.rate(&currency).is_none() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": format!("No exchange rate available for {}", currency)
+        }));
+    }
+    let stock_quantity = req.stock_quantity.unwrap_or(0);
+    if stock_quantity < 0 {
+        return HttpResponse::BadRequest().json(json!({ "error": "Stock quantity cannot be negative" }));
+    }
+
+    let mut products = state.products.lock().unwrap();
+    let validated = (|| {
+        Ok::<_, String>((
+            validate_sku(&req.sku)?,
+            canonical_category(&products, &req.category)?,
+            validate_ncm(req.ncm.as_deref())?,
+            validate_amount("Price", req.price)?,
+            req.cost.map(|cost| validate_amount("Cost", cost)).transpose()?,
+            custom_fields::apply_values(&definitions, CustomFieldEntity::Product, &BTreeMap::new(), &req.custom_fields)?,
+            custom_fields::normalize_tags(&req.tags)?,
+        ))
+    })();
+    let (sku, category, ncm, price, cost, values, tags) = match validated {
+        Ok(validated) => validated,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    if products.iter().any(|p| p.sku.eq_ignore_ascii_case(&sku)) {
+        return HttpResponse::Conflict().json(json!({ "error": format!("SKU {} is already in use", sku) }));
+    }
+
+    let now = Utc::now();
+    let product = Product {
+        id: Uuid::new_v4(),
+        name,
+        sku,
+        category,
+        description: optional_text(req.description),
+        ncm,
+        price: Money::new(price, currency.clone()),
+        cost: cost.map(|cost| Money::new(cost, currency)),
+        // O saldo inicial entra no razão logo abaixo
+        stock_quantity: 0,
+        reserved_quantity: 0,
+        is_active: req.is_active.unwrap_or(true),
+        custom_fields: values,
+        tags,
+        created_at: now,
+        updated_at: now,
+    };
+    products.push(product.clone());
+    drop(products);
+    let changed_by = optional_text(req.changed_by);
+    state.price_history.lock().unwrap().push(price_change(&product, None, changed_by.clone(), now));
+
+    if stock_quantity > 0 {
+        let receipt = StockMovement {
+            id: Uuid::new_v4(),
+            product_id: product.id,
+            kind: StockMovementKind::Receipt,
+            location: state.ledger.lock().unwrap().default_location(),
+            quantity: stock_quantity,
+            reference_id: None,
+            reason: Some("Initial stock".to_string()),
+            created_by: changed_by,
+            created_at: now,
+        };
+        if let Err(err) = state.record_movements(vec![receipt]) {
+            return HttpResponse::InternalServerError().json(json!({ "error": err }));
+        }
+    }
+
+    let products = state.products.lock().unwrap();
+    HttpResponse::Created().json(products.iter().find(|p| p.id == product.id))
+}
+
+pub async fn update_product(
+    path: web::Path<Uuid>,
+    product_req: web::Json<ProductUpdate>,
+    state: web::Data<ProductState>,
+    field_state: web::Data<CustomFieldState>,
+) -> impl Responder {
+    let product_id = path.into_inner();
+    let req = product_req.into_inner();
+    let definitions = field_state.definitions.lock().unwrap().clone();
+    let mut products = state.products.lock().unwrap();
+
+    let Some(index) = products.iter().position(|p| p.id == product_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Product not found" }));
+    };
+    let validated = (|| {
+        Ok::<_, String>((
+            req.sku.as_deref().map(validate_sku).transpose()?,
+            req.category.as_deref().map(|c| canonical_category(&products, c)).transpose()?,
+            req.ncm.as_deref().map(|n| validate_ncm(Some(n))).transpose()?,
+            req.price.map(|price| validate_amount("Price", price)).transpose()?,
+            req.cost.map(|cost| validate_amount("Cost", cost)).transpose()?,
+            custom_fields::apply_values(
+                &definitions,
+                CustomFieldEntity::Product,
+                &products[index].custom_fields,
+                &req.custom_fields.unwrap_or_default(),
+            )?,
+            req.tags.as_deref().map(custom_fields::normalize_tags).transpose()?,
+        ))
+    })();
+    let (sku, category, ncm, price, cost, values, tags) = match validated {
+        Ok(validated) => validated,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    if let Some(sku) = &sku {
+        if products.iter().any(|p| p.id != product_id && p.sku.eq_ignore_ascii_case(sku)) {
+            return HttpResponse::Conflict().json(json!({ "error": format!("SKU {} is already in use", sku) }));
+        }
+    }
+    if let Some(name) = &req.name {
+        if name.trim().is_empty() {
+            return HttpResponse::BadRequest().json(json!({ "error": "Product name is required" }));
+        }
+    }
+
+    let previous = products[index].clone();
+    let product = &mut products[index];
+    if let Some(name) = req.name {
+        product.name = name.trim().to_string();
+    }
+    if let Some(sku) = sku {
+        product.sku = sku;
+    }
+    if let Some(category) = category {
+        product.category = category;
+    }
+    if req.description.is_some() {
+        product.description = optional_text(req.description);
+    }
+    if let Some(ncm) = ncm {
+        product.ncm = ncm;
+    }
+    if let Some(price) = price {
+        product.price.amount = price;
+    }
+    if let Some(cost) = cost {
+        product.cost = Some(Money::new(cost, product.price.currency.clone()));
+    }
+    product.custom_fields = values;
+    if let Some(tags) = tags {
+        product.tags = tags;
+    }
+    let now = Utc::now();
+    product.updated_at = now;
+
+    if product.price != previous.price || product.cost != previous.cost {
+        state.price_history.lock().unwrap().push(price_change(product, Some(&previous), optional_text(req.changed_by), now));
+    }
+
+    HttpResponse::Ok().json(product.clone())
+}
+
+fn set_active(state: &ProductState, product_id: Uuid, active: bool) -> HttpResponse {
+    let mut products = state.products.lock().unwrap();
+    match products.iter_mut().find(|p| p.id == product_id) {
+        Some(product) => {
+            if product.is_active != active {
+                product.is_active = active;
+                product.updated_at = Utc::now();
+            }
+            HttpResponse::Ok().json(&*product)
+        }
+        None => HttpResponse::NotFound().json(json!({ "error": "Product not found" })),
+    }
+}
+
+pub async fn activate_product(path: web::Path<Uuid>, state: web::Data<ProductState>) -> impl Responder {
+    set_active(&state, path.into_inner(), true)
+}
+
+// Tira o produto do catálogo de venda sem apagar o histórico
+pub async fn deactivate_product(path: web::Path<Uuid>, state: web::Data<ProductState>) -> impl Responder {
+    set_active(&state, path.into_inner(), false)
+}
+
+pub async fn get_price_history(path: web::Path<Uuid>, state: web::Data<ProductState>) -> impl Responder {
+    let product_id = path.into_inner();
+    if !state.products.lock().unwrap().iter().any(|p| p.id == product_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Product not found" }));
+    }
+
+    let mut history: Vec<ProductPriceChange> = state.price_history.lock().unwrap()
+        .iter()
+        .filter(|c| c.product_id == product_id)
+        .cloned()
+        .collect();
+    history.sort_by_key(|c| c.changed_at);
+    HttpResponse::Ok().json(history)
+}
+
+// Categorias em uso, com a margem média dos produtos ativos que têm custo
+pub async fn get_categories(state: web::Data<ProductState>) -> impl Responder {
+    let products = state.products.lock().unwrap();
+    let mut categories: BTreeMap<&str, Vec<&Product>> = BTreeMap::new();
+    for product in products.iter() {
+        categories.entry(product.category.as_str()).or_default().push(product);
+    }
+
+    let summary: Vec<_> = categories
+        .into_iter()
+        .map(|(category, products)| {
+            let margins: Vec<Decimal> = products
+                .iter()
+                .filter(|p| p.is_active)
+                .filter_map(|p| margin_percent(&p.price, p.cost.as_ref()))
+                .collect();
+            let average_margin = (!margins.is_empty())
+                .then(|| round_cents(margins.iter().sum::<Decimal>() / Decimal::from(margins.len())));
+            json!({
+                "category": category,
+                "products": products.len(),
+                "active_products": products.iter().filter(|p| p.is_active).count(),
+                "average_margin_percent": average_margin,
+            })
+        })
+        .collect();
+    HttpResponse::Ok().json(summary)
+}
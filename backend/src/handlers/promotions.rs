@@ -0,0 +1,286 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::money::{round_cents, ExchangeRates, Money};
+use crate::models::promotion::{Promotion, PromotionCreate, PromotionKind, PromotionRedemption, PromotionReport};
+use crate::models::{Product, Sale, SaleStatus};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+// Teto de `buy` e `get`; a soma dos dois divide a quantidade de cada linha
+const MAX_BUY_GET_QUANTITY: i32 = 1000;
+
+pub struct PromotionState {
+    pub promotions: Mutex<Vec<Promotion>>,
+    pub redemptions: Mutex<Vec<PromotionRedemption>>,
+}
+
+impl PromotionState {
+    pub fn new(product_state: &ProductState) -> Self {
+        let products = product_state.products.lock().unwrap();
+        let by_sku = |sku: &str| products.iter().find(|p| p.sku == sku).map(|p| p.id);
+        let now = Utc::now();
+
+        let mut promotions = vec![Promotion {
+            id: Uuid::new_v4(),
+            code: Some("BEMVINDO10".to_string()),
+            name: "Boas-vindas 10%".to_string(),
+            kind: PromotionKind::Percentage { percent: dec!(10) },
+            starts_at: None,
+            ends_at: None,
+            max_redemptions: None,
+            max_per_customer: Some(1),
+            product_ids: Vec::new(),
+            categories: Vec::new(),
+            stackable: false,
+            priority: 0,
+            active: true,
+            created_at: now,
+        }];
+
+        if let (Some(basic), Some(business)) = (by_sku("BAS-003"), by_sku("BUS-002")) {
+            promotions.push(Promotion {
+                id: Uuid::new_v4(),
+                code: None,
+                name: "Basic Plan: leve 3, pague 2".to_string(),
+                kind: PromotionKind::BuyXGetY { buy: 2, get: 1 },
+                starts_at: None,
+                ends_at: None,
+                max_redemptions: None,
+                max_per_customer: None,
+                product_ids: vec![basic],
+                categories: Vec::new(),
+                stackable: true,
+                priority: 10,
+                active: true,
+                created_at: now,
+            });
+            promotions.push(Promotion {
+                id: Uuid::new_v4(),
+                code: Some("UPGRADE".to_string()),
+                name: "Upgrade gratuito para Business Suite".to_string(),
+                kind: PromotionKind::TierUpgrade { from_product_id: basic, to_product_id: business },
+                starts_at: None,
+                ends_at: None,
+                max_redemptions: Some(100),
+                max_per_customer: Some(1),
+                product_ids: Vec::new(),
+                categories: Vec::new(),
+                stackable: true,
+                priority: 0,
+                active: true,
+                created_at: now,
+            });
+        }
+
+        PromotionState {
+            promotions: Mutex::new(promotions),
+            redemptions: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+fn validate(req: &PromotionCreate, products: &[Product], rates: &ExchangeRates) -> Result<(), String> {
+    if req.name.trim().is_empty() {
+        return Err("Promotion name is required".to_string());
+    }
+    if let Some(code) = &req.code {
+        let code = code.trim();
+        if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err("Coupon code must contain only letters, digits, '-' or '_'".to_string());
+        }
+    }
+    if let (Some(starts_at), Some(ends_at)) = (req.starts_at, req.ends_at) {
+        if ends_at <= starts_at {
+            return Err("Promotion must end after it starts".to_string());
+        }
+    }
+    if req.max_redemptions == Some(0) || req.max_per_customer == Some(0) {
+        return Err("Usage limits must be greater than zero".to_string());
+    }
+    if let Some(id) = req.product_ids.iter().find(|id| !products.iter().any(|p| p.id == **id)) {
+        return Err(format!("Product {} not found", id));
+    }
+
+    match &req.kind {
+        PromotionKind::Percentage { percent } => {
+            if *percent <= Decimal::ZERO || *percent > Decimal::ONE_HUNDRED {
+                return Err("Percentage must be greater than 0 and at most 100".to_string());
+            }
+        }
+        PromotionKind::FixedAmount { amount } => {
+            if amount.amount <= Decimal::ZERO {
+                return Err("Discount amount must be greater than zero".to_string());
+            }
+            if rates.rate(&amount.currency).is_none() {
+                return Err(format!("No exchange rate available for {}", amount.currency));
+            }
+        }
+        PromotionKind::BuyXGetY { buy, get } => {
+            if *buy <= 0 || *get <= 0 {
+                return Err("Buy and get quantities must be greater than zero".to_string());
+            }
+            if *buy > MAX_BUY_GET_QUANTITY || *get > MAX_BUY_GET_QUANTITY {
+                return Err(format!("Buy and get quantities cannot exceed {}", MAX_BUY_GET_QUANTITY));
+            }
+        }
+        PromotionKind::TierUpgrade { from_product_id, to_product_id } => {
+            let find = |id: &Uuid| {
+                products.iter().find(|p| p.id == *id).ok_or_else(|| format!("Product {} not found", id))
+            };
+            let (from, to) = (find(from_product_id)?, find(to_product_id)?);
+            if from.id == to.id {
+                return Err("Upgrade must target a different product".to_string());
+            }
+            let base_price = |p: &Product| rates.convert(&p.price, &rates.base).map(|m| m.amount);
+            if base_price(to) <= base_price(from) {
+                return Err("Upgrade product must be more expensive than the original".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_promotions(state: web::Data<PromotionState>) -> impl Responder {
+    let promotions = state.promotions.lock().unwrap();
+    HttpResponse::Ok().json(&*promotions)
+}
+
+pub async fn get_promotion(path: web::Path<Uuid>, state: web::Data<PromotionState>) -> impl Responder {
+    let promotion_id = path.into_inner();
+    let promotions = state.promotions.lock().unwrap();
+    match promotions.iter().find(|p| p.id == promotion_id) {
+        Some(promotion) => HttpResponse::Ok().json(promotion),
+        None => HttpResponse::NotFound().json(json!({ "error": "Promotion not found" })),
+    }
+}
+
+pub async fn create_promotion(
+    promotion_req: web::Json<PromotionCreate>,
+    state: web::Data<PromotionState>,
+    product_state: web::Data<ProductState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let req = promotion_req.into_inner();
+    if let Err(err) = validate(&req, &product_state.products.lock().unwrap(), &rates) {
+        return HttpResponse::BadRequest().json(json!({ "error": err }));
+    }
+
+    let code = req.code.map(|code| code.trim().to_uppercase());
+    let mut promotions = state.promotions.lock().unwrap();
+    if let Some(code) = &code {
+        if promotions.iter().any(|p| p.code.as_ref() == Some(code)) {
+            return HttpResponse::Conflict().json(json!({
+                "error": format!("Coupon code '{}' is already in use", code)
+            }));
+        }
+    }
+
+    let promotion = Promotion {
+        id: Uuid::new_v4(),
+        code,
+        name: req.name.trim().to_string(),
+        kind: req.kind,
+        starts_at: req.starts_at,
+        ends_at: req.ends_at,
+        max_redemptions: req.max_redemptions,
+        max_per_customer: req.max_per_customer,
+        product_ids: req.product_ids,
+        categories: req.categories,
+        stackable: req.stackable,
+        priority: req.priority,
+        active: true,
+        created_at: Utc::now(),
+    };
+    promotions.push(promotion.clone());
+
+    HttpResponse::Created().json(promotion)
+}
+
+// Encerra a promoção sem apagar o histórico de resgates
+pub async fn deactivate_promotion(path: web::Path<Uuid>, state: web::Data<PromotionState>) -> impl Responder {
+    let promotion_id = path.into_inner();
+    let mut promotions = state.promotions.lock().unwrap();
+    match promotions.iter_mut().find(|p| p.id == promotion_id) {
+        Some(promotion) => {
+            promotion.active = false;
+            HttpResponse::Ok().json(&*promotion)
+        }
+        None => HttpResponse::NotFound().json(json!({ "error": "Promotion not found" })),
+    }
+}
+
+// Resgates e impacto em receita por promoção, na moeda base e só com vendas capturadas
+pub async fn get_promotion_report(
+    state: web::Data<PromotionState>,
+    sales_state: web::Data<SalesState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let promotions = state.promotions.lock().unwrap();
+    let redemptions = state.redemptions.lock().unwrap();
+    let sales = sales_state.sales.lock().unwrap();
+    let base = |amount: Decimal| Money::new(round_cents(amount), rates.base.clone());
+    let average = |total: Decimal, count: usize| {
+        if count == 0 { Decimal::ZERO } else { total / Decimal::from(count) }
+    };
+
+    let captured: Vec<_> = sales.iter().filter(|s| s.status.is_captured()).collect();
+    let revenue_of = |sale: &Sale| sale.to_base(sale.amount.amount - sale.refunded_amount.amount);
+
+    let report: Vec<PromotionReport> = promotions
+        .iter()
+        .map(|promotion| {
+            let redeemed: Vec<_> = redemptions
+                .iter()
+                .filter(|r| r.promotion_id == promotion.id)
+                .filter_map(|r| captured.iter().find(|s| s.id == r.sale_id).map(|sale| (r, *sale)))
+                .collect();
+            let customers: HashSet<Uuid> = redeemed.iter().map(|(r, _)| r.customer_id).collect();
+            let discount: Decimal = redeemed.iter().map(|(r, sale)| sale.to_base(r.discount.amount)).sum();
+            let revenue: Decimal = redeemed.iter().map(|(_, sale)| revenue_of(sale)).sum();
+
+            PromotionReport {
+                promotion_id: promotion.id,
+                code: promotion.code.clone(),
+                name: promotion.name.clone(),
+                kind: promotion.kind.as_str().to_string(),
+                redemptions: redeemed.len(),
+                customers: customers.len(),
+                discount_given: base(discount),
+                revenue: base(revenue),
+                average_order_value: base(average(revenue, redeemed.len())),
+            }
+        })
+        .collect();
+
+    let (promoted, regular): (Vec<&Sale>, Vec<&Sale>) = captured.into_iter().partition(|s| !s.promotions.is_empty());
+    let promoted_revenue: Decimal = promoted.iter().map(|s| revenue_of(s)).sum();
+    let regular_revenue: Decimal = regular.iter().map(|s| revenue_of(s)).sum();
+    let total_revenue = promoted_revenue + regular_revenue;
+    let total_discount: Decimal = promoted
+        .iter()
+        .map(|s| s.to_base(s.promotions.iter().map(|p| p.discount).sum()))
+        .sum();
+    let pending = sales.iter().filter(|s| s.status == SaleStatus::Pending && !s.promotions.is_empty()).count();
+
+    HttpResponse::Ok().json(json!({
+        "currency": rates.base,
+        "total_revenue": round_cents(total_revenue),
+        "promoted_revenue": round_cents(promoted_revenue),
+        "promoted_revenue_share": if total_revenue.is_zero() {
+            Decimal::ZERO
+        } else {
+            round_cents(promoted_revenue * Decimal::ONE_HUNDRED / total_revenue)
+        },
+        "total_discount": round_cents(total_discount),
+        "average_order_value_with_promotions": round_cents(average(promoted_revenue, promoted.len())),
+        "average_order_value_without_promotions": round_cents(average(regular_revenue, regular.len())),
+        "pending_sales_with_promotions": pending,
+        "promotions": report
+    }))
+}
@@ -0,0 +1,443 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::products::ProductState;
+use crate::handlers::promotions::PromotionState;
+use crate::handlers::sales::{place_sale, SalesState};
+use crate::handlers::users::UserState;
+use crate::models::money::{round_cents, ExchangeRates, Money, MAX_UNIT_PRICE};
+use crate::models::query::ListQuery;
+use crate::models::quote::{
+    PipelineBucket, PipelineSnapshot, PipelineStage, Quote, QuoteConvert, QuoteCreate, QuoteItem, QuoteStageUpdate,
+    StageChange,
+};
+use crate::models::tax::TaxTable;
+use crate::models::{SaleCreate, SaleItem, SaleItemCreate};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub struct QuoteState {
+    pub quotes: Mutex<Vec<Quote>>,
+    pub next_number: Mutex<u64>,
+}
+
+impl QuoteState {
+    pub fn new(product_state: &ProductState, customer_state: &CustomerState) -> Self {
+        let products = product_state.products.lock().unwrap().clone();
+        let customers = customer_state.customers.lock().unwrap().clone();
+        let now = Utc::now();
+
+        // (cliente, vendedor, linhas, estágios com dias atrás)
+        let seed = [
+            (0, 3, vec![(0, 2)], vec![(PipelineStage::Lead, 40), (PipelineStage::Qualified, 30), (PipelineStage::Proposal, 12)]),
+            (1, 4, vec![(1, 10)], vec![(PipelineStage::Lead, 25), (PipelineStage::Qualified, 18), (PipelineStage::Negotiation, 4)]),
+            (2, 3, vec![(2, 50)], vec![(PipelineStage::Lead, 9)]),
+            (2, 4, vec![(1, 3)], vec![(PipelineStage::Lead, 35), (PipelineStage::Proposal, 20), (PipelineStage::Lost, 6)]),
+        ];
+
+        let mut quotes = Vec::new();
+        for (number, (customer, rep_id, lines, stages)) in seed.into_iter().enumerate() {
+            let Some(customer) = customers.get(customer) else { continue };
+            let items: Vec<QuoteItem> = lines
+                .iter()
+                .filter_map(|(index, quantity)| {
+                    let product = products.get(*index)?;
+                    let line_total = product.price.amount * Decimal::from(*quantity);
+                    Some(QuoteItem {
+                        product_id: product.id,
+                        quantity: *quantity,
+                        unit_price: product.price.amount,
+                        discount: Decimal::ZERO,
+                        line_total,
+                    })
+                })
+                .collect();
+            let Some(first) = products.get(lines[0].0) else { continue };
+            let stage_history: Vec<StageChange> = stages
+                .iter()
+                .map(|(stage, days_ago)| StageChange {
+                    stage: *stage,
+                    probability: stage.default_probability(),
+                    changed_at: now - Duration::days(*days_ago),
+                })
+                .collect();
+            let created_at = stage_history[0].changed_at;
+            let current = stage_history.last().unwrap().clone();
+
+            quotes.push(Quote {
+                id: Uuid::new_v4(),
+                number: format!("Q-{:06}", number + 1),
+                customer_id: customer.id,
+                rep_id,
+                amount: Money::new(items.iter().map(|i| i.line_total).sum(), first.price.currency.clone()),
+                items,
+                stage: current.stage,
+                probability: current.probability,
+                expires_at: created_at + Duration::days(60),
+                notes: None,
+                lost_reason: (current.stage == PipelineStage::Lost).then(|| "Escolheu um concorrente".to_string()),
+                sale_id: None,
+                stage_history,
+                created_at,
+                updated_at: current.changed_at,
+            });
+        }
+
+        QuoteState {
+            next_number: Mutex::new(quotes.len() as u64 + 1),
+            quotes: Mutex::new(quotes),
+        }
+    }
+}
+
+fn validate_probability(probability: Decimal) -> Result<Decimal, String> {
+    if probability < Decimal::ZERO || probability > Decimal::ONE_HUNDRED {
+        return Err("Probability must be between 0 and 100".to_string());
+    }
+    Ok(probability)
+}
+
+pub async fn get_quotes(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<QuoteState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<Quote>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let quotes = state.quotes.lock().unwrap();
+    HttpResponse::Ok().json(query.apply(&quotes))
+}
+
+pub async fn get_quote(path: web::Path<Uuid>, state: web::Data<QuoteState>) -> impl Responder {
+    let quote_id = path.into_inner();
+    let quotes = state.quotes.lock().unwrap();
+    match quotes.iter().find(|q| q.id == quote_id) {
+        Some(quote) => HttpResponse::Ok().json(json!({ "quote": quote, "expired": quote.is_expired(Utc::now()) })),
+        None => HttpResponse::NotFound().json(json!({ "error": "Quote not found" })),
+    }
+}
+
+pub async fn create_quote(
+    quote_req: web::Json<QuoteCreate>,
+    state: web::Data<QuoteState>,
+    product_state: web::Data<ProductState>,
+    customer_state: web::Data<CustomerState>,
+    user_state: web::Data<UserState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let req = quote_req.into_inner();
+    if req.items.is_empty() {
+        return HttpResponse::BadRequest().json(json!({ "error": "A quote must have at least one item" }));
+    }
+    if !customer_state.customers.lock().unwrap().iter().any(|c| c.id == req.customer_id) {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Customer {} not found", req.customer_id) }));
+    }
+    if !user_state.users.lock().unwrap().iter().any(|u| u.id == req.rep_id) {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Sales rep {} not found", req.rep_id) }));
+    }
+    let valid_days = req.valid_days.unwrap_or(30);
+    if !(1..=365).contains(&valid_days) {
+        return HttpResponse::BadRequest().json(json!({ "error": "Quote validity must be between 1 and 365 days" }));
+    }
+    let stage = req.stage.unwrap_or(PipelineStage::Lead);
+    if stage.is_closed() {
+        return HttpResponse::BadRequest().json(json!({ "error": "A new quote must be in an open stage" }));
+    }
+    let probability = match validate_probability(req.probability.unwrap_or(stage.default_probability())) {
+        Ok(probability) => probability,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let currency = req.currency.clone().unwrap_or_else(|| rates.base.clone());
+    if rates.rate(&currency).is_none() {
+        return HttpResponse::BadRequest().json(json!({
+            "error": format!("No exchange rate available for {}", currency)
+        }));
+    }
+
+    let mut items = Vec::with_capacity(req.items.len());
+    {
+        let products = product_state.products.lock().unwrap();
+        for line in &req.items {
+            let Some(product) = products.iter().find(|p| p.id == line.product_id) else {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": format!("Product {} not found", line.product_id)
+                }));
+            };
+            if !product.is_active {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": format!("Product {} is inactive", product.sku)
+                }));
+            }
+            let unit_price = match line.unit_price.or_else(|| rates.convert(&product.price, &currency).map(|p| p.amount)) {
+                Some(price) => price,
+                None => return HttpResponse::BadRequest().json(json!({
+                    "error": format!("Cannot convert {} to {}", product.price.currency, currency)
+                })),
+            };
+            // O preço negociado vira o preço da linha de venda na conversão
+            if unit_price > MAX_UNIT_PRICE {
+                return HttpResponse::BadRequest().json(json!({
+                    "error": format!("Unit price for product {} cannot exceed {}", product.sku, MAX_UNIT_PRICE)
+                }));
+            }
+            // Mesmas regras de quantidade, preço e desconto de uma linha de venda
+            match SaleItem::new(product.id, line.quantity, unit_price, line.discount.unwrap_or_default()) {
+                Ok(item) => items.push(QuoteItem {
+                    product_id: item.product_id,
+                    quantity: item.quantity,
+                    unit_price: item.unit_price,
+                    discount: item.discount,
+                    line_total: item.line_total,
+                }),
+                Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+            }
+        }
+    }
+
+    let now = Utc::now();
+    let number = {
+        let mut next_number = state.next_number.lock().unwrap();
+        let number = *next_number;
+        *next_number += 1;
+        number
+    };
+    let quote = Quote {
+        id: Uuid::new_v4(),
+        number: format!("Q-{:06}", number),
+        customer_id: req.customer_id,
+        rep_id: req.rep_id,
+        amount: Money::new(items.iter().map(|i| i.line_total).sum(), currency),
+        items,
+        stage,
+        probability,
+        stage_history: vec![StageChange { stage, probability, changed_at: now }],
+        expires_at: now + Duration::days(valid_days),
+        notes: req.notes,
+        lost_reason: None,
+        sale_id: None,
+        created_at: now,
+        updated_at: now,
+    };
+    state.quotes.lock().unwrap().push(quote.clone());
+
+    HttpResponse::Created().json(quote)
+}
+
+pub async fn update_quote_stage(
+    path: web::Path<Uuid>,
+    stage_req: web::Json<QuoteStageUpdate>,
+    state: web::Data<QuoteState>,
+) -> impl Responder {
+    let quote_id = path.into_inner();
+    let mut quotes = state.quotes.lock().unwrap();
+    let Some(quote) = quotes.iter_mut().find(|q| q.id == quote_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Quote not found" }));
+    };
+
+    let now = Utc::now();
+    if quote.stage.is_closed() {
+        return HttpResponse::Conflict().json(json!({
+            "error": format!("Quote is already {}", quote.stage.as_str())
+        }));
+    }
+    if stage_req.stage == PipelineStage::Won {
+        return HttpResponse::BadRequest().json(json!({ "error": "Convert the quote into a sale to mark it as won" }));
+    }
+    // Cotação vencida só pode ser dada como perdida
+    if quote.is_expired(now) && stage_req.stage != PipelineStage::Lost {
+        return HttpResponse::Conflict().json(json!({ "error": "Quote has expired" }));
+    }
+    let probability = if stage_req.stage == PipelineStage::Lost {
+        Decimal::ZERO
+    } else {
+        match validate_probability(stage_req.probability.unwrap_or(stage_req.stage.default_probability())) {
+            Ok(probability) => probability,
+            Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+        }
+    };
+
+    quote.stage = stage_req.stage;
+    quote.probability = probability;
+    quote.lost_reason = stage_req.lost_reason.clone().filter(|_| stage_req.stage == PipelineStage::Lost);
+    quote.stage_history.push(StageChange { stage: quote.stage, probability, changed_at: now });
+    quote.updated_at = now;
+
+    HttpResponse::Ok().json(&*quote)
+}
+
+// Cotação aceita vira venda pendente com os preços negociados
+#[allow(clippy::too_many_arguments)]
+pub async fn convert_quote(
+    path: web::Path<Uuid>,
+    convert_req: web::Json<QuoteConvert>,
+    state: web::Data<QuoteState>,
+    sales_state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+    rates: web::Data<ExchangeRates>,
+    taxes: web::Data<TaxTable>,
+    promotion_state: web::Data<PromotionState>,
+    customer_state: web::Data<CustomerState>,
+) -> impl Responder {
+    let quote_id = path.into_inner();
+    // Mesma ordem da exclusão de clientes: cadastro antes das cotações
+    let customers = customer_state.customers.lock().unwrap();
+    let mut quotes = state.quotes.lock().unwrap();
+    let Some(quote) = quotes.iter_mut().find(|q| q.id == quote_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Quote not found" }));
+    };
+
+    let now = Utc::now();
+    if quote.stage.is_closed() {
+        return HttpResponse::Conflict().json(json!({
+            "error": format!("Quote is already {}", quote.stage.as_str())
+        }));
+    }
+    if quote.is_expired(now) {
+        return HttpResponse::Conflict().json(json!({ "error": "Quote has expired" }));
+    }
+
+    let req = convert_req.into_inner();
+    let sale_req = SaleCreate {
+        customer_id: quote.customer_id,
+        items: quote.items
+            .iter()
+            .map(|item| SaleItemCreate {
+                product_id: item.product_id,
+                quantity: item.quantity,
+                discount: Some(item.discount),
+            })
+            .collect(),
+        payment_method: req.payment_method,
+        currency: Some(quote.currency().clone()),
+        tax_region: req.tax_region,
+        prices_include_tax: Some(false),
+        coupon_codes: req.coupon_codes,
+        notes: Some(format!("Quote {}", quote.number)),
+        warehouse: req.warehouse,
+    };
+    // Os preços negociados na cotação substituem os de catálogo
+    let quoted_prices: Vec<Decimal> = quote.items.iter().map(|item| item.unit_price).collect();
+    let (sale, skipped) = match place_sale(
+        &sale_req, Some(&quoted_prices), &customers, &sales_state, &product_state, &rates, &taxes, &promotion_state,
+    ) {
+        Ok(placed) => placed,
+        Err(response) => return response,
+    };
+
+    quote.stage = PipelineStage::Won;
+    quote.probability = PipelineStage::Won.default_probability();
+    quote.sale_id = Some(sale.id);
+    quote.stage_history.push(StageChange { stage: quote.stage, probability: quote.probability, changed_at: now });
+    quote.updated_at = now;
+
+    HttpResponse::Created().json(json!({
+        "quote": quote,
+        "sale": sale,
+        "skipped_promotions": skipped,
+        "fulfillment": product_state.sale_allocations(sale.id)
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PipelineQuery {
+    pub rep_id: Option<u64>,
+    // Semanas de histórico na evolução do pipeline
+    pub weeks: Option<i64>,
+}
+
+fn add_to_bucket(buckets: &mut Vec<PipelineBucket>, key: String, value: Decimal, probability: Decimal) {
+    let weighted = value * probability / Decimal::ONE_HUNDRED;
+    match buckets.iter_mut().find(|b| b.key == key) {
+        Some(bucket) => {
+            bucket.count += 1;
+            bucket.value += value;
+            bucket.weighted_value += weighted;
+        }
+        None => buckets.push(PipelineBucket { key, count: 1, value, weighted_value: weighted }),
+    }
+}
+
+// Pipeline aberto ponderado pela probabilidade, na moeda base
+pub async fn get_pipeline(
+    query: web::Query<PipelineQuery>,
+    state: web::Data<QuoteState>,
+    user_state: web::Data<UserState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let weeks = query.weeks.unwrap_or(8);
+    if !(1..=52).contains(&weeks) {
+        return HttpResponse::BadRequest().json(json!({ "error": "'weeks' must be between 1 and 52" }));
+    }
+
+    let quotes = state.quotes.lock().unwrap();
+    let quotes: Vec<&Quote> = quotes.iter().filter(|q| query.rep_id.is_none_or(|rep| q.rep_id == rep)).collect();
+    let base_value = |quote: &Quote| rates.convert(&quote.amount, &rates.base).map(|m| m.amount).unwrap_or_default();
+    let now = Utc::now();
+
+    let users = user_state.users.lock().unwrap();
+    let mut by_stage: Vec<PipelineBucket> = Vec::new();
+    let mut by_rep: Vec<PipelineBucket> = Vec::new();
+    for quote in quotes.iter().filter(|q| !q.stage.is_closed() && !q.is_expired(now)) {
+        let value = base_value(quote);
+        add_to_bucket(&mut by_stage, quote.stage.as_str().to_string(), value, quote.probability);
+        let rep = users
+            .iter()
+            .find(|u| u.id == quote.rep_id)
+            .map(|u| u.name.clone())
+            .unwrap_or_else(|| quote.rep_id.to_string());
+        add_to_bucket(&mut by_rep, rep, value, quote.probability);
+    }
+    by_stage.sort_by_key(|b| PipelineStage::ALL.iter().position(|s| s.as_str() == b.key));
+    by_rep.sort_by_key(|b| std::cmp::Reverse(b.weighted_value));
+
+    // Foto semanal do pipeline aberto, reconstruída pelo histórico de estágios
+    let snapshot = |date: DateTime<Utc>| {
+        let open: Vec<(&Quote, &StageChange)> = quotes
+            .iter()
+            .filter(|q| q.created_at <= date && date < q.expires_at)
+            .filter_map(|q| q.stage_at(date).map(|change| (*q, change)))
+            .filter(|(_, change)| !change.stage.is_closed())
+            .collect();
+        PipelineSnapshot {
+            date,
+            count: open.len(),
+            value: round_cents(open.iter().map(|(q, _)| base_value(q)).sum()),
+            weighted_value: round_cents(
+                open.iter().map(|(q, c)| base_value(q) * c.probability / Decimal::ONE_HUNDRED).sum(),
+            ),
+        }
+    };
+    let timeline: Vec<PipelineSnapshot> = (0..=weeks).rev().map(|week| snapshot(now - Duration::weeks(week))).collect();
+
+    let closed = |stage: PipelineStage| quotes.iter().filter(|q| q.stage == stage).count();
+    let (won, lost) = (closed(PipelineStage::Won), closed(PipelineStage::Lost));
+    let round = |mut buckets: Vec<PipelineBucket>| {
+        for bucket in buckets.iter_mut() {
+            bucket.value = round_cents(bucket.value);
+            bucket.weighted_value = round_cents(bucket.weighted_value);
+        }
+        buckets
+    };
+
+    HttpResponse::Ok().json(json!({
+        "currency": rates.base,
+        "open_value": round_cents(by_stage.iter().map(|b| b.value).sum()),
+        "weighted_value": round_cents(by_stage.iter().map(|b| b.weighted_value).sum()),
+        "won": won,
+        "lost": lost,
+        "win_rate": if won + lost == 0 {
+            Decimal::ZERO
+        } else {
+            round_cents(Decimal::from(won * 100) / Decimal::from(won + lost))
+        },
+        "by_stage": round(by_stage),
+        "by_rep": round(by_rep),
+        "timeline": timeline
+    }))
+}
@@ -0,0 +1,173 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::money::{round_cents, Money};
+use crate::models::{Refund, RefundCreate, RefundLine, Sale, SaleStatus};
+use chrono::Utc;
+use rust_decimal::{Decimal, RoundingStrategy};
+use uuid::Uuid;
+
+fn build_refund(sale: &mut Sale, req: &RefundCreate) -> Result<Refund, String> {
+    if !matches!(sale.status, SaleStatus::Completed | SaleStatus::PartiallyRefunded) {
+        return Err(format!("Sale with status '{}' cannot be refunded", sale.status.as_str()));
+    }
+
+    let remaining = sale.net_amount()?.amount;
+    let mut lines = Vec::new();
+
+    match (&req.lines, req.amount) {
+        (Some(_), Some(_)) => return Err("Provide either amount or lines, not both".to_string()),
+        (Some(requested), None) => {
+            if requested.is_empty() {
+                return Err("Refund lines cannot be empty".to_string());
+            }
+            for line in requested {
+                let item = sale.items.iter_mut()
+                    .find(|item| item.product_id == line.product_id)
+                    .ok_or_else(|| format!("Product {} is not part of this sale", line.product_id))?;
+
+                let refundable = item.quantity - item.refunded_quantity;
+                if line.quantity <= 0 || line.quantity > refundable {
+                    return Err(format!(
+                        "Refund quantity for product {} must be between 1 and {}",
+                        line.product_id, refundable
+                    ));
+                }
+
+                // A última unidade leva o saldo da linha para não sobrar centavos
+                let amount = if line.quantity == refundable {
+                    item.net_total()
+                } else {
+                    round_cents(item.line_total / Decimal::from(item.quantity) * Decimal::from(line.quantity))
+                        .min(item.net_total())
+                };
+                item.refunded_quantity += line.quantity;
+                item.refunded_amount += amount;
+                lines.push(RefundLine { product_id: item.product_id, quantity: line.quantity, amount });
+            }
+        }
+        (None, amount) => {
+            if req.restock {
+                return Err("Restocking requires refund lines".to_string());
+            }
+            let amount = round_cents(amount.unwrap_or(remaining));
+            if amount <= Decimal::ZERO || amount > remaining {
+                return Err(format!("Refund amount must be between 0 and {:.2}", remaining));
+            }
+            // Valor livre é distribuído proporcionalmente entre as linhas pelo método
+            // do maior resto: cada linha recebe a parte truncada em centavos, limitada
+            // ao seu saldo, e os centavos restantes vão para as maiores frações
+            let cent = Decimal::new(1, 2);
+            let exact: Vec<Decimal> = sale.items.iter()
+                .map(|item| amount * item.net_total() / remaining)
+                .collect();
+            let mut shares: Vec<Decimal> = sale.items.iter().zip(&exact)
+                .map(|(item, exact)| {
+                    exact.round_dp_with_strategy(2, RoundingStrategy::ToZero).min(item.net_total())
+                })
+                .collect();
+            let mut order: Vec<usize> = (0..shares.len()).collect();
+            order.sort_by(|&a, &b| (exact[b] - shares[b]).cmp(&(exact[a] - shares[a])));
+            let mut residue = amount - shares.iter().copied().sum::<Decimal>();
+            while residue > Decimal::ZERO {
+                let before = residue;
+                for &index in &order {
+                    if residue > Decimal::ZERO && shares[index] < sale.items[index].net_total() {
+                        shares[index] += cent;
+                        residue -= cent;
+                    }
+                }
+                if residue == before {
+                    return Err(format!("Refund amount must not exceed {:.2}", remaining));
+                }
+            }
+            for (item, share) in sale.items.iter_mut().zip(shares) {
+                item.refunded_amount += share;
+                lines.push(RefundLine { product_id: item.product_id, quantity: 0, amount: share });
+            }
+        }
+    }
+
+    let amount: Decimal = lines.iter().map(|line| line.amount).sum();
+    if amount > remaining {
+        return Err(format!("Refund amount must not exceed {:.2}", remaining));
+    }
+
+    let amount = Money::new(amount, sale.currency().clone());
+    sale.refunded_amount = sale.refunded_amount.checked_add(&amount)?;
+    sale.status = if sale.net_amount()?.amount.is_zero() {
+        SaleStatus::Refunded
+    } else {
+        SaleStatus::PartiallyRefunded
+    };
+
+    Ok(Refund {
+        id: Uuid::new_v4(),
+        sale_id: sale.id,
+        amount,
+        reason: req.reason,
+        note: req.note.clone(),
+        lines,
+        restocked: req.restock,
+        created_at: Utc::now(),
+    })
+}
+
+pub async fn create_refund(
+    path: web::Path<Uuid>,
+    refund_req: web::Json<RefundCreate>,
+    state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+) -> impl Responder {
+    let sale_id = path.into_inner();
+    let mut sales = state.sales.lock().unwrap();
+
+    let Some(sale) = sales.iter_mut().find(|s| s.id == sale_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Sale not found" }));
+    };
+
+    // Trabalha numa cópia para não deixar a venda pela metade em caso de erro
+    let mut updated = sale.clone();
+    let mut refund = match build_refund(&mut updated, &refund_req) {
+        Ok(refund) => refund,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    *sale = updated.clone();
+
+    let mut refunds = state.refunds.lock().unwrap();
+    if refund.restocked {
+        let earlier: Vec<Uuid> = refunds.iter().filter(|r| r.sale_id == sale_id).map(|r| r.id).collect();
+        refund.restocked = product_state.restock(refund.sale_id, refund.id, &earlier, &refund.lines) > 0;
+    }
+    refunds.push(refund.clone());
+    drop(refunds);
+
+    HttpResponse::Created().json(json!({
+        "message": "Refund created successfully",
+        "refund": refund,
+        "sale": updated
+    }))
+}
+
+pub async fn get_refunds(
+    path: web::Path<Uuid>,
+    state: web::Data<SalesState>,
+) -> impl Responder {
+    let sale_id = path.into_inner();
+
+    if !state.sales.lock().unwrap().iter().any(|s| s.id == sale_id) {
+        return HttpResponse::NotFound().json(json!({ "error": "Sale not found" }));
+    }
+
+    let refunds: Vec<Refund> = state.refunds.lock().unwrap()
+        .iter()
+        .filter(|r| r.sale_id == sale_id)
+        .cloned()
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "total_refunded": refunds.iter().map(|r| r.amount.amount).sum::<Decimal>(),
+        "refunds": refunds
+    }))
+}
@@ -1,67 +1,436 @@
 use actix_web::{web, HttpResponse, Responder};
 use serde_json::json;
-use crate::models::{Sale, SaleCreate, SaleStatus};
-use chrono::Utc;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::payments::PaymentState;
+use crate::handlers::products::ProductState;
+use crate::handlers::promotions::PromotionState;
+use crate::models::money::{ExchangeRates, Money};
+use crate::models::{
+    Customer, PaymentIntentStatus, PaymentMethod, Product, ProductRevenue, Refund, Sale, SaleCreate, SaleItem, SaleSnapshot,
+    SaleStatus,
+};
+use crate::models::promotion::{PromotionRedemption, SkippedPromotion};
+use crate::models::query::ListQuery;
+use crate::models::tax::TaxTable;
+use crate::services::promotions::{apply_promotions, PromotionContext};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+use std::sync::Mutex;
 use uuid::Uuid;
 
-pub async fn get_sales() -> impl Responder {
-    let sales = vec![
-        Sale {
-            id: Uuid::new_v4(),
-            customer_id: Uuid::new_v4(),
-            product_id: Uuid::new_v4(),
-            amount: 4999.99,
-            quantity: 1,
-            status: SaleStatus::Completed,
-            payment_method: "credit_card".to_string(),
-            created_at: Utc::now(),
-        },
-        Sale {
-            id: Uuid::new_v4(),
-            customer_id: Uuid::new_v4(),
-            product_id: Uuid::new_v4(),
-            amount: 899.99,
-            quantity: 1,
-            status: SaleStatus::Pending,
-            payment_method: "paypal".to_string(),
-            created_at: Utc::now(),
-        },
-        Sale {
-            id: Uuid::new_v4(),
-            customer_id: Uuid::new_v4(),
-            product_id: Uuid::new_v4(),
-            amount: 249.99,
-            quantity: 1,
-            status: SaleStatus::Completed,
-            payment_method: "bank_transfer".to_string(),
-            created_at: Utc::now(),
-        },
-    ];
-    
-    HttpResponse::Ok().json(json!({
-        "sales": sales,
-        "total": sales.len(),
-        "total_revenue": sales.iter().map(|s| s.amount).sum::<f64>(),
-        "page": 1,
-        "per_page": 10
-    }))
+pub struct SalesState {
+    pub sales: Mutex<Vec<Sale>>,
+    pub refunds: Mutex<Vec<Refund>>,
+}
+
+impl SalesState {
+    pub fn new(product_state: &ProductState, customer_state: &CustomerState, taxes: &TaxTable) -> Self {
+        let products = product_state.products.lock().unwrap().clone();
+        let customers = customer_state.customers.lock().unwrap().clone();
+        let now = Utc::now();
+
+        let seed = [
+            (0, vec![(0, 1)], SaleStatus::Completed, PaymentMethod::CreditCard, 2),
+            (1, vec![(1, 1), (2, 3)], SaleStatus::Pending, PaymentMethod::Paypal, 1),
+            (2, vec![(2, 2)], SaleStatus::Completed, PaymentMethod::BankTransfer, 0),
+        ];
+
+        let sales: Vec<Sale> = seed
+            .iter()
+            .map(|(customer, lines, status, payment_method, days_ago)| {
+                let items: Vec<SaleItem> = lines
+                    .iter()
+                    .filter_map(|(index, quantity)| {
+                        let product = &products[*index];
+                        let mut item = SaleItem::new(product.id, *quantity, product.price.amount, Decimal::ZERO).ok()?;
+                        let line_taxes = taxes.calculate(
+                            item.line_total,
+                            &product.category,
+                            &taxes.default_region,
+                            taxes.prices_include_tax,
+                        );
+                        item.apply_taxes(line_taxes, taxes.prices_include_tax);
+                        Some(item)
+                    })
+                    .collect();
+                let currency = products[lines[0].0].price.currency.clone();
+
+                Sale {
+                    id: Uuid::new_v4(),
+                    customer_id: customers[*customer].id,
+                    amount: Sale::total_from(&items, currency.clone()),
+                    refunded_amount: Money::zero(currency),
+                    exchange_rate: Decimal::ONE,
+                    tax_region: taxes.default_region.clone(),
+                    prices_include_tax: taxes.prices_include_tax,
+                    promotions: Vec::new(),
+                    subscription_id: None,
+                    notes: None,
+                    snapshot: SaleSnapshot::capture(&customers[*customer], &products, &items),
+                    items,
+                    status: *status,
+                    payment_method: *payment_method,
+                    created_at: now - Duration::days(*days_ago),
+                }
+            })
+            .collect();
+
+        // Vendas pendentes seguram estoque; as concluídas já saíram do razão
+        for sale in &sales {
+            if !matches!(sale.status, SaleStatus::Pending | SaleStatus::Completed) {
+                continue;
+            }
+            let reserved = product_state.reserve_stock(sale.id, &sale.items, None).is_ok();
+            if reserved && sale.status == SaleStatus::Completed {
+                product_state.commit_stock(sale.id);
+            }
+        }
+
+        SalesState {
+            sales: Mutex::new(sales),
+            refunds: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Cancela vendas pendentes: a reserva de estoque é liberada e as cobranças em aberto são encerradas.
+    // Trava as cobranças antes das vendas, como o webhook, para um pagamento não concluir uma venda cancelada
+    pub fn cancel_pending(
+        &self,
+        sale_ids: &[Uuid],
+        reason: &str,
+        product_state: &ProductState,
+        payment_state: &PaymentState,
+    ) -> Vec<Sale> {
+        let mut intents = payment_state.intents.lock().unwrap();
+        let mut sales = self.sales.lock().unwrap();
+        let now = Utc::now();
+        let mut cancelled = Vec::new();
+        for sale in sales.iter_mut().filter(|s| sale_ids.contains(&s.id) && s.status == SaleStatus::Pending) {
+            sale.status = SaleStatus::Cancelled;
+            product_state.release_stock(sale.id);
+            for intent in intents.iter_mut().filter(|i| i.sale_id == sale.id && i.status == PaymentIntentStatus::Pending) {
+                intent.status = PaymentIntentStatus::Failed;
+                intent.failure_reason = Some(reason.to_string());
+                intent.updated_at = now;
+            }
+            cancelled.push(sale.clone());
+        }
+        cancelled
+    }
+
+    // Reservas mais antigas que o prazo: a venda pendente é cancelada; reserva sem venda pendente só é liberada
+    pub fn expire_reservations(
+        &self,
+        ttl: Duration,
+        product_state: &ProductState,
+        payment_state: &PaymentState,
+        now: DateTime<Utc>,
+    ) -> Vec<Sale> {
+        let sale_ids = product_state.expired_reservations(now - ttl);
+        if sale_ids.is_empty() {
+            return Vec::new();
+        }
+        let cancelled = self.cancel_pending(&sale_ids, "Stock reservation expired", product_state, payment_state);
+        for sale_id in sale_ids.iter().filter(|id| !cancelled.iter().any(|s| s.id == **id)) {
+            product_state.release_stock(*sale_id);
+        }
+        cancelled
+    }
 }
 
-pub async fn create_sale(sale_req: web::Json<SaleCreate>) -> impl Responder {
+// Receita líquida (descontados os reembolsos) por produto na moeda base, a partir das linhas
+pub fn revenue_by_product(sales: &[Sale], products: &[Product], rates: &ExchangeRates) -> Vec<ProductRevenue> {
+    let mut revenue: Vec<ProductRevenue> = Vec::new();
+
+    for sale in sales.iter().filter(|s| s.status.is_captured()) {
+        for item in &sale.items {
+            let net = sale.to_base(item.net_total());
+            let tax = sale.to_base(item.net_tax());
+            match revenue.iter_mut().find(|r| r.product_id == item.product_id) {
+                Some(entry) => {
+                    entry.quantity += item.quantity - item.refunded_quantity;
+                    entry.orders += 1;
+                    entry.revenue += net;
+                    entry.tax += tax;
+                }
+                None => revenue.push(ProductRevenue {
+                    product_id: item.product_id,
+                    name: products.iter()
+                        .find(|p| p.id == item.product_id)
+                        .map(|p| p.name.clone())
+                        .unwrap_or_else(|| "Unknown product".to_string()),
+                    quantity: item.quantity - item.refunded_quantity,
+                    orders: 1,
+                    revenue: Money::new(net, rates.base.clone()),
+                    tax: Money::new(tax, rates.base.clone()),
+                }),
+            }
+        }
+    }
+
+    revenue.sort_by_key(|r| std::cmp::Reverse(r.revenue.amount));
+    revenue
+}
+
+pub async fn get_sales(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<SalesState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<Sale>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
+    let sales = state.sales.lock().unwrap();
+    HttpResponse::Ok().json(query.apply(&sales))
+}
+
+// Cria a venda pendente: preços, promoções, impostos e reserva de estoque.
+// `quoted_prices` traz o preço de cada linha fixado por uma cotação; a API pública
+// de vendas sempre usa o preço de catálogo. `customers` vem travado pelo chamador
+// até a venda ser gravada, para que a exclusão do cliente não corra com ela
+#[allow(clippy::too_many_arguments)]
+pub fn place_sale(
+    sale_req: &SaleCreate,
+    quoted_prices: Option<&[Decimal]>,
+    customers: &[Customer],
+    state: &SalesState,
+    product_state: &ProductState,
+    rates: &ExchangeRates,
+    taxes: &TaxTable,
+    promotion_state: &PromotionState,
+) -> Result<(Sale, Vec<SkippedPromotion>), HttpResponse> {
+    if sale_req.items.is_empty() {
+        return Err(HttpResponse::BadRequest().json(json!({ "error": "A sale must have at least one item" })));
+    }
+    let customer = match customers.iter().find(|c| c.id == sale_req.customer_id) {
+        None => return Err(HttpResponse::BadRequest().json(json!({
+            "error": format!("Customer {} not found", sale_req.customer_id)
+        }))),
+        Some(customer) if customer.anonymized_at.is_some() => return Err(HttpResponse::BadRequest().json(json!({
+            "error": format!("Customer {} has been anonymized", sale_req.customer_id)
+        }))),
+        Some(customer) => customer,
+    };
+
+    let currency = sale_req.currency.clone().unwrap_or_else(|| rates.base.clone());
+    let Some(exchange_rate) = rates.rate(&currency) else {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "error": format!("No exchange rate available for {}", currency)
+        })));
+    };
+
+    let tax_region = sale_req.tax_region.clone()
+        .map(|region| region.trim().to_uppercase())
+        .unwrap_or_else(|| taxes.default_region.clone());
+    let prices_include_tax = sale_req.prices_include_tax.unwrap_or(taxes.prices_include_tax);
+    let warehouse = match sale_req.warehouse.as_deref().map(|code| product_state.check_warehouse(code)).transpose() {
+        Ok(warehouse) => warehouse,
+        Err(err) => return Err(HttpResponse::BadRequest().json(json!({ "error": err }))),
+    };
+
+    // Vendas que falharam ou foram canceladas não contam para os limites de uso das promoções
+    let failed_sales: HashSet<Uuid> = state.sales.lock().unwrap()
+        .iter()
+        .filter(|s| matches!(s.status, SaleStatus::Failed | SaleStatus::Cancelled))
+        .map(|s| s.id)
+        .collect();
+
+    // Promoções ficam travadas até o resgate ser registrado, para respeitar os limites de uso
+    let promotions = promotion_state.promotions.lock().unwrap();
+    let mut redemptions = promotion_state.redemptions.lock().unwrap();
+
+    let mut items = Vec::with_capacity(sale_req.items.len());
+    let (outcome, snapshot) = {
+        let products = product_state.products.lock().unwrap();
+        for (index, line) in sale_req.items.iter().enumerate() {
+            let Some(product) = products.iter().find(|p| p.id == line.product_id) else {
+                return Err(HttpResponse::BadRequest().json(json!({
+                    "error": format!("Product {} not found", line.product_id)
+                })));
+            };
+            if !product.is_active {
+                return Err(HttpResponse::BadRequest().json(json!({
+                    "error": format!("Product {} is inactive", product.sku)
+                })));
+            }
+            // Preço de catálogo é convertido para a moeda da venda
+            let unit_price = match quoted_prices.and_then(|prices| prices.get(index).copied()) {
+                Some(price) => price,
+                None => match rates.convert(&product.price, &currency) {
+                    Some(price) => price.amount,
+                    None => return Err(HttpResponse::BadRequest().json(json!({
+                        "error": format!("Cannot convert {} to {}", product.price.currency, currency)
+                    }))),
+                },
+            };
+            match SaleItem::new(product.id, line.quantity, unit_price, line.discount.unwrap_or_default()) {
+                Ok(item) => items.push(item),
+                Err(err) => return Err(HttpResponse::BadRequest().json(json!({ "error": err }))),
+            }
+        }
+
+        let counted: Vec<PromotionRedemption> = redemptions
+            .iter()
+            .filter(|r| !failed_sales.contains(&r.sale_id))
+            .cloned()
+            .collect();
+        let ctx = PromotionContext {
+            customer_id: sale_req.customer_id,
+            currency: &currency,
+            rates,
+            products: &products,
+            redemptions: &counted,
+            now: Utc::now(),
+        };
+        let mut outcome = match apply_promotions(&promotions, &sale_req.coupon_codes, &items, &ctx) {
+            Ok(outcome) => outcome,
+            Err(err) => return Err(HttpResponse::BadRequest().json(json!({ "error": err }))),
+        };
+
+        // Impostos calculados por linha, já com os descontos, conforme a categoria do produto e a região da venda
+        for item in outcome.items.iter_mut() {
+            let category = products.iter()
+                .find(|p| p.id == item.product_id)
+                .map(|p| p.category.as_str())
+                .unwrap_or_default();
+            let line_taxes = taxes.calculate(item.line_total, category, &tax_region, prices_include_tax);
+            item.apply_taxes(line_taxes, prices_include_tax);
+        }
+        let snapshot = SaleSnapshot::capture(customer, &products, &outcome.items);
+        (outcome, snapshot)
+    };
+    let items = outcome.items;
+
+    // Venda pendente reserva o estoque de cada linha
+    let sale_id = Uuid::new_v4();
+    if let Err(err) = product_state.reserve_stock(sale_id, &items, warehouse.as_deref()) {
+        return Err(HttpResponse::Conflict().json(json!({ "error": err })));
+    }
+
     let sale = Sale {
-        id: Uuid::new_v4(),
+        id: sale_id,
         customer_id: sale_req.customer_id,
-        product_id: sale_req.product_id,
-        amount: sale_req.amount,
-        quantity: sale_req.quantity,
+        amount: Sale::total_from(&items, currency.clone()),
+        refunded_amount: Money::zero(currency),
+        exchange_rate,
+        tax_region,
+        prices_include_tax,
+        promotions: outcome.applied,
+        subscription_id: None,
+        notes: sale_req.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()).map(str::to_string),
+        snapshot,
+        items,
         status: SaleStatus::Pending,
-        payment_method: sale_req.payment_method.clone(),
+        payment_method: sale_req.payment_method,
         created_at: Utc::now(),
     };
-    
-    HttpResponse::Created().json(json!({
-        "message": "Sale created successfully",
-        "sale": sale,
-        "transaction_id": Uuid::new_v4().to_string()
+
+    for applied in &sale.promotions {
+        redemptions.push(PromotionRedemption {
+            id: Uuid::new_v4(),
+            promotion_id: applied.promotion_id,
+            sale_id: sale.id,
+            customer_id: sale.customer_id,
+            discount: Money::new(applied.discount, sale.currency().clone()),
+            created_at: sale.created_at,
+        });
+    }
+    state.sales.lock().unwrap().push(sale.clone());
+
+    Ok((sale, outcome.skipped))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn create_sale(
+    sale_req: web::Json<SaleCreate>,
+    state: web::Data<SalesState>,
+    customer_state: web::Data<CustomerState>,
+    product_state: web::Data<ProductState>,
+    rates: web::Data<ExchangeRates>,
+    taxes: web::Data<TaxTable>,
+    promotion_state: web::Data<PromotionState>,
+) -> impl Responder {
+    let customers = customer_state.customers.lock().unwrap();
+    match place_sale(&sale_req, None, &customers, &state, &product_state, &rates, &taxes, &promotion_state) {
+        Ok((sale, skipped)) => HttpResponse::Created().json(json!({
+            "message": "Sale created successfully",
+            "fulfillment": product_state.sale_allocations(sale.id),
+            "sale": sale,
+            "skipped_promotions": skipped,
+            "transaction_id": Uuid::new_v4().to_string()
+        })),
+        Err(response) => response,
+    }
+}
+
+// Só vendas pendentes podem ser canceladas; as concluídas seguem pelo reembolso
+pub async fn cancel_sale(
+    path: web::Path<Uuid>,
+    state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+    payment_state: web::Data<PaymentState>,
+) -> impl Responder {
+    let sale_id = path.into_inner();
+    let mut cancelled = state.cancel_pending(&[sale_id], "Sale cancelled", &product_state, &payment_state);
+    if let Some(sale) = cancelled.pop() {
+        return HttpResponse::Ok().json(sale);
+    }
+    match state.sales.lock().unwrap().iter().find(|s| s.id == sale_id) {
+        Some(sale) => HttpResponse::Conflict().json(json!({
+            "error": format!("Only pending sales can be cancelled; this sale is {}", sale.status.as_str())
+        })),
+        None => HttpResponse::NotFound().json(json!({ "error": "Sale not found" })),
+    }
+}
+
+pub struct RevenueTotals {
+    // Valor cobrado, com impostos
+    pub gross: Money,
+    pub refunds: Money,
+    // Cobrado menos reembolsos
+    pub total: Money,
+    // Impostos sobre o que não foi reembolsado
+    pub tax: Money,
+    // Receita sem impostos e sem reembolsos
+    pub net: Money,
+}
+
+// Totais na moeda base
+pub fn revenue_totals(sales: &[Sale], rates: &ExchangeRates) -> RevenueTotals {
+    let captured = sales.iter().filter(|s| s.status.is_captured());
+    let gross: Decimal = captured.clone().map(|s| s.to_base(s.amount.amount)).sum();
+    let refunds: Decimal = captured.clone().map(|s| s.to_base(s.refunded_amount.amount)).sum();
+    let tax: Decimal = captured.map(|s| s.to_base(s.net_tax().amount)).sum();
+
+    RevenueTotals {
+        gross: Money::new(gross, rates.base.clone()),
+        refunds: Money::new(refunds, rates.base.clone()),
+        total: Money::new(gross - refunds, rates.base.clone()),
+        tax: Money::new(tax, rates.base.clone()),
+        net: Money::new(gross - refunds - tax, rates.base.clone()),
+    }
+}
+
+pub async fn get_sales_analytics(
+    state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let sales = state.sales.lock().unwrap();
+    let products = product_state.products.lock().unwrap();
+    let by_product = revenue_by_product(&sales, &products, &rates);
+    let totals = revenue_totals(&sales, &rates);
+
+    HttpResponse::Ok().json(json!({
+        "currency": rates.base,
+        "gross_revenue": totals.gross.amount,
+        "total_refunds": totals.refunds.amount,
+        "total_revenue": totals.total.amount,
+        "total_tax": totals.tax.amount,
+        "net_revenue": totals.net.amount,
+        "items_sold": by_product.iter().map(|p| p.quantity).sum::<i32>(),
+        "revenue_by_product": by_product
     }))
 }
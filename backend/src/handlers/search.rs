@@ -0,0 +1,198 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use crate::handlers::auth::{bearer_claims, AppState as AuthState};
+use crate::handlers::customers::CustomerState;
+use crate::handlers::payments::PaymentState;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::search::{SearchEntity, SearchResults};
+use crate::models::PaymentInstructions;
+use crate::services::search::{self, IndexedField, SearchDocument, SearchIndex};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+const MIN_QUERY_LENGTH: usize = 2;
+
+pub struct SearchState {
+    pub index: Mutex<SearchIndex>,
+}
+
+fn field(name: &'static str, weight: f64, text: &str) -> IndexedField {
+    IndexedField { name, weight, text: text.to_string() }
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        SearchState {
+            index: Mutex::new(SearchIndex::build(Vec::new(), Utc::now())),
+        }
+    }
+
+    // Reconstrói o índice a partir dos cadastros; roda na subida, no agendador, em `/search/reindex` e logo
+    // depois de apagar, anonimizar ou mesclar clientes, para dados removidos não continuarem pesquisáveis
+    pub fn refresh(
+        &self,
+        customer_state: &CustomerState,
+        product_state: &ProductState,
+        sales_state: &SalesState,
+        payment_state: &PaymentState,
+    ) -> usize {
+        let mut documents = Vec::new();
+        let document = |entity, id, title: String, subtitle: Option<String>, fields| SearchDocument {
+            entity,
+            id,
+            title,
+            subtitle,
+            fields,
+        };
+
+        let customer_names: HashMap<Uuid, String> = {
+            let customers = customer_state.customers.lock().unwrap();
+            // Cadastros anonimizados não são pesquisáveis
+            for customer in customers.iter().filter(|c| c.anonymized_at.is_none()) {
+                documents.push(document(
+                    SearchEntity::Customer,
+                    customer.id,
+                    customer.name.clone(),
+                    Some(format!("{} · {}", customer.company, customer.email)),
+                    vec![
+                        field("name", 3.0, &customer.name),
+                        field("company", 2.0, &customer.company),
+                        field("email", 2.0, &customer.email),
+                    ],
+                ));
+            }
+            customers.iter().map(|c| (c.id, c.name.clone())).collect()
+        };
+
+        for product in product_state.products.lock().unwrap().iter() {
+            documents.push(document(
+                SearchEntity::Product,
+                product.id,
+                product.name.clone(),
+                Some(product.sku.clone()),
+                vec![
+                    field("name", 3.0, &product.name),
+                    field("sku", 3.0, &product.sku),
+                    field("description", 1.0, product.description.as_deref().unwrap_or_default()),
+                ],
+            ));
+        }
+
+        // Ids das cobranças e txid do PIX identificam a venda junto ao gateway
+        let mut transactions: HashMap<Uuid, Vec<String>> = HashMap::new();
+        for intent in payment_state.intents.lock().unwrap().iter() {
+            let ids = transactions.entry(intent.sale_id).or_default();
+            ids.push(intent.id.to_string());
+            if let Some(PaymentInstructions::Pix { txid, .. }) = &intent.instructions {
+                ids.push(txid.clone());
+            }
+        }
+        for sale in sales_state.sales.lock().unwrap().iter() {
+            let customer = customer_names.get(&sale.customer_id).cloned().unwrap_or_default();
+            documents.push(document(
+                SearchEntity::Sale,
+                sale.id,
+                format!("{} · {}", customer, sale.amount),
+                Some(format!("{} · {}", sale.status.as_str(), sale.created_at.format("%Y-%m-%d"))),
+                vec![
+                    field("id", 2.0, &sale.id.to_string()),
+                    field("transaction_id", 2.0, &transactions.remove(&sale.id).unwrap_or_default().join(" ")),
+                    field("notes", 1.0, sale.notes.as_deref().unwrap_or_default()),
+                ],
+            ));
+        }
+
+        let index = SearchIndex::build(documents, Utc::now());
+        let size = index.len();
+        *self.index.lock().unwrap() = index;
+        size
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    // Lista separada por vírgulas: customer, product, sale
+    pub types: Option<String>,
+    pub limit: Option<usize>,
+}
+
+pub async fn search(
+    req: HttpRequest,
+    query: web::Query<SearchQuery>,
+    state: web::Data<SearchState>,
+    auth_state: web::Data<AuthState>,
+) -> impl Responder {
+    let Some(claims) = bearer_claims(&req, &auth_state) else {
+        return HttpResponse::Unauthorized().json(json!({ "error": "Authentication required" }));
+    };
+    let allowed = search::allowed_entities(&claims.role);
+    if allowed.is_empty() {
+        return HttpResponse::Forbidden().json(json!({ "error": format!("Role {} cannot use search", claims.role) }));
+    }
+
+    let entities: Vec<SearchEntity> = match &query.types {
+        Some(types) => {
+            let mut entities = Vec::new();
+            for raw in types.split(',').filter(|t| !t.trim().is_empty()) {
+                let Some(entity) = SearchEntity::parse(raw) else {
+                    return HttpResponse::BadRequest().json(json!({ "error": format!("Unknown search type: {}", raw.trim()) }));
+                };
+                if !allowed.contains(&entity) {
+                    return HttpResponse::Forbidden().json(json!({
+                        "error": format!("Role {} cannot search {}", claims.role, entity.as_str())
+                    }));
+                }
+                entities.push(entity);
+            }
+            entities
+        }
+        None => allowed.to_vec(),
+    };
+
+    let text = query.q.trim();
+    if text.chars().count() < MIN_QUERY_LENGTH {
+        return HttpResponse::BadRequest().json(json!({
+            "error": format!("Query must have at least {} characters", MIN_QUERY_LENGTH)
+        }));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let index = state.index.lock().unwrap();
+    let (results, total) = index.search(text, &entities, limit);
+    HttpResponse::Ok().json(SearchResults {
+        query: text.to_string(),
+        total,
+        results,
+        indexed_at: index.built_at,
+    })
+}
+
+// Atualiza o índice na hora, sem esperar o agendador
+pub async fn reindex(
+    req: HttpRequest,
+    state: web::Data<SearchState>,
+    auth_state: web::Data<AuthState>,
+    customer_state: web::Data<CustomerState>,
+    product_state: web::Data<ProductState>,
+    sales_state: web::Data<SalesState>,
+    payment_state: web::Data<PaymentState>,
+) -> impl Responder {
+    match bearer_claims(&req, &auth_state) {
+        None => return HttpResponse::Unauthorized().json(json!({ "error": "Authentication required" })),
+        Some(claims) if claims.role != "admin" => {
+            return HttpResponse::Forbidden().json(json!({ "error": "Only admins can rebuild the search index" }))
+        }
+        Some(_) => {}
+    }
+
+    let documents = state.refresh(&customer_state, &product_state, &sales_state, &payment_state);
+    let indexed_at = state.index.lock().unwrap().built_at;
+    HttpResponse::Ok().json(json!({ "documents": documents, "indexed_at": indexed_at }))
+}
@@ -0,0 +1,154 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::sales::SalesState;
+use crate::models::money::ExchangeRates;
+use crate::models::segment::{SegmentDefinition, SegmentTrendPoint};
+use crate::services::rfm;
+use chrono::{DateTime, Months, Utc};
+use std::sync::Mutex;
+
+pub struct SegmentState {
+    pub definitions: Mutex<Vec<SegmentDefinition>>,
+    pub last_run_at: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl SegmentState {
+    pub fn new() -> Self {
+        SegmentState {
+            definitions: Mutex::new(rfm::default_definitions()),
+            last_run_at: Mutex::new(None),
+        }
+    }
+}
+
+// Segmentação atual calculada das vendas, sem alterar os clientes
+pub async fn get_segments(
+    state: web::Data<SegmentState>,
+    customer_state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let definitions = state.definitions.lock().unwrap().clone();
+    let customers = customer_state.customers.lock().unwrap().clone();
+    let sales = sales_state.sales.lock().unwrap();
+    let now = Utc::now();
+
+    let scores = rfm::score_customers(&customers, &sales, &definitions, now);
+    let segments = rfm::summarize(&definitions, &scores, &sales, None);
+
+    HttpResponse::Ok().json(json!({
+        "as_of": now,
+        "currency": rates.base,
+        "last_run_at": *state.last_run_at.lock().unwrap(),
+        "scored_customers": scores.len(),
+        "unscored_customers": customers.len() - scores.len(),
+        "segments": segments,
+        "customers": scores
+    }))
+}
+
+// Recalcula e grava o segmento em cada cliente com compras; os demais ficam como estão
+pub async fn run_segmentation(
+    state: web::Data<SegmentState>,
+    customer_state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+) -> impl Responder {
+    let definitions = state.definitions.lock().unwrap().clone();
+    let now = Utc::now();
+    let scores = {
+        let customers = customer_state.customers.lock().unwrap().clone();
+        let sales = sales_state.sales.lock().unwrap();
+        rfm::score_customers(&customers, &sales, &definitions, now)
+    };
+
+    let mut updated = 0;
+    let mut customers = customer_state.customers.lock().unwrap();
+    for score in &scores {
+        let Some(customer) = customers.iter_mut().find(|c| c.id == score.customer_id) else {
+            continue;
+        };
+        if customer.segment.as_deref() != Some(score.segment.as_str()) {
+            customer.segment = Some(score.segment.clone());
+            customer.updated_at = now;
+            updated += 1;
+        }
+    }
+    *state.last_run_at.lock().unwrap() = Some(now);
+
+    HttpResponse::Ok().json(json!({
+        "run_at": now,
+        "scored_customers": scores.len(),
+        "updated_customers": updated,
+        "customers": scores
+    }))
+}
+
+pub async fn get_segment_definitions(state: web::Data<SegmentState>) -> impl Responder {
+    let definitions = state.definitions.lock().unwrap();
+    HttpResponse::Ok().json(&*definitions)
+}
+
+// Substitui a lista inteira; a ordem define a prioridade
+pub async fn update_segment_definitions(
+    definitions_req: web::Json<Vec<SegmentDefinition>>,
+    state: web::Data<SegmentState>,
+) -> impl Responder {
+    let mut definitions = definitions_req.into_inner();
+    if let Err(err) = rfm::validate_definitions(&definitions) {
+        return HttpResponse::BadRequest().json(json!({ "error": err }));
+    }
+    for definition in definitions.iter_mut() {
+        definition.name = definition.name.trim().to_string();
+    }
+
+    *state.definitions.lock().unwrap() = definitions.clone();
+    HttpResponse::Ok().json(definitions)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendQuery {
+    // Meses de histórico; padrão 6
+    pub months: Option<u32>,
+}
+
+// Segmentação ao fim de cada mês, com a receita do mês por segmento
+pub async fn get_segment_trends(
+    query: web::Query<TrendQuery>,
+    state: web::Data<SegmentState>,
+    customer_state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let months = query.months.unwrap_or(6);
+    if !(1..=24).contains(&months) {
+        return HttpResponse::BadRequest().json(json!({ "error": "'months' must be between 1 and 24" }));
+    }
+
+    let definitions = state.definitions.lock().unwrap().clone();
+    let customers = customer_state.customers.lock().unwrap().clone();
+    let sales = sales_state.sales.lock().unwrap();
+    let now = Utc::now();
+
+    let trends: Vec<SegmentTrendPoint> = (0..months)
+        .rev()
+        .filter_map(|offset| {
+            let period_end = now.checked_sub_months(Months::new(offset))?;
+            let period_start = period_end.checked_sub_months(Months::new(1))?;
+            let scores = rfm::score_customers(&customers, &sales, &definitions, period_end);
+            Some(SegmentTrendPoint {
+                period_start,
+                period_end,
+                scored_customers: scores.len(),
+                segments: rfm::summarize(&definitions, &scores, &sales, Some((period_start, period_end))),
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(json!({
+        "currency": rates.base,
+        "months": months,
+        "trends": trends
+    }))
+}
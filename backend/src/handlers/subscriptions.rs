@@ -0,0 +1,565 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Deserialize;
+use serde_json::json;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::products::ProductState;
+use crate::handlers::sales::SalesState;
+use crate::models::money::{round_cents, ExchangeRates, Money};
+use crate::models::subscription::{
+    BillingInterval, MrrMovement, MrrMovementKind, Subscription, SubscriptionCancel, SubscriptionChange,
+    SubscriptionChangeRequest, SubscriptionCreate, SubscriptionMetrics, SubscriptionStatus,
+};
+use crate::models::tax::TaxTable;
+use crate::models::{Customer, PaymentMethod, Product, Sale, SaleItem, SaleSnapshot, SaleStatus};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub struct SubscriptionState {
+    pub subscriptions: Mutex<Vec<Subscription>>,
+    pub changes: Mutex<Vec<SubscriptionChange>>,
+    pub movements: Mutex<Vec<MrrMovement>>,
+}
+
+fn movement(subscription: &Subscription, kind: MrrMovementKind, amount: Decimal, at: DateTime<Utc>) -> MrrMovement {
+    MrrMovement {
+        id: Uuid::new_v4(),
+        subscription_id: subscription.id,
+        customer_id: subscription.customer_id,
+        kind,
+        amount,
+        occurred_at: at,
+    }
+}
+
+fn base_mrr(subscription: &Subscription, rates: &ExchangeRates) -> Decimal {
+    rates
+        .convert(&Money::new(subscription.mrr(), subscription.unit_price.currency.clone()), &rates.base)
+        .map(|m| m.amount)
+        .unwrap_or_default()
+}
+
+impl SubscriptionState {
+    pub fn new(product_state: &ProductState, customer_state: &CustomerState) -> Self {
+        let products = product_state.products.lock().unwrap().clone();
+        let customers = customer_state.customers.lock().unwrap().clone();
+        let now = Utc::now();
+
+        // (cliente, produto, quantidade, intervalo, dias desde o início, dias de trial, cancelada há)
+        let seed = [
+            (0, 0, 1, BillingInterval::Monthly, 75, 0, None),
+            (1, 1, 5, BillingInterval::Yearly, 200, 0, None),
+            (1, 2, 3, BillingInterval::Monthly, 120, 0, Some(20)),
+            (2, 2, 10, BillingInterval::Monthly, 5, 14, None),
+        ];
+
+        let mut subscriptions = Vec::new();
+        let mut movements = Vec::new();
+        for (customer, product, quantity, interval, days_ago, trial_days, canceled_days_ago) in seed {
+            let (Some(customer), Some(product)) = (customers.get(customer), products.get(product)) else {
+                continue;
+            };
+            let started_at = now - Duration::days(days_ago);
+            let trial_ends_at = (trial_days > 0).then(|| started_at + Duration::days(trial_days));
+            let ended_at = canceled_days_ago.map(|days| now - Duration::days(days));
+
+            let (mut period_start, mut period_end) = match trial_ends_at {
+                Some(trial_end) => (started_at, trial_end),
+                None => (started_at, interval.next(started_at)),
+            };
+            let until = ended_at.unwrap_or(now);
+            while trial_ends_at.is_none() && period_end <= until {
+                period_start = period_end;
+                period_end = interval.next(period_end);
+            }
+
+            let subscription = Subscription {
+                id: Uuid::new_v4(),
+                customer_id: customer.id,
+                product_id: product.id,
+                quantity,
+                unit_price: product.price.clone(),
+                interval,
+                payment_method: PaymentMethod::CreditCard,
+                status: match (trial_ends_at, ended_at) {
+                    (_, Some(_)) => SubscriptionStatus::Canceled,
+                    (Some(_), None) => SubscriptionStatus::Trialing,
+                    (None, None) => SubscriptionStatus::Active,
+                },
+                trial_ends_at,
+                started_at,
+                current_period_start: period_start,
+                current_period_end: period_end,
+                cancel_at_period_end: false,
+                canceled_at: ended_at,
+                ended_at,
+                credit: Decimal::ZERO,
+                created_at: started_at,
+            };
+
+            let mrr = subscription.unit_price.amount * Decimal::from(quantity);
+            if trial_ends_at.is_none() {
+                movements.push(movement(&subscription, MrrMovementKind::New, mrr, started_at));
+            }
+            if let Some(ended_at) = ended_at {
+                movements.push(movement(&subscription, MrrMovementKind::Churn, -mrr, ended_at));
+            }
+            subscriptions.push(subscription);
+        }
+
+        SubscriptionState {
+            subscriptions: Mutex::new(subscriptions),
+            changes: Mutex::new(Vec::new()),
+            movements: Mutex::new(movements),
+        }
+    }
+
+    // Gera as cobranças vencidas, converte trials e encerra cancelamentos agendados
+    pub fn run_renewals(
+        &self,
+        sales_state: &SalesState,
+        customer_state: &CustomerState,
+        product_state: &ProductState,
+        taxes: &TaxTable,
+        rates: &ExchangeRates,
+        now: DateTime<Utc>,
+    ) -> Vec<Sale> {
+        // Cadastro antes das assinaturas, na mesma ordem da exclusão de clientes
+        let customers = customer_state.customers.lock().unwrap();
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let mut movements = self.movements.lock().unwrap();
+        let mut renewals = Vec::new();
+
+        for subscription in subscriptions.iter_mut() {
+            while subscription.status != SubscriptionStatus::Canceled && subscription.current_period_end <= now {
+                let period_end = subscription.current_period_end;
+
+                if subscription.cancel_at_period_end {
+                    if subscription.status == SubscriptionStatus::Active {
+                        let mrr = base_mrr(subscription, rates);
+                        movements.push(movement(subscription, MrrMovementKind::Churn, -mrr, period_end));
+                    }
+                    subscription.status = SubscriptionStatus::Canceled;
+                    subscription.ended_at = Some(period_end);
+                    break;
+                }
+
+                let Some(customer) = customers.iter().find(|c| c.id == subscription.customer_id) else {
+                    println!("⚠️  Renewal of subscription {} failed: customer not found", subscription.id);
+                    break;
+                };
+                let discount = subscription.credit.min(subscription.period_amount());
+                let sale = match billing_sale(
+                    subscription,
+                    customer,
+                    subscription.product_id,
+                    subscription.quantity,
+                    subscription.unit_price.amount * Decimal::from(subscription.interval.months()),
+                    discount,
+                    product_state,
+                    taxes,
+                    rates,
+                    period_end,
+                ) {
+                    Ok(sale) => sale,
+                    Err(err) => {
+                        // Fica vencida e é tentada de novo na próxima execução
+                        println!("⚠️  Renewal of subscription {} failed: {}", subscription.id, err);
+                        break;
+                    }
+                };
+
+                if subscription.status == SubscriptionStatus::Trialing {
+                    subscription.status = SubscriptionStatus::Active;
+                    let mrr = base_mrr(subscription, rates);
+                    movements.push(movement(subscription, MrrMovementKind::New, mrr, period_end));
+                }
+                subscription.credit -= discount;
+                subscription.current_period_start = period_end;
+                subscription.current_period_end = subscription.interval.next(period_end);
+                renewals.push(sale);
+            }
+        }
+
+        sales_state.sales.lock().unwrap().extend(renewals.iter().cloned());
+        renewals
+    }
+
+    pub fn metrics(&self, rates: &ExchangeRates, from: DateTime<Utc>, to: DateTime<Utc>) -> SubscriptionMetrics {
+        let subscriptions = self.subscriptions.lock().unwrap();
+        let movements = self.movements.lock().unwrap();
+
+        let mrr: Decimal = subscriptions.iter().map(|s| base_mrr(s, rates)).sum();
+        let paying: HashSet<Uuid> = subscriptions
+            .iter()
+            .filter(|s| s.status == SubscriptionStatus::Active)
+            .map(|s| s.customer_id)
+            .collect();
+
+        // MRR por cliente em uma data, a partir das movimentações
+        let mrr_by_customer = |at: DateTime<Utc>| {
+            let mut by_customer: HashMap<Uuid, Decimal> = HashMap::new();
+            for m in movements.iter().filter(|m| m.occurred_at < at) {
+                *by_customer.entry(m.customer_id).or_default() += m.amount;
+            }
+            by_customer
+        };
+        let at_start = mrr_by_customer(from);
+        let at_end = mrr_by_customer(to);
+        let starting_mrr: Decimal = at_start.values().sum();
+        let customers_at_start: HashSet<Uuid> =
+            at_start.iter().filter(|(_, mrr)| **mrr > Decimal::ZERO).map(|(id, _)| *id).collect();
+        let churned_customers = customers_at_start
+            .iter()
+            .filter(|id| at_end.get(id).copied().unwrap_or_default() <= Decimal::ZERO)
+            .count();
+
+        let in_period = |kind: MrrMovementKind| -> Decimal {
+            movements
+                .iter()
+                .filter(|m| m.kind == kind && m.occurred_at >= from && m.occurred_at < to)
+                .map(|m| m.amount.abs())
+                .sum()
+        };
+        let (new, expansion, contraction, churned) = (
+            in_period(MrrMovementKind::New),
+            in_period(MrrMovementKind::Expansion),
+            in_period(MrrMovementKind::Contraction),
+            in_period(MrrMovementKind::Churn),
+        );
+        let percent = |value: Decimal, total: Decimal| {
+            if total.is_zero() { Decimal::ZERO } else { round_cents(value * Decimal::ONE_HUNDRED / total) }
+        };
+
+        SubscriptionMetrics {
+            currency: rates.base.clone(),
+            from,
+            to,
+            mrr: round_cents(mrr),
+            arr: round_cents(mrr * Decimal::from(12)),
+            active_subscriptions: subscriptions.iter().filter(|s| s.status == SubscriptionStatus::Active).count(),
+            trialing_subscriptions: subscriptions.iter().filter(|s| s.status == SubscriptionStatus::Trialing).count(),
+            paying_customers: paying.len(),
+            arpa: if paying.is_empty() { Decimal::ZERO } else { round_cents(mrr / Decimal::from(paying.len())) },
+            starting_mrr: round_cents(starting_mrr),
+            new_mrr: round_cents(new),
+            expansion_mrr: round_cents(expansion),
+            contraction_mrr: round_cents(contraction),
+            churned_mrr: round_cents(churned),
+            net_new_mrr: round_cents(new + expansion - contraction - churned),
+            mrr_churn_rate: percent(churned, starting_mrr),
+            customer_churn_rate: percent(Decimal::from(churned_customers), Decimal::from(customers_at_start.len())),
+            net_revenue_retention: percent(starting_mrr + expansion - contraction - churned, starting_mrr),
+        }
+    }
+}
+
+// Venda pendente de uma cobrança de assinatura, com impostos e reserva de estoque
+#[allow(clippy::too_many_arguments)]
+fn billing_sale(
+    subscription: &Subscription,
+    customer: &Customer,
+    product_id: Uuid,
+    quantity: i32,
+    unit_price: Decimal,
+    discount: Decimal,
+    product_state: &ProductState,
+    taxes: &TaxTable,
+    rates: &ExchangeRates,
+    created_at: DateTime<Utc>,
+) -> Result<Sale, String> {
+    let currency = subscription.unit_price.currency.clone();
+    let exchange_rate = rates.rate(&currency).ok_or_else(|| format!("No exchange rate available for {}", currency))?;
+
+    let mut item = SaleItem::new(product_id, quantity, unit_price, discount)?;
+    let product = find_product(product_state, product_id).ok_or_else(|| format!("Product {} not found", product_id))?;
+    let line_taxes = taxes.calculate(item.line_total, &product.category, &taxes.default_region, taxes.prices_include_tax);
+    item.apply_taxes(line_taxes, taxes.prices_include_tax);
+    let items = vec![item];
+    let snapshot = SaleSnapshot::capture(customer, std::slice::from_ref(&product), &items);
+
+    let sale_id = Uuid::new_v4();
+    product_state.reserve_stock(sale_id, &items, None)?;
+
+    Ok(Sale {
+        id: sale_id,
+        customer_id: subscription.customer_id,
+        amount: Sale::total_from(&items, currency.clone()),
+        refunded_amount: Money::zero(currency),
+        exchange_rate,
+        tax_region: taxes.default_region.clone(),
+        prices_include_tax: taxes.prices_include_tax,
+        promotions: Vec::new(),
+        subscription_id: Some(subscription.id),
+        notes: None,
+        snapshot,
+        items,
+        status: SaleStatus::Pending,
+        payment_method: subscription.payment_method,
+        created_at,
+    })
+}
+
+fn find_product(product_state: &ProductState, product_id: Uuid) -> Option<Product> {
+    product_state.products.lock().unwrap().iter().find(|p| p.id == product_id).cloned()
+}
+
+pub async fn get_subscriptions(state: web::Data<SubscriptionState>) -> impl Responder {
+    let subscriptions = state.subscriptions.lock().unwrap();
+    HttpResponse::Ok().json(&*subscriptions)
+}
+
+pub async fn get_subscription(path: web::Path<Uuid>, state: web::Data<SubscriptionState>) -> impl Responder {
+    let subscription_id = path.into_inner();
+    let Some(subscription) = state.subscriptions.lock().unwrap().iter().find(|s| s.id == subscription_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Subscription not found" }));
+    };
+    let changes: Vec<SubscriptionChange> = state.changes.lock().unwrap()
+        .iter()
+        .filter(|c| c.subscription_id == subscription_id)
+        .cloned()
+        .collect();
+
+    HttpResponse::Ok().json(json!({ "subscription": subscription, "changes": changes }))
+}
+
+pub async fn create_subscription(
+    subscription_req: web::Json<SubscriptionCreate>,
+    state: web::Data<SubscriptionState>,
+    sales_state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+    customer_state: web::Data<CustomerState>,
+    taxes: web::Data<TaxTable>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let req = subscription_req.into_inner();
+    let quantity = req.quantity.unwrap_or(1);
+    if quantity <= 0 {
+        return HttpResponse::BadRequest().json(json!({ "error": "Quantity must be greater than zero" }));
+    }
+    if !(0..=90).contains(&req.trial_days) {
+        return HttpResponse::BadRequest().json(json!({ "error": "Trial must be between 0 and 90 days" }));
+    }
+    let Some(customer) = customer_state.customers.lock().unwrap().iter().find(|c| c.id == req.customer_id).cloned() else {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Customer {} not found", req.customer_id) }));
+    };
+    let Some(product) = find_product(&product_state, req.product_id) else {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Product {} not found", req.product_id) }));
+    };
+    if !product.is_active {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Product {} is inactive", product.sku) }));
+    }
+
+    let now = Utc::now();
+    let trial_ends_at = (req.trial_days > 0).then(|| now + Duration::days(req.trial_days));
+    let mut subscription = Subscription {
+        id: Uuid::new_v4(),
+        customer_id: req.customer_id,
+        product_id: product.id,
+        quantity,
+        unit_price: product.price.clone(),
+        interval: req.interval,
+        payment_method: req.payment_method,
+        status: SubscriptionStatus::Trialing,
+        trial_ends_at,
+        started_at: now,
+        current_period_start: now,
+        current_period_end: trial_ends_at.unwrap_or_else(|| req.interval.next(now)),
+        cancel_at_period_end: false,
+        canceled_at: None,
+        ended_at: None,
+        credit: Decimal::ZERO,
+        created_at: now,
+    };
+
+    // Sem trial, o primeiro período é cobrado na contratação
+    let sale = if trial_ends_at.is_none() {
+        let unit_price = product.price.amount * Decimal::from(req.interval.months());
+        match billing_sale(&subscription, &customer, product.id, quantity, unit_price, Decimal::ZERO, &product_state, &taxes, &rates, now) {
+            Ok(sale) => {
+                subscription.status = SubscriptionStatus::Active;
+                let mrr = base_mrr(&subscription, &rates);
+                state.movements.lock().unwrap().push(movement(&subscription, MrrMovementKind::New, mrr, now));
+                sales_state.sales.lock().unwrap().push(sale.clone());
+                Some(sale)
+            }
+            Err(err) => return HttpResponse::Conflict().json(json!({ "error": err })),
+        }
+    } else {
+        None
+    };
+
+    state.subscriptions.lock().unwrap().push(subscription.clone());
+
+    HttpResponse::Created().json(json!({ "subscription": subscription, "sale": sale }))
+}
+
+// Upgrade/downgrade no meio do período: a diferença é proporcional ao tempo restante
+#[allow(clippy::too_many_arguments)]
+pub async fn change_subscription(
+    path: web::Path<Uuid>,
+    change_req: web::Json<SubscriptionChangeRequest>,
+    state: web::Data<SubscriptionState>,
+    sales_state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+    taxes: web::Data<TaxTable>,
+    rates: web::Data<ExchangeRates>,
+    customer_state: web::Data<CustomerState>,
+) -> impl Responder {
+    let subscription_id = path.into_inner();
+    // Cadastro antes das assinaturas, na mesma ordem da exclusão de clientes
+    let customers = customer_state.customers.lock().unwrap();
+    let mut subscriptions = state.subscriptions.lock().unwrap();
+    let Some(subscription) = subscriptions.iter_mut().find(|s| s.id == subscription_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Subscription not found" }));
+    };
+    if subscription.status == SubscriptionStatus::Canceled {
+        return HttpResponse::Conflict().json(json!({ "error": "Subscription is canceled" }));
+    }
+
+    let product_id = change_req.product_id.unwrap_or(subscription.product_id);
+    let quantity = change_req.quantity.unwrap_or(subscription.quantity);
+    if quantity <= 0 {
+        return HttpResponse::BadRequest().json(json!({ "error": "Quantity must be greater than zero" }));
+    }
+    if product_id == subscription.product_id && quantity == subscription.quantity {
+        return HttpResponse::BadRequest().json(json!({ "error": "Nothing to change" }));
+    }
+    let Some(product) = find_product(&product_state, product_id) else {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Product {} not found", product_id) }));
+    };
+    // Trocar para um plano fora do catálogo não é permitido; manter o plano atual, sim
+    if !product.is_active && product_id != subscription.product_id {
+        return HttpResponse::BadRequest().json(json!({ "error": format!("Product {} is inactive", product.sku) }));
+    }
+    // Trocar de plano aplica o preço de catálogo atual; mudar só a quantidade mantém o preço travado
+    let unit_price = if product_id == subscription.product_id {
+        subscription.unit_price.clone()
+    } else {
+        match rates.convert(&product.price, &subscription.unit_price.currency) {
+            Some(price) => price,
+            None => return HttpResponse::BadRequest().json(json!({
+                "error": format!("Cannot convert {} to {}", product.price.currency, subscription.unit_price.currency)
+            })),
+        }
+    };
+
+    let now = Utc::now();
+    let mut updated = subscription.clone();
+    updated.product_id = product_id;
+    updated.quantity = quantity;
+    updated.unit_price = unit_price;
+
+    let mut proration = Decimal::ZERO;
+    let mut sale = None;
+    if subscription.status == SubscriptionStatus::Active {
+        let period = (subscription.current_period_end - subscription.current_period_start).num_seconds();
+        let remaining = (subscription.current_period_end - now).num_seconds().clamp(0, period.max(0));
+        let fraction = if period > 0 { Decimal::from(remaining) / Decimal::from(period) } else { Decimal::ZERO };
+        proration = round_cents((updated.period_amount() - subscription.period_amount()) * fraction);
+
+        if proration > Decimal::ZERO {
+            let Some(customer) = customers.iter().find(|c| c.id == updated.customer_id) else {
+                return HttpResponse::Conflict().json(json!({ "error": format!("Customer {} not found", updated.customer_id) }));
+            };
+            match billing_sale(&updated, customer, product_id, 1, proration, Decimal::ZERO, &product_state, &taxes, &rates, now) {
+                Ok(created) => sale = Some(created),
+                Err(err) => return HttpResponse::Conflict().json(json!({ "error": err })),
+            }
+        } else {
+            updated.credit -= proration;
+        }
+
+        let delta = base_mrr(&updated, &rates) - base_mrr(subscription, &rates);
+        if !delta.is_zero() {
+            let kind = if delta > Decimal::ZERO { MrrMovementKind::Expansion } else { MrrMovementKind::Contraction };
+            state.movements.lock().unwrap().push(movement(&updated, kind, delta, now));
+        }
+    }
+
+    let change = SubscriptionChange {
+        id: Uuid::new_v4(),
+        subscription_id,
+        from_product_id: subscription.product_id,
+        to_product_id: product_id,
+        from_quantity: subscription.quantity,
+        to_quantity: quantity,
+        proration,
+        sale_id: sale.as_ref().map(|s: &Sale| s.id),
+        changed_at: now,
+    };
+    *subscription = updated.clone();
+    drop(subscriptions);
+
+    if let Some(sale) = &sale {
+        sales_state.sales.lock().unwrap().push(sale.clone());
+    }
+    state.changes.lock().unwrap().push(change.clone());
+
+    HttpResponse::Ok().json(json!({ "subscription": updated, "change": change, "sale": sale }))
+}
+
+pub async fn cancel_subscription(
+    path: web::Path<Uuid>,
+    cancel_req: web::Json<SubscriptionCancel>,
+    state: web::Data<SubscriptionState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let subscription_id = path.into_inner();
+    let mut subscriptions = state.subscriptions.lock().unwrap();
+    let Some(subscription) = subscriptions.iter_mut().find(|s| s.id == subscription_id) else {
+        return HttpResponse::NotFound().json(json!({ "error": "Subscription not found" }));
+    };
+    if subscription.status == SubscriptionStatus::Canceled {
+        return HttpResponse::Conflict().json(json!({ "error": "Subscription is already canceled" }));
+    }
+
+    let now = Utc::now();
+    subscription.canceled_at = Some(now);
+    if cancel_req.at_period_end.unwrap_or(true) {
+        subscription.cancel_at_period_end = true;
+    } else {
+        if subscription.status == SubscriptionStatus::Active {
+            let mrr = base_mrr(subscription, &rates);
+            state.movements.lock().unwrap().push(movement(subscription, MrrMovementKind::Churn, -mrr, now));
+        }
+        subscription.status = SubscriptionStatus::Canceled;
+        subscription.ended_at = Some(now);
+    }
+
+    HttpResponse::Ok().json(&*subscription)
+}
+
+// Executa o agendador de renovações imediatamente
+pub async fn run_renewals(
+    state: web::Data<SubscriptionState>,
+    sales_state: web::Data<SalesState>,
+    product_state: web::Data<ProductState>,
+    taxes: web::Data<TaxTable>,
+    rates: web::Data<ExchangeRates>,
+    customer_state: web::Data<CustomerState>,
+) -> impl Responder {
+    let sales = state.run_renewals(&sales_state, &customer_state, &product_state, &taxes, &rates, Utc::now());
+    HttpResponse::Ok().json(json!({ "renewals": sales.len(), "sales": sales }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetricsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+pub async fn get_subscription_metrics(
+    query: web::Query<MetricsQuery>,
+    state: web::Data<SubscriptionState>,
+    rates: web::Data<ExchangeRates>,
+) -> impl Responder {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::days(30));
+    if from >= to {
+        return HttpResponse::BadRequest().json(json!({ "error": "'from' must be before 'to'" }));
+    }
+    HttpResponse::Ok().json(state.metrics(&rates, from, to))
+}
@@ -0,0 +1,266 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde_json::json;
+use crate::handlers::customers::CustomerState;
+use crate::handlers::payments::PaymentState;
+use crate::handlers::quotes::QuoteState;
+use crate::handlers::sales::SalesState;
+use crate::handlers::subscriptions::SubscriptionState;
+use crate::models::money::Money;
+use crate::models::query::ListQuery;
+use crate::models::subscription::SubscriptionStatus;
+use crate::models::timeline::{TimelineEvent, TimelineEventType};
+use crate::models::PaymentIntentStatus;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+fn event(
+    kind: TimelineEventType,
+    reference_id: Uuid,
+    suffix: &str,
+    occurred_at: DateTime<Utc>,
+    title: String,
+    amount: Option<Money>,
+    details: serde_json::Value,
+) -> TimelineEvent {
+    TimelineEvent {
+        id: format!("{}:{}{}", kind.as_str(), reference_id, suffix),
+        kind,
+        occurred_at,
+        title,
+        amount,
+        reference_id,
+        details,
+    }
+}
+
+// Tudo o que aconteceu com o cliente em um feed só, do mais recente para o mais antigo
+// Chamados de suporte e notificações ficam de fora até existirem na API
+#[allow(clippy::too_many_arguments)]
+pub async fn get_customer_timeline(
+    path: web::Path<Uuid>,
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<CustomerState>,
+    sales_state: web::Data<SalesState>,
+    payment_state: web::Data<PaymentState>,
+    subscription_state: web::Data<SubscriptionState>,
+    quote_state: web::Data<QuoteState>,
+) -> impl Responder {
+    let customer_id = path.into_inner();
+    let query = match ListQuery::parse::<TimelineEvent>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+    let Some(customer) = state.customers.lock().unwrap().iter().find(|c| c.id == customer_id).cloned() else {
+        return HttpResponse::NotFound().json(json!({ "error": "Customer not found" }));
+    };
+    let mut events = Vec::new();
+
+    // Os ids das vendas ligam reembolsos e pagamentos ao cliente
+    let sales: HashSet<Uuid> = {
+        let sales = sales_state.sales.lock().unwrap();
+        sales
+            .iter()
+            .filter(|s| s.customer_id == customer_id)
+            .map(|sale| {
+                events.push(event(
+                    TimelineEventType::Sale,
+                    sale.id,
+                    "",
+                    sale.created_at,
+                    format!("Sale {}", sale.status.as_str()),
+                    Some(sale.amount.clone()),
+                    json!({
+                        "status": sale.status,
+                        "payment_method": sale.payment_method,
+                        "items": sale.items.len(),
+                        "promotions": sale.promotions.iter().map(|p| &p.name).collect::<Vec<_>>(),
+                        "subscription_id": sale.subscription_id
+                    }),
+                ));
+                sale.id
+            })
+            .collect()
+    };
+
+    for refund in sales_state.refunds.lock().unwrap().iter().filter(|r| sales.contains(&r.sale_id)) {
+        events.push(event(
+            TimelineEventType::Refund,
+            refund.id,
+            "",
+            refund.created_at,
+            "Refund issued".to_string(),
+            Some(refund.amount.clone()),
+            json!({ "sale_id": refund.sale_id, "reason": refund.reason, "note": refund.note, "restocked": refund.restocked }),
+        ));
+    }
+
+    for intent in payment_state.intents.lock().unwrap().iter().filter(|i| sales.contains(&i.sale_id)) {
+        let title = match intent.status {
+            PaymentIntentStatus::Pending => "Payment requested",
+            PaymentIntentStatus::Succeeded => "Payment received",
+            PaymentIntentStatus::Failed => "Payment failed",
+        };
+        let occurred_at = match intent.status {
+            PaymentIntentStatus::Pending => intent.created_at,
+            _ => intent.updated_at,
+        };
+        events.push(event(
+            TimelineEventType::Payment,
+            intent.id,
+            "",
+            occurred_at,
+            title.to_string(),
+            Some(intent.amount.clone()),
+            json!({ "sale_id": intent.sale_id, "method": intent.method, "status": intent.status, "failure_reason": intent.failure_reason }),
+        ));
+    }
+
+    for change in state.status_changes.lock().unwrap().iter().filter(|c| c.customer_id == customer_id) {
+        events.push(event(
+            TimelineEventType::StatusChange,
+            change.id,
+            "",
+            change.changed_at,
+            format!("Status changed from {} to {}", change.from, change.to),
+            None,
+            json!({ "from": change.from, "to": change.to }),
+        ));
+    }
+
+    for note in state.notes.lock().unwrap().iter().filter(|n| n.customer_id == customer_id) {
+        events.push(event(
+            TimelineEventType::Note,
+            note.id,
+            "",
+            note.created_at,
+            "Note added".to_string(),
+            None,
+            json!({ "author": note.author, "body": note.body }),
+        ));
+    }
+
+    {
+        let subscriptions = subscription_state.subscriptions.lock().unwrap();
+        let owned: Vec<_> = subscriptions.iter().filter(|s| s.customer_id == customer_id).collect();
+        for subscription in &owned {
+            let details = json!({ "product_id": subscription.product_id, "quantity": subscription.quantity, "interval": subscription.interval });
+            events.push(event(
+                TimelineEventType::Subscription,
+                subscription.id,
+                ":started",
+                subscription.started_at,
+                if subscription.trial_ends_at.is_some() { "Trial started" } else { "Subscription started" }.to_string(),
+                Some(Money::new(subscription.period_amount(), subscription.unit_price.currency.clone())),
+                details.clone(),
+            ));
+            if let (SubscriptionStatus::Canceled, Some(ended_at)) = (subscription.status, subscription.ended_at) {
+                events.push(event(
+                    TimelineEventType::Subscription,
+                    subscription.id,
+                    ":ended",
+                    ended_at,
+                    "Subscription ended".to_string(),
+                    None,
+                    details,
+                ));
+            }
+        }
+        for change in subscription_state.changes.lock().unwrap().iter() {
+            if let Some(subscription) = owned.iter().find(|s| s.id == change.subscription_id) {
+                events.push(event(
+                    TimelineEventType::Subscription,
+                    change.id,
+                    ":changed",
+                    change.changed_at,
+                    "Subscription plan changed".to_string(),
+                    Some(Money::new(change.proration, subscription.unit_price.currency.clone())),
+                    json!({
+                        "subscription_id": change.subscription_id,
+                        "from_product_id": change.from_product_id,
+                        "to_product_id": change.to_product_id,
+                        "from_quantity": change.from_quantity,
+                        "to_quantity": change.to_quantity,
+                        "sale_id": change.sale_id
+                    }),
+                ));
+            }
+        }
+    }
+
+    for quote in quote_state.quotes.lock().unwrap().iter().filter(|q| q.customer_id == customer_id) {
+        for (i, change) in quote.stage_history.iter().enumerate() {
+            let title = if i == 0 {
+                format!("Quote {} created", quote.number)
+            } else {
+                format!("Quote {} moved to {}", quote.number, change.stage.as_str())
+            };
+            events.push(event(
+                TimelineEventType::Quote,
+                quote.id,
+                &format!(":{}", i),
+                change.changed_at,
+                title,
+                Some(quote.amount.clone()),
+                json!({ "number": quote.number, "stage": change.stage, "probability": change.probability, "sale_id": quote.sale_id }),
+            ));
+        }
+    }
+
+    for merge in state.merges.lock().unwrap().iter().filter(|m| m.survivor_id == customer_id || m.merged_id == customer_id) {
+        let details = json!({
+            "survivor_id": merge.survivor_id,
+            "merged_id": merge.merged_id,
+            "merged_customer": merge.merged_customer.name,
+            "merged_by": merge.merged_by
+        });
+        events.push(event(
+            TimelineEventType::Merge,
+            merge.id,
+            ":merged",
+            merge.merged_at,
+            format!("Merged with {}", merge.merged_customer.name),
+            None,
+            details.clone(),
+        ));
+        if let Some(undone_at) = merge.undone_at {
+            events.push(event(
+                TimelineEventType::Merge,
+                merge.id,
+                ":undone",
+                undone_at,
+                format!("Merge with {} undone", merge.merged_customer.name),
+                None,
+                details,
+            ));
+        }
+    }
+
+    // Previsão de CLV mais recente como insight
+    if let Some(clv) = &customer.predicted_clv {
+        let title = if clv.probability_alive < Decimal::from(50) {
+            "Churn risk: customer likely inactive"
+        } else {
+            "Customer value forecast updated"
+        };
+        events.push(event(
+            TimelineEventType::Insight,
+            customer.id,
+            ":clv",
+            clv.computed_at,
+            title.to_string(),
+            Some(clv.value.clone()),
+            json!({
+                "expected_purchases": clv.expected_purchases,
+                "probability_alive": clv.probability_alive,
+                "lower": clv.lower,
+                "upper": clv.upper,
+                "horizon_months": clv.horizon_months,
+                "segment": customer.segment
+            }),
+        ));
+    }
+
+    HttpResponse::Ok().json(query.apply(&events))
+}
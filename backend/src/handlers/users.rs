@@ -1,6 +1,8 @@
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use std::sync::Mutex;
+use crate::models::query::{FieldValue, ListQuery, Listable};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
@@ -11,6 +13,27 @@ pub struct User {
     pub created_at: String,
 }
 
+impl Listable for User {
+    const FILTERABLE: &'static [&'static str] = &["name", "email", "role", "created_at"];
+    const SORTABLE: &'static [&'static str] = &["id", "name", "email", "role", "created_at"];
+    const DEFAULT_SORT: &'static str = "id";
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        match field {
+            "id" => FieldValue::Number(self.id as f64),
+            "name" => FieldValue::Text(self.name.clone()),
+            "email" => FieldValue::Text(self.email.clone()),
+            "role" => FieldValue::Text(self.role.clone()),
+            "created_at" => FieldValue::Text(self.created_at.clone()),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        format!("{:020}", self.id)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateUserRequest {
     pub name: String,
@@ -71,9 +94,17 @@ impl UserState {
     }
 }
 
-pub async fn get_users(state: web::Data<UserState>) -> impl Responder {
+pub async fn get_users(
+    query: web::Query<Vec<(String, String)>>,
+    state: web::Data<UserState>,
+) -> impl Responder {
+    let query = match ListQuery::parse::<User>(&query) {
+        Ok(query) => query,
+        Err(err) => return HttpResponse::BadRequest().json(json!({ "error": err })),
+    };
+
     let users = state.users.lock().unwrap();
-    HttpResponse::Ok().json(users.clone())
+    HttpResponse::Ok().json(query.apply(&users))
 }
 
 pub async fn get_user(
@@ -1,23 +1,201 @@
 mod handlers;
+mod middleware;
 mod models;
 mod database;
 mod config;
+mod services;
 
 use actix_web::{web, App, HttpServer};
+use actix_web::middleware::from_fn;
 use handlers::auth::AppState as AuthState;
 use handlers::users::UserState;
+use handlers::sales::SalesState;
+use handlers::products::ProductState;
+use handlers::clv::ClvState;
+use handlers::customers::CustomerState;
+use handlers::custom_fields::CustomFieldState;
+use handlers::privacy::PrivacyState;
+use handlers::search::SearchState;
+use handlers::imports::ImportState;
+use handlers::invoices::InvoiceState;
+use handlers::fiscal::FiscalState;
+use handlers::payments::PaymentState;
+use handlers::promotions::PromotionState;
+use handlers::quotes::QuoteState;
+use handlers::segments::SegmentState;
+use handlers::subscriptions::SubscriptionState;
+use middleware::idempotency::{idempotency, IdempotencyStore};
+use models::fiscal::FiscalIssuer;
+use models::money::{Currency, ExchangeRates};
+use models::tax::TaxTable;
+use services::payments::mock::MockGateway;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    println!("🚀 Starting AI Business Platform API on http://0.0.0.0:8080");
+    let config = config::Config::from_env().expect("Invalid configuration");
+    println!("🚀 Starting AI Business Platform API on http://{}:{}", config.host, config.port);
+
+    let base_currency = Currency::new(&config.base_currency).expect("Invalid BASE_CURRENCY");
+    let exchange_rates = ExchangeRates::load(&config.exchange_rates_path, base_currency.clone())
+        .unwrap_or_else(|err| {
+            println!("⚠️  {} - only {} will be accepted", err, base_currency);
+            ExchangeRates::new(base_currency.clone())
+        });
+    let exchange_rates = web::Data::new(exchange_rates);
+    let tax_table = TaxTable::load(&config.tax_rules_path).unwrap_or_else(|err| {
+        println!("⚠️  {} - sales will be created without taxes", err);
+        TaxTable::empty(&config.default_tax_region)
+    });
+    let fiscal_issuer = FiscalIssuer::load(&config.fiscal_issuer_path)
+        .map_err(|err| println!("⚠️  {} - NF-e generation disabled", err))
+        .ok();
     
     let auth_state = web::Data::new(AuthState::new());
     let user_state = web::Data::new(UserState::new());
+    let product_state = web::Data::new(ProductState::new(&base_currency));
+    let customer_state = web::Data::new(CustomerState::new(&base_currency));
+    let sales_state = web::Data::new(SalesState::new(&product_state, &customer_state, &tax_table));
+    let tax_table = web::Data::new(tax_table);
+    let import_state = web::Data::new(ImportState::new(config.import_max_bytes));
+    let invoice_state = web::Data::new(InvoiceState::new(&config.tenant_id, &config.invoice_prefix));
+    let fiscal_state = web::Data::new(FiscalState::new(fiscal_issuer));
+    let payment_state = web::Data::new(PaymentState::new(Box::new(MockGateway::new(
+        &config.payment_webhook_secret,
+        &config.payment_webhook_url,
+        &config.pix_key,
+        &config.merchant_name,
+        &config.merchant_city,
+        config.boleto_days,
+    ))));
+    let promotion_state = web::Data::new(PromotionState::new(&product_state));
+    let subscription_state = web::Data::new(SubscriptionState::new(&product_state, &customer_state));
+    let quote_state = web::Data::new(QuoteState::new(&product_state, &customer_state));
+    let segment_state = web::Data::new(SegmentState::new());
+    let custom_field_state = web::Data::new(CustomFieldState::new());
+    let privacy_state = web::Data::new(PrivacyState::new(config.privacy_request_deadline_days));
+    let search_state = web::Data::new(SearchState::new());
+    let clv_state = web::Data::new(ClvState::new(config.clv_horizon_months));
+    let idempotency_store = web::Data::new(IdempotencyStore::new(config.idempotency_ttl, config.idempotency_max_body));
+
+    // Agendador de renovações de assinaturas
+    {
+        let subscription_state = subscription_state.clone();
+        let sales_state = sales_state.clone();
+        let customer_state = customer_state.clone();
+        let product_state = product_state.clone();
+        let tax_table = tax_table.clone();
+        let exchange_rates = exchange_rates.clone();
+        let every = std::time::Duration::from_secs(config.subscription_renewal_interval.max(1));
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(every);
+            loop {
+                ticker.tick().await;
+                let renewals = subscription_state.run_renewals(
+                    &sales_state,
+                    &customer_state,
+                    &product_state,
+                    &tax_table,
+                    &exchange_rates,
+                    chrono::Utc::now(),
+                );
+                if !renewals.is_empty() {
+                    println!("🔁 {} subscription renewal(s) billed", renewals.len());
+                }
+            }
+        });
+    }
+
+    // Recálculo periódico de LTV e CLV; o primeiro roda na subida. Pedidos de recálculo (mesclagens)
+    // são atendidos em poucos segundos, e o ajuste roda no pool bloqueante
+    {
+        let clv_state = clv_state.clone();
+        let customer_state = customer_state.clone();
+        let sales_state = sales_state.clone();
+        let exchange_rates = exchange_rates.clone();
+        let every = std::time::Duration::from_secs(config.clv_refresh_interval.max(1));
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(every.min(std::time::Duration::from_secs(5)));
+            let mut last_run: Option<std::time::Instant> = None;
+            loop {
+                ticker.tick().await;
+                let due = last_run.is_none_or(|at| at.elapsed() >= every);
+                if !clv_state.take_refresh_request() && !due {
+                    continue;
+                }
+                last_run = Some(std::time::Instant::now());
+                let refreshed = handlers::clv::refresh_blocking(
+                    clv_state.clone(),
+                    customer_state.clone(),
+                    sales_state.clone(),
+                    exchange_rates.clone(),
+                )
+                .await;
+                if let Err(err) = refreshed {
+                    println!("⚠️  CLV refresh failed: {}", err);
+                }
+            }
+        });
+    }
+
+    // Vendas pendentes abandonadas: reservas vencidas cancelam a venda e devolvem o estoque
+    {
+        let sales_state = sales_state.clone();
+        let product_state = product_state.clone();
+        let payment_state = payment_state.clone();
+        let ttl = chrono::Duration::seconds(config.reservation_ttl.max(1));
+        let every = std::time::Duration::from_secs(config.reservation_sweep_interval.max(1));
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(every);
+            loop {
+                ticker.tick().await;
+                let expired = sales_state.expire_reservations(ttl, &product_state, &payment_state, chrono::Utc::now());
+                if !expired.is_empty() {
+                    println!("⌛ {} pending sale(s) cancelled after the stock reservation expired", expired.len());
+                }
+            }
+        });
+    }
+
+    // Atualização periódica do índice de busca; a primeira roda na subida
+    {
+        let search_state = search_state.clone();
+        let customer_state = customer_state.clone();
+        let product_state = product_state.clone();
+        let sales_state = sales_state.clone();
+        let payment_state = payment_state.clone();
+        let every = std::time::Duration::from_secs(config.search_refresh_interval.max(1));
+        actix_web::rt::spawn(async move {
+            let mut ticker = actix_web::rt::time::interval(every);
+            loop {
+                ticker.tick().await;
+                search_state.refresh(&customer_state, &product_state, &sales_state, &payment_state);
+            }
+        });
+    }
 
     HttpServer::new(move || {
         App::new()
+            .wrap(from_fn(idempotency))
+            .app_data(idempotency_store.clone())
             .app_data(auth_state.clone())
             .app_data(user_state.clone())
+            .app_data(sales_state.clone())
+            .app_data(product_state.clone())
+            .app_data(customer_state.clone())
+            .app_data(import_state.clone())
+            .app_data(invoice_state.clone())
+            .app_data(fiscal_state.clone())
+            .app_data(payment_state.clone())
+            .app_data(promotion_state.clone())
+            .app_data(subscription_state.clone())
+            .app_data(segment_state.clone())
+            .app_data(custom_field_state.clone())
+            .app_data(privacy_state.clone())
+            .app_data(search_state.clone())
+            .app_data(clv_state.clone())
+            .app_data(quote_state.clone())
+            .app_data(exchange_rates.clone())
+            .app_data(tax_table.clone())
             // Rotas de autenticação
             .route("/api/v1/auth/login", web::post().to(handlers::auth::login))
             .route("/api/v1/auth/register", web::post().to(handlers::auth::register))
@@ -36,10 +214,109 @@ async fn main() -> std::io::Result<()> {
             .route("/api/v1/health", web::get().to(handlers::api::health))
             .route("/api/v1/config", web::get().to(handlers::api::get_config))
             .route("/api/v1/dashboard", web::get().to(handlers::api::get_dashboard))
-            .route("/api/v1/sales", web::get().to(handlers::api::get_sales))
-            .route("/api/v1/customers", web::get().to(handlers::api::get_customers))
+            .route("/api/v1/sales", web::get().to(handlers::sales::get_sales))
+            .route("/api/v1/sales", web::post().to(handlers::sales::create_sale))
+            .route("/api/v1/sales/analytics", web::get().to(handlers::sales::get_sales_analytics))
+            .route("/api/v1/sales/{id}/cancel", web::post().to(handlers::sales::cancel_sale))
+            .route("/api/v1/sales/{id}/refunds", web::get().to(handlers::refunds::get_refunds))
+            .route("/api/v1/sales/{id}/refunds", web::post().to(handlers::refunds::create_refund))
+            .route("/api/v1/sales/{id}/invoice", web::get().to(handlers::invoices::get_invoice))
+            .route("/api/v1/sales/{id}/invoice", web::post().to(handlers::invoices::issue_invoice))
+            .route("/api/v1/sales/{id}/invoice.pdf", web::get().to(handlers::invoices::get_invoice_pdf))
+            .route("/api/v1/sales/{id}/nfe", web::get().to(handlers::fiscal::get_nfe))
+            .route("/api/v1/sales/{id}/nfe", web::post().to(handlers::fiscal::issue_nfe))
+            .route("/api/v1/sales/{id}/nfe.xml", web::get().to(handlers::fiscal::get_nfe_xml))
+            .route("/api/v1/sales/{id}/payments", web::get().to(handlers::payments::get_sale_payments))
+            .route("/api/v1/sales/{id}/payments", web::post().to(handlers::payments::create_payment))
+            // Rotas de pagamentos
+            .route("/api/v1/payments/{id}", web::get().to(handlers::payments::get_payment))
+            .route("/api/v1/payments/{id}/simulate", web::post().to(handlers::payments::simulate_payment))
+            .route("/api/v1/payments/webhooks/{gateway}", web::post().to(handlers::payments::receive_webhook))
+            // Rotas de produtos
+            .route("/api/v1/products", web::get().to(handlers::products::get_products))
+            .route("/api/v1/products", web::post().to(handlers::products::create_product))
+            .route("/api/v1/products/categories", web::get().to(handlers::products::get_categories))
+            .route("/api/v1/products/{id}", web::get().to(handlers::products::get_product))
+            .route("/api/v1/products/{id}", web::put().to(handlers::products::update_product))
+            .route("/api/v1/products/{id}/activate", web::post().to(handlers::products::activate_product))
+            .route("/api/v1/products/{id}/deactivate", web::post().to(handlers::products::deactivate_product))
+            .route("/api/v1/products/{id}/price-history", web::get().to(handlers::products::get_price_history))
+            // Rotas de estoque
+            .route("/api/v1/inventory", web::get().to(handlers::inventory::get_inventory))
+            .route("/api/v1/inventory/movements", web::get().to(handlers::inventory::get_stock_movements))
+            .route("/api/v1/inventory/movements", web::post().to(handlers::inventory::create_stock_movement))
+            .route("/api/v1/inventory/transfers", web::post().to(handlers::inventory::create_stock_transfer))
+            .route("/api/v1/inventory/reservations", web::get().to(handlers::inventory::get_stock_reservations))
+            .route("/api/v1/warehouses", web::get().to(handlers::inventory::get_warehouses))
+            .route("/api/v1/warehouses", web::post().to(handlers::inventory::create_warehouse))
+            .route("/api/v1/warehouses/{code}", web::put().to(handlers::inventory::update_warehouse))
+            .route("/api/v1/warehouses/{code}/stock", web::get().to(handlers::inventory::get_warehouse_stock))
+            // Rotas de promoções
+            .route("/api/v1/promotions", web::get().to(handlers::promotions::get_promotions))
+            .route("/api/v1/promotions", web::post().to(handlers::promotions::create_promotion))
+            .route("/api/v1/promotions/report", web::get().to(handlers::promotions::get_promotion_report))
+            .route("/api/v1/promotions/{id}", web::get().to(handlers::promotions::get_promotion))
+            .route("/api/v1/promotions/{id}", web::delete().to(handlers::promotions::deactivate_promotion))
+            // Rotas de assinaturas
+            .route("/api/v1/subscriptions", web::get().to(handlers::subscriptions::get_subscriptions))
+            .route("/api/v1/subscriptions", web::post().to(handlers::subscriptions::create_subscription))
+            .route("/api/v1/subscriptions/metrics", web::get().to(handlers::subscriptions::get_subscription_metrics))
+            .route("/api/v1/subscriptions/renewals/run", web::post().to(handlers::subscriptions::run_renewals))
+            .route("/api/v1/subscriptions/{id}", web::get().to(handlers::subscriptions::get_subscription))
+            .route("/api/v1/subscriptions/{id}/change", web::post().to(handlers::subscriptions::change_subscription))
+            .route("/api/v1/subscriptions/{id}/cancel", web::post().to(handlers::subscriptions::cancel_subscription))
+            // Rotas de cotações e pipeline
+            .route("/api/v1/quotes", web::get().to(handlers::quotes::get_quotes))
+            .route("/api/v1/quotes", web::post().to(handlers::quotes::create_quote))
+            .route("/api/v1/quotes/pipeline", web::get().to(handlers::quotes::get_pipeline))
+            .route("/api/v1/quotes/{id}", web::get().to(handlers::quotes::get_quote))
+            .route("/api/v1/quotes/{id}/stage", web::post().to(handlers::quotes::update_quote_stage))
+            .route("/api/v1/quotes/{id}/convert", web::post().to(handlers::quotes::convert_quote))
+            // Rotas de campos personalizados
+            .route("/api/v1/custom-fields", web::get().to(handlers::custom_fields::get_custom_fields))
+            .route("/api/v1/custom-fields", web::post().to(handlers::custom_fields::create_custom_field))
+            .route("/api/v1/custom-fields/{id}", web::delete().to(handlers::custom_fields::delete_custom_field))
+            .route("/api/v1/products/{id}/attributes", web::put().to(handlers::custom_fields::update_product_attributes))
+            // Rotas de privacidade (LGPD/GDPR)
+            .route("/api/v1/privacy/requests", web::get().to(handlers::privacy::get_privacy_requests))
+            .route("/api/v1/privacy/requests", web::post().to(handlers::privacy::create_privacy_request))
+            .route("/api/v1/privacy/requests/{id}", web::get().to(handlers::privacy::get_privacy_request))
+            .route("/api/v1/privacy/requests/{id}/complete", web::post().to(handlers::privacy::complete_privacy_request))
+            .route("/api/v1/privacy/requests/{id}/reject", web::post().to(handlers::privacy::reject_privacy_request))
+            .route("/api/v1/privacy/requests/{id}/export", web::get().to(handlers::privacy::get_privacy_export))
+            // Rotas de busca
+            .route("/api/v1/search", web::get().to(handlers::search::search))
+            .route("/api/v1/search/reindex", web::post().to(handlers::search::reindex))
+            // Rotas de importação
+            .route("/api/v1/import/sales", web::post().to(handlers::imports::import_sales))
+            .route("/api/v1/import/jobs/{id}", web::get().to(handlers::imports::get_import_job))
+            .route("/api/v1/import/jobs/{id}/errors", web::get().to(handlers::imports::get_import_errors))
+            // Rotas de clientes
+            .route("/api/v1/customers/segments", web::get().to(handlers::segments::get_segments))
+            .route("/api/v1/customers/segments/run", web::post().to(handlers::segments::run_segmentation))
+            .route("/api/v1/customers/segments/definitions", web::get().to(handlers::segments::get_segment_definitions))
+            .route("/api/v1/customers/segments/definitions", web::put().to(handlers::segments::update_segment_definitions))
+            .route("/api/v1/customers/segments/trends", web::get().to(handlers::segments::get_segment_trends))
+            .route("/api/v1/customers/clv", web::get().to(handlers::clv::get_clv_model))
+            .route("/api/v1/customers/clv/refresh", web::post().to(handlers::clv::refresh_clv))
+            .route("/api/v1/customers/duplicates", web::get().to(handlers::duplicates::get_duplicates))
+            .route("/api/v1/customers/duplicates/scan", web::post().to(handlers::duplicates::scan_duplicates))
+            .route("/api/v1/customers/duplicates/{id}/dismiss", web::post().to(handlers::duplicates::dismiss_duplicate))
+            .route("/api/v1/customers/merges", web::get().to(handlers::duplicates::get_merges))
+            .route("/api/v1/customers/merges", web::post().to(handlers::duplicates::merge_customers))
+            .route("/api/v1/customers/merges/{id}/undo", web::post().to(handlers::duplicates::undo_merge))
+            .route("/api/v1/customers/export", web::get().to(handlers::customers::export_customers))
+            .route("/api/v1/customers", web::get().to(handlers::customers::get_customers))
+            .route("/api/v1/customers", web::post().to(handlers::customers::create_customer))
+            .route("/api/v1/customers/{id}", web::get().to(handlers::customers::get_customer))
+            .route("/api/v1/customers/{id}", web::put().to(handlers::customers::update_customer))
+            .route("/api/v1/customers/{id}", web::delete().to(handlers::customers::delete_customer))
+            .route("/api/v1/customers/{id}/sales", web::get().to(handlers::customers::get_customer_sales))
+            .route("/api/v1/customers/{id}/notes", web::get().to(handlers::customers::get_customer_notes))
+            .route("/api/v1/customers/{id}/notes", web::post().to(handlers::customers::create_customer_note))
+            .route("/api/v1/customers/{id}/timeline", web::get().to(handlers::timeline::get_customer_timeline))
     })
-    .bind("0.0.0.0:8080")?
+    .bind((config.host.as_str(), config.port))?
     .run()
     .await
 }
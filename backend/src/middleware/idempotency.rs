@@ -0,0 +1,223 @@
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{error, web, Error, HttpMessage, HttpResponse};
+use crate::handlers::auth::{bearer_claims, AppState as AuthState};
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+pub const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+const MAX_KEY_LENGTH: usize = 255;
+
+enum EntryState {
+    InProgress,
+    Completed {
+        status: StatusCode,
+        content_type: Option<header::HeaderValue>,
+        body: web::Bytes,
+    },
+}
+
+struct IdempotencyEntry {
+    fingerprint: String,
+    state: EntryState,
+    expires_at: DateTime<Utc>,
+}
+
+// Chaves valem por cliente e por rota: (cliente, método, caminho, chave)
+type EntryKey = (String, String, String, String);
+
+pub struct IdempotencyStore {
+    entries: Mutex<HashMap<EntryKey, IdempotencyEntry>>,
+    ttl: Duration,
+    // Corpo máximo lido para calcular a impressão digital
+    max_body_bytes: usize,
+}
+
+enum Lookup {
+    New,
+    Replay(HttpResponse),
+    InProgress,
+    Mismatch,
+}
+
+impl IdempotencyStore {
+    pub fn new(ttl_seconds: i64, max_body_bytes: usize) -> Self {
+        IdempotencyStore {
+            entries: Mutex::new(HashMap::new()),
+            ttl: Duration::seconds(ttl_seconds),
+            max_body_bytes,
+        }
+    }
+
+    // Registra a chave como em andamento, ou devolve o que já existe para ela
+    fn begin(&self, key: &EntryKey, fingerprint: &str) -> Lookup {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Utc::now();
+        entries.retain(|_, entry| entry.expires_at > now);
+
+        match entries.get(key) {
+            Some(entry) if entry.fingerprint != fingerprint => Lookup::Mismatch,
+            Some(IdempotencyEntry { state: EntryState::InProgress, .. }) => Lookup::InProgress,
+            Some(IdempotencyEntry { state: EntryState::Completed { status, content_type, body }, .. }) => {
+                let mut response = HttpResponse::build(*status);
+                if let Some(content_type) = content_type {
+                    response.insert_header((header::CONTENT_TYPE, content_type.clone()));
+                }
+                response.insert_header(("Idempotent-Replayed", "true"));
+                Lookup::Replay(response.body(body.clone()))
+            }
+            None => {
+                entries.insert(key.clone(), IdempotencyEntry {
+                    fingerprint: fingerprint.to_string(),
+                    state: EntryState::InProgress,
+                    expires_at: now + self.ttl,
+                });
+                Lookup::New
+            }
+        }
+    }
+
+    fn complete(&self, key: &EntryKey, status: StatusCode, content_type: Option<header::HeaderValue>, body: web::Bytes) {
+        let mut entries = self.entries.lock().unwrap();
+        // Erros de servidor não são guardados para que o cliente possa tentar de novo
+        if status.is_server_error() {
+            entries.remove(key);
+        } else if let Some(entry) = entries.get_mut(key) {
+            entry.state = EntryState::Completed { status, content_type, body };
+        }
+    }
+
+    fn abort(&self, key: &EntryKey) {
+        // Chamado também durante o unwind de um pânico; não pode entrar em pânico de novo
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(key);
+        }
+    }
+}
+
+// Libera a chave se a requisição não terminar: erro, pânico no handler ou cliente que desistiu
+struct InFlight {
+    store: web::Data<IdempotencyStore>,
+    key: EntryKey,
+    completed: bool,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.store.abort(&self.key);
+        }
+    }
+}
+
+// Usuário autenticado pelo token; sem token, o endereço da conexão
+fn client_identity(req: &ServiceRequest) -> String {
+    let claims = req.app_data::<web::Data<AuthState>>().and_then(|auth| bearer_claims(req.request(), auth));
+    match claims {
+        Some(claims) => format!("user:{}", claims.sub),
+        None => format!("ip:{}", req.peer_addr().map(|addr| addr.ip().to_string()).unwrap_or_default()),
+    }
+}
+
+fn too_large(limit: usize) -> HttpResponse {
+    HttpResponse::PayloadTooLarge().json(json!({
+        "error": format!("Requests with {} are limited to {} bytes", IDEMPOTENCY_HEADER, limit)
+    }))
+}
+
+fn fingerprint(req: &ServiceRequest, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(req.uri().to_string());
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+fn reject(req: ServiceRequest, response: HttpResponse) -> Result<ServiceResponse<BoxBody>, Error> {
+    Ok(req.into_response(response))
+}
+
+// Garante que POSTs repetidos com o mesmo Idempotency-Key não executem duas vezes
+pub async fn idempotency(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let key = req.headers()
+        .get(IDEMPOTENCY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_string());
+
+    let (Some(key), Some(store)) = (key, req.app_data::<web::Data<IdempotencyStore>>().cloned()) else {
+        return next.call(req).await.map(|res| res.map_into_boxed_body());
+    };
+    if req.method() != Method::POST {
+        return next.call(req).await.map(|res| res.map_into_boxed_body());
+    }
+
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return reject(req, HttpResponse::BadRequest().json(json!({
+            "error": format!("{} must have between 1 and {} characters", IDEMPOTENCY_HEADER, MAX_KEY_LENGTH)
+        })));
+    }
+
+    // O corpo é lido por inteiro, até o limite, para calcular a impressão digital e depois devolvido ao handler
+    let limit = store.max_body_bytes;
+    let declared = req.headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > limit) {
+        return reject(req, too_large(limit));
+    }
+    let mut body = web::BytesMut::new();
+    let mut payload = req.take_payload();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > limit {
+            return reject(req, too_large(limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = body.freeze();
+    let fingerprint = fingerprint(&req, &body);
+    req.set_payload(Payload::from(body));
+
+    let key: EntryKey = (client_identity(&req), req.method().to_string(), req.path().to_string(), key);
+    match store.begin(&key, &fingerprint) {
+        Lookup::Replay(response) => return reject(req, response),
+        Lookup::InProgress => {
+            return reject(req, HttpResponse::Conflict().json(json!({
+                "error": "A request with this Idempotency-Key is still being processed"
+            })));
+        }
+        Lookup::Mismatch => {
+            return reject(req, HttpResponse::UnprocessableEntity().json(json!({
+                "error": "Idempotency-Key was already used with a different request"
+            })));
+        }
+        Lookup::New => {}
+    }
+
+    let mut in_flight = InFlight { store, key, completed: false };
+    let res = next.call(req).await?;
+
+    let (req, res) = res.into_parts();
+    let status = res.status();
+    let headers = res.headers().clone();
+    let body = to_bytes(res.into_body()).await.map_err(|err| error::ErrorInternalServerError(err.into().to_string()))?;
+
+    in_flight.store.complete(&in_flight.key, status, headers.get(header::CONTENT_TYPE).cloned(), body.clone());
+    in_flight.completed = true;
+
+    let mut response = HttpResponse::build(status);
+    for (name, value) in headers.iter() {
+        response.append_header((name.clone(), value.clone()));
+    }
+    Ok(ServiceResponse::new(req, response.body(body)))
+}
@@ -0,0 +1,52 @@
+use crate::models::money::{Currency, Money};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+// Parâmetros do BG/NBD: taxa de compra ~ Gamma(r, alpha), abandono ~ Beta(a, b)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BgNbdParams {
+    pub r: f64,
+    pub alpha: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+// Parâmetros do Gamma-Gamma para o valor médio por compra
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GammaGammaParams {
+    pub p: f64,
+    pub q: f64,
+    pub gamma: f64,
+}
+
+// Previsão de valor futuro de um cliente no horizonte do modelo, na moeda base
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClvEstimate {
+    pub expected_purchases: Decimal,
+    pub expected_order_value: Money,
+    // Probabilidade, em percentual, de o cliente ainda estar ativo
+    pub probability_alive: Decimal,
+    pub value: Money,
+    pub lower: Money,
+    pub upper: Money,
+    pub horizon_months: u32,
+    pub computed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClvModel {
+    pub computed_at: DateTime<Utc>,
+    pub currency: Currency,
+    pub horizon_months: u32,
+    // Intervalo de confiança das previsões, em percentual
+    pub confidence: u32,
+    pub bootstrap_samples: usize,
+    pub customers_fitted: usize,
+    pub repeat_customers: usize,
+    pub bg_nbd: Option<BgNbdParams>,
+    // Ausente quando há poucos clientes recorrentes; usa-se o ticket médio de cada cliente
+    pub gamma_gamma: Option<GammaGammaParams>,
+    pub total_lifetime_value: Decimal,
+    pub total_predicted_value: Decimal,
+}
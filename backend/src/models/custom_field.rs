@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldEntity {
+    Customer,
+    Product,
+}
+
+impl CustomFieldEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomFieldEntity::Customer => "customer",
+            CustomFieldEntity::Product => "product",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    // Guardada como "YYYY-MM-DD"
+    Date,
+    Enum,
+    Boolean,
+}
+
+// Atributo definido pelo cliente da plataforma (ramo de atuação, responsável pela conta...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomFieldDefinition {
+    pub id: Uuid,
+    pub entity: CustomFieldEntity,
+    // Chave no objeto `custom_fields` e nos filtros `custom.<key>`
+    pub key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    // Valores aceitos em campos `enum`
+    pub options: Vec<String>,
+    pub required: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomFieldDefinitionCreate {
+    pub entity: CustomFieldEntity,
+    pub key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+// Só os campos informados são alterados; em `custom_fields`, `null` remove o valor
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AttributesUpdate {
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
+    pub tags: Option<Vec<String>>,
+}
@@ -0,0 +1,70 @@
+use crate::models::Customer;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateStatus {
+    Pending,
+    Dismissed,
+    Merged,
+}
+
+// Par de clientes possivelmente duplicados aguardando revisão
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCandidate {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub duplicate_id: Uuid,
+    // 0 a 100
+    pub score: Decimal,
+    pub reasons: Vec<String>,
+    pub status: DuplicateStatus,
+    pub detected_at: DateTime<Utc>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+impl DuplicateCandidate {
+    pub fn involves(&self, a: Uuid, b: Uuid) -> bool {
+        (self.customer_id == a && self.duplicate_id == b) || (self.customer_id == b && self.duplicate_id == a)
+    }
+}
+
+// Registros movidos do cliente absorvido para o sobrevivente, para desfazer a fusão
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReassignment {
+    pub sales: Vec<Uuid>,
+    pub subscriptions: Vec<Uuid>,
+    pub mrr_movements: Vec<Uuid>,
+    pub quotes: Vec<Uuid>,
+    pub promotion_redemptions: Vec<Uuid>,
+    pub notes: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerMerge {
+    pub id: Uuid,
+    pub survivor_id: Uuid,
+    pub merged_id: Uuid,
+    // Cópias dos dois cadastros antes da fusão
+    pub survivor_before: Customer,
+    pub merged_customer: Customer,
+    // Campos vazios do sobrevivente preenchidos com os do absorvido
+    pub filled_fields: Vec<String>,
+    // Chaves de campos personalizados e tags copiadas do absorvido; o desfazer remove só estas
+    pub added_custom_fields: Vec<String>,
+    pub added_tags: Vec<String>,
+    pub reassigned: MergeReassignment,
+    pub merged_by: Option<String>,
+    pub merged_at: DateTime<Utc>,
+    pub undone_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeRequest {
+    pub survivor_id: Uuid,
+    pub merged_id: Uuid,
+    pub merged_by: Option<String>,
+}
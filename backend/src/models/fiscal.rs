@@ -0,0 +1,218 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+// Valor de cada caractere no cálculo do dígito verificador; letras valem (ASCII - 48),
+// o que cobre o CNPJ alfanumérico e mantém o cálculo original para dígitos
+fn char_value(c: char) -> u32 {
+    c as u32 - '0' as u32
+}
+
+fn mod11_digit(base: &str, weights: &[u32]) -> char {
+    let sum: u32 = base.chars().zip(weights).map(|(c, w)| char_value(c) * w).sum();
+    let remainder = sum % 11;
+    let digit = if remainder < 2 { 0 } else { 11 - remainder };
+    char::from_digit(digit, 10).unwrap()
+}
+
+// Os cálculos fatiam por posição de byte, então só aceitam ASCII
+fn valid_cpf(cpf: &str) -> bool {
+    if cpf.len() != 11 || !cpf.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    // 000.000.000-00, 111.111.111-11 etc. passam no cálculo mas não são válidos
+    if cpf.chars().all(|c| c == cpf.chars().next().unwrap()) {
+        return false;
+    }
+    let first = mod11_digit(&cpf[..9], &[10, 9, 8, 7, 6, 5, 4, 3, 2]);
+    let second = mod11_digit(&cpf[..10], &[11, 10, 9, 8, 7, 6, 5, 4, 3, 2]);
+    cpf[9..] == format!("{}{}", first, second)
+}
+
+fn valid_cnpj(cnpj: &str) -> bool {
+    if cnpj.len() != 14
+        || !cnpj.is_ascii()
+        || !cnpj[..12].chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+        || !cnpj[12..].chars().all(|c| c.is_ascii_digit())
+    {
+        return false;
+    }
+    if cnpj.chars().all(|c| c == cnpj.chars().next().unwrap()) {
+        return false;
+    }
+    let first = mod11_digit(&cnpj[..12], &[5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2]);
+    let second = mod11_digit(&cnpj[..13], &[6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2]);
+    cnpj[12..] == format!("{}{}", first, second)
+}
+
+// CPF (pessoa física) ou CNPJ (pessoa jurídica), sempre guardado sem pontuação
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TaxId {
+    Cpf(String),
+    Cnpj(String),
+}
+
+impl TaxId {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let normalized: String = raw
+            .chars()
+            .filter(|c| !matches!(c, '.' | '-' | '/' | ' '))
+            .collect::<String>()
+            .to_uppercase();
+        if !normalized.is_ascii() {
+            return Err(format!("Tax ID must contain only digits and letters: {}", raw));
+        }
+
+        match normalized.len() {
+            11 if valid_cpf(&normalized) => Ok(TaxId::Cpf(normalized)),
+            14 if valid_cnpj(&normalized) => Ok(TaxId::Cnpj(normalized)),
+            11 => Err(format!("Invalid CPF: {}", raw)),
+            14 => Err(format!("Invalid CNPJ: {}", raw)),
+            _ => Err(format!("Tax ID must be a CPF (11 digits) or CNPJ (14 characters): {}", raw)),
+        }
+    }
+
+    pub fn digits(&self) -> &str {
+        match self {
+            TaxId::Cpf(value) | TaxId::Cnpj(value) => value,
+        }
+    }
+
+    pub fn formatted(&self) -> String {
+        match self {
+            TaxId::Cpf(v) => format!("{}.{}.{}-{}", &v[..3], &v[3..6], &v[6..9], &v[9..]),
+            TaxId::Cnpj(v) => format!("{}.{}.{}/{}-{}", &v[..2], &v[2..5], &v[5..8], &v[8..12], &v[12..]),
+        }
+    }
+}
+
+impl TryFrom<String> for TaxId {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        TaxId::parse(&raw)
+    }
+}
+
+impl From<TaxId> for String {
+    fn from(tax_id: TaxId) -> Self {
+        tax_id.digits().to_string()
+    }
+}
+
+impl fmt::Display for TaxId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.formatted())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiscalAddress {
+    pub street: String,
+    pub number: String,
+    pub district: String,
+    // Código IBGE do município (7 dígitos)
+    pub city_code: String,
+    pub city: String,
+    pub state: String,
+    pub postal_code: String,
+    pub phone: Option<String>,
+}
+
+// Dados do emitente, carregados de arquivo como as cotações
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiscalIssuer {
+    pub cnpj: TaxId,
+    pub legal_name: String,
+    pub trade_name: Option<String>,
+    pub state_registration: String,
+    // Código de Regime Tributário: 1 = Simples Nacional, 3 = Regime Normal
+    pub tax_regime: u8,
+    pub icms_rate: Decimal,
+    pub pis_rate: Decimal,
+    pub cofins_rate: Decimal,
+    pub cfop: String,
+    pub series: u32,
+    // 1 = produção, 2 = homologação
+    pub environment: u8,
+    pub address: FiscalAddress,
+}
+
+impl FiscalIssuer {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read fiscal issuer from {}: {}", path, e))?;
+        let issuer: FiscalIssuer = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid fiscal issuer file {}: {}", path, e))?;
+
+        if !matches!(issuer.cnpj, TaxId::Cnpj(_)) {
+            return Err("Fiscal issuer must be identified by a CNPJ".to_string());
+        }
+        if !matches!(issuer.tax_regime, 1 | 3) {
+            return Err(format!("Unsupported tax regime: {}", issuer.tax_regime));
+        }
+        Ok(issuer)
+    }
+}
+
+// NF-e gerada para uma venda; o XML não é assinado nem transmitido à SEFAZ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NfeDocument {
+    pub id: Uuid,
+    pub sale_id: Uuid,
+    pub series: u32,
+    pub number: u64,
+    pub access_key: String,
+    pub total: Decimal,
+    #[serde(skip_serializing)]
+    pub xml: String,
+    pub issued_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TaxId;
+
+    #[test]
+    fn accepts_valid_cpfs_with_or_without_punctuation() {
+        assert_eq!(TaxId::parse("529.982.247-25"), Ok(TaxId::Cpf("52998224725".to_string())));
+        assert_eq!(TaxId::parse("11144477735"), Ok(TaxId::Cpf("11144477735".to_string())));
+        assert_eq!(TaxId::parse("52998224725").unwrap().formatted(), "529.982.247-25");
+    }
+
+    #[test]
+    fn rejects_invalid_cpfs() {
+        assert!(TaxId::parse("529.982.247-24").is_err());
+        assert!(TaxId::parse("111.111.111-11").is_err());
+        assert!(TaxId::parse("5299822472A").is_err());
+    }
+
+    #[test]
+    fn accepts_valid_numeric_and_alphanumeric_cnpjs() {
+        assert_eq!(TaxId::parse("11.222.333/0001-81"), Ok(TaxId::Cnpj("11222333000181".to_string())));
+        assert_eq!(TaxId::parse("11444777000161"), Ok(TaxId::Cnpj("11444777000161".to_string())));
+        assert_eq!(TaxId::parse("12.ABC.345/01DE-35"), Ok(TaxId::Cnpj("12ABC34501DE35".to_string())));
+        assert_eq!(TaxId::parse("12abc34501de35"), Ok(TaxId::Cnpj("12ABC34501DE35".to_string())));
+        assert_eq!(TaxId::parse("12ABC34501DE35").unwrap().formatted(), "12.ABC.345/01DE-35");
+    }
+
+    #[test]
+    fn rejects_invalid_cnpjs() {
+        assert!(TaxId::parse("11.222.333/0001-80").is_err());
+        assert!(TaxId::parse("00000000000000").is_err());
+        assert!(TaxId::parse("12ABC34501DE36").is_err());
+        // Letras só valem na raiz e na ordem, nunca nos dígitos verificadores
+        assert!(TaxId::parse("12ABC34501DEA5").is_err());
+    }
+
+    #[test]
+    fn rejects_non_ascii_and_wrong_lengths_without_panicking() {
+        assert!(TaxId::parse("1234567890€1").is_err());
+        assert!(TaxId::parse("1234567890€12").is_err());
+        assert!(TaxId::parse("123456789").is_err());
+        assert!(TaxId::parse("").is_err());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::models::money::Money;
+use crate::models::query::{FieldValue, Listable};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Código do depósito criado com o sistema
+pub const DEFAULT_LOCATION: &str = "main";
+
+// Depósito (local de estoque), identificado pelo código usado nos lançamentos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Warehouse {
+    pub code: String,
+    pub name: String,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    // Primeira opção na escolha automática do depósito de uma venda
+    pub is_default: bool,
+    // Depósitos inativos não recebem mercadoria nem atendem vendas novas
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WarehouseCreate {
+    pub code: String,
+    pub name: String,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub is_default: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WarehouseUpdate {
+    pub name: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub is_default: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+// Parte de uma venda atendida por um depósito
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockAllocation {
+    pub product_id: Uuid,
+    pub location: String,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StockMovementKind {
+    Receipt,
+    Sale,
+    RefundReturn,
+    Adjustment,
+    Transfer,
+}
+
+impl StockMovementKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockMovementKind::Receipt => "receipt",
+            StockMovementKind::Sale => "sale",
+            StockMovementKind::RefundReturn => "refund_return",
+            StockMovementKind::Adjustment => "adjustment",
+            StockMovementKind::Transfer => "transfer",
+        }
+    }
+}
+
+// Lançamento do razão de estoque; nunca é alterado nem apagado
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockMovement {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub kind: StockMovementKind,
+    pub location: String,
+    // Positiva para entradas, negativa para saídas
+    pub quantity: i32,
+    // Venda, reembolso ou transferência que originou o lançamento
+    pub reference_id: Option<Uuid>,
+    pub reason: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Listable for StockMovement {
+    const FILTERABLE: &'static [&'static str] = &["product_id", "kind", "location", "reference_id", "quantity", "created_at"];
+    const SORTABLE: &'static [&'static str] = &["created_at", "quantity"];
+    const DEFAULT_SORT: &'static str = "-created_at";
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        match field {
+            "product_id" => FieldValue::Text(self.product_id.to_string()),
+            "kind" => FieldValue::Text(self.kind.as_str().to_string()),
+            "location" => FieldValue::Text(self.location.clone()),
+            "reference_id" => self.reference_id.map(|id| FieldValue::Text(id.to_string())).unwrap_or(FieldValue::Null),
+            "quantity" => FieldValue::Number(self.quantity as f64),
+            "created_at" => FieldValue::Time(self.created_at),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReservationStatus {
+    Active,
+    Committed,
+    Released,
+}
+
+impl ReservationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Active => "active",
+            ReservationStatus::Committed => "committed",
+            ReservationStatus::Released => "released",
+        }
+    }
+}
+
+// Unidades separadas para uma venda pendente; viram baixa no pagamento ou voltam na falha
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockReservation {
+    pub id: Uuid,
+    pub sale_id: Uuid,
+    pub product_id: Uuid,
+    pub location: String,
+    pub quantity: i32,
+    pub status: ReservationStatus,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl Listable for StockReservation {
+    const FILTERABLE: &'static [&'static str] = &["sale_id", "product_id", "location", "status", "created_at"];
+    const SORTABLE: &'static [&'static str] = &["created_at", "quantity"];
+    const DEFAULT_SORT: &'static str = "-created_at";
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        match field {
+            "sale_id" => FieldValue::Text(self.sale_id.to_string()),
+            "product_id" => FieldValue::Text(self.product_id.to_string()),
+            "location" => FieldValue::Text(self.location.clone()),
+            "status" => FieldValue::Text(self.status.as_str().to_string()),
+            "quantity" => FieldValue::Number(self.quantity as f64),
+            "created_at" => FieldValue::Time(self.created_at),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+// Saldo de um produto em um local
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationStock {
+    pub location: String,
+    pub on_hand: i32,
+    pub reserved: i32,
+    pub available: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockLevel {
+    pub product_id: Uuid,
+    pub sku: String,
+    pub name: String,
+    pub on_hand: i32,
+    pub reserved: i32,
+    pub available: i32,
+    pub locations: Vec<LocationStock>,
+}
+
+// Entrada manual: recebimento de mercadoria ou ajuste de inventário
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockMovementCreate {
+    pub product_id: Uuid,
+    pub kind: StockMovementKind,
+    pub quantity: i32,
+    pub location: Option<String>,
+    pub reason: Option<String>,
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StockTransferCreate {
+    pub product_id: Uuid,
+    pub from_location: String,
+    pub to_location: String,
+    pub quantity: i32,
+    pub reason: Option<String>,
+    pub created_by: Option<String>,
+}
+
+// Demanda prevista de um produto em um depósito para o horizonte da otimização
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationDemand {
+    pub product_id: Uuid,
+    pub location: String,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferSuggestion {
+    pub product_id: Uuid,
+    pub sku: String,
+    pub from_location: String,
+    pub to_location: String,
+    pub quantity: i32,
+    pub reason: String,
+}
+
+// Parâmetros de reposição de um produto que substituem os padrões da requisição
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderOverride {
+    pub product_id: Uuid,
+    // Prazo de entrega do fornecedor, em dias
+    pub lead_time_days: Option<f64>,
+    pub service_level: Option<f64>,
+}
+
+// Política de reposição de um produto (estoque de segurança, ponto de pedido e lote econômico)
+// e o pedido sugerido agora, com a explicação de cada número
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderRecommendation {
+    pub product_id: Uuid,
+    pub sku: String,
+    pub name: String,
+    pub units_sold: i32,
+    pub average_daily_demand: Decimal,
+    pub demand_std_dev: Decimal,
+    pub lead_time_days: Decimal,
+    pub service_level: Decimal,
+    pub safety_stock: i32,
+    pub reorder_point: i32,
+    // Ausente quando o produto não tem custo cadastrado
+    pub economic_order_quantity: Option<i32>,
+    // Disponível em todos os depósitos (saldo menos reservado)
+    pub inventory_position: i32,
+    pub order_quantity: i32,
+    // Depósito que recebe o pedido
+    pub location: String,
+    // Custos na moeda base
+    pub unit_cost: Option<Money>,
+    pub order_cost: Option<Money>,
+    pub reasons: Vec<String>,
+}
@@ -1,7 +1,33 @@
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
+pub mod clv;
+pub mod custom_field;
+pub mod duplicate;
+pub mod fiscal;
+pub mod inventory;
+pub mod money;
+pub mod privacy;
+pub mod promotion;
+pub mod query;
+pub mod quote;
+pub mod search;
+pub mod segment;
+pub mod subscription;
+pub mod tax;
+pub mod timeline;
+
+use clv::ClvEstimate;
+use fiscal::TaxId;
+use money::{round_cents, Currency, Money};
+use promotion::AppliedPromotion;
+use query::{FieldValue, Listable};
+use tax::LineTax;
+
 // Auth models
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoginRequest {
@@ -53,47 +79,761 @@ pub enum UserRole {
 }
 
 // Sales models
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sale {
     pub id: Uuid,
     pub customer_id: Uuid,
-    pub product_id: Uuid,
-    pub amount: f64,
-    pub quantity: i32,
+    pub items: Vec<SaleItem>,
+    pub amount: Money,
+    pub refunded_amount: Money,
+    // Cotação da moeda da venda para a moeda base no momento da venda
+    pub exchange_rate: Decimal,
+    // Região usada nas regras de imposto e se os preços informados já incluíam impostos
+    pub tax_region: String,
+    pub prices_include_tax: bool,
+    // Descontos de promoção já estão nas linhas; aqui fica o registro de cada uma
+    #[serde(default)]
+    pub promotions: Vec<AppliedPromotion>,
+    // Preenchido nas cobranças geradas por assinaturas
+    #[serde(default)]
+    pub subscription_id: Option<Uuid>,
+    // Observações livres e referências externas (pedido importado, cotação)
+    #[serde(default)]
+    pub notes: Option<String>,
+    // Cliente e produtos como estavam na venda; a fatura é emitida a partir daqui
+    pub snapshot: SaleSnapshot,
     pub status: SaleStatus,
-    pub payment_method: String,
+    pub payment_method: PaymentMethod,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+impl Sale {
+    // O total da venda é sempre derivado das linhas
+    pub fn total_from(items: &[SaleItem], currency: Currency) -> Money {
+        Money::new(items.iter().map(|item| item.line_total).sum(), currency)
+    }
+
+    pub fn currency(&self) -> &Currency {
+        &self.amount.currency
+    }
+
+    pub fn quantity(&self) -> i32 {
+        self.items.iter().map(|item| item.quantity).sum()
+    }
+
+    pub fn net_amount(&self) -> Result<Money, String> {
+        self.amount.checked_sub(&self.refunded_amount)
+    }
+
+    pub fn tax_amount(&self) -> Money {
+        Money::new(self.items.iter().map(|item| item.tax_amount).sum(), self.currency().clone())
+    }
+
+    // Imposto descontados os reembolsos
+    pub fn net_tax(&self) -> Money {
+        Money::new(self.items.iter().map(|item| item.net_tax()).sum(), self.currency().clone())
+    }
+
+    // Valor na moeda base usando a cotação registrada na venda
+    pub fn to_base(&self, amount: Decimal) -> Decimal {
+        round_cents(amount * self.exchange_rate)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaleItem {
+    pub product_id: Uuid,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub discount: Decimal,
+    // Valor cobrado pela linha, já com os impostos
+    pub line_total: Decimal,
+    pub taxes: Vec<LineTax>,
+    pub tax_amount: Decimal,
+    pub refunded_quantity: i32,
+    pub refunded_amount: Decimal,
+}
+
+impl SaleItem {
+    pub fn new(product_id: Uuid, quantity: i32, unit_price: Decimal, discount: Decimal) -> Result<Self, String> {
+        if quantity <= 0 {
+            return Err("Quantity must be greater than zero".to_string());
+        }
+        if unit_price.is_sign_negative() {
+            return Err("Unit price cannot be negative".to_string());
+        }
+        let unit_price = round_cents(unit_price);
+        let discount = round_cents(discount);
+        let gross = unit_price.checked_mul(Decimal::from(quantity)).ok_or("Line amount is too large")?;
+        if discount.is_sign_negative() || discount > gross {
+            return Err("Discount must be between zero and the line amount".to_string());
+        }
+        let line_total = gross.checked_sub(discount).ok_or("Line amount is too large")?;
+
+        Ok(SaleItem {
+            product_id,
+            quantity,
+            unit_price,
+            discount,
+            line_total,
+            taxes: Vec::new(),
+            tax_amount: Decimal::ZERO,
+            refunded_quantity: 0,
+            refunded_amount: Decimal::ZERO,
+        })
+    }
+
+    pub fn net_total(&self) -> Decimal {
+        self.line_total - self.refunded_amount
+    }
+
+    // Preço sem imposto: os impostos são somados ao total da linha
+    pub fn apply_taxes(&mut self, taxes: Vec<LineTax>, inclusive: bool) {
+        let tax_amount: Decimal = taxes.iter().map(|t| t.amount).sum();
+        if !inclusive {
+            self.line_total += tax_amount;
+        }
+        self.tax_amount = tax_amount;
+        self.taxes = taxes;
+    }
+
+    // Imposto ainda devido depois dos reembolsos, proporcional ao valor reembolsado
+    pub fn net_tax(&self) -> Decimal {
+        if self.line_total.is_zero() {
+            return Decimal::ZERO;
+        }
+        self.tax_amount - round_cents(self.tax_amount * self.refunded_amount / self.line_total)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SaleStatus {
     Pending,
     Completed,
     Failed,
+    PartiallyRefunded,
     Refunded,
+    // Pendente cancelada a pedido ou por reserva de estoque vencida
+    Cancelled,
+}
+
+impl SaleStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SaleStatus::Pending => "pending",
+            SaleStatus::Completed => "completed",
+            SaleStatus::Failed => "failed",
+            SaleStatus::PartiallyRefunded => "partially_refunded",
+            SaleStatus::Refunded => "refunded",
+            SaleStatus::Cancelled => "cancelled",
+        }
+    }
+
+    // Vendas que geraram receita (antes de descontar reembolsos)
+    pub fn is_captured(&self) -> bool {
+        matches!(self, SaleStatus::Completed | SaleStatus::PartiallyRefunded | SaleStatus::Refunded)
+    }
+}
+
+impl Listable for Sale {
+    const FILTERABLE: &'static [&'static str] =
+        &["status", "payment_method", "customer_id", "amount", "currency", "quantity", "created_at"];
+    const SORTABLE: &'static [&'static str] = &["amount", "quantity", "status", "created_at"];
+    const DEFAULT_SORT: &'static str = "-created_at";
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        match field {
+            "status" => FieldValue::Text(self.status.as_str().to_string()),
+            "payment_method" => FieldValue::Text(self.payment_method.as_str().to_string()),
+            "customer_id" => FieldValue::Text(self.customer_id.to_string()),
+            "amount" => FieldValue::Number(self.amount.amount.to_f64().unwrap_or_default()),
+            "currency" => FieldValue::Text(self.currency().to_string()),
+            "quantity" => FieldValue::Number(self.quantity() as f64),
+            "created_at" => FieldValue::Time(self.created_at),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SaleCreate {
     pub customer_id: Uuid,
+    pub items: Vec<SaleItemCreate>,
+    pub payment_method: PaymentMethod,
+    // Quando omitida, usa a moeda base
+    pub currency: Option<Currency>,
+    // Quando omitidos, usam a região padrão e o modo de preço da tabela de impostos
+    pub tax_region: Option<String>,
+    pub prices_include_tax: Option<bool>,
+    // Cupons informados pelo cliente; promoções automáticas não precisam de código
+    #[serde(default)]
+    pub coupon_codes: Vec<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    // Depósito que atende a venda; quando omitido, é escolhido automaticamente
+    #[serde(default)]
+    pub warehouse: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaleItemCreate {
     pub product_id: Uuid,
-    pub amount: f64,
     pub quantity: i32,
-    pub payment_method: String,
+    pub discount: Option<Decimal>,
+}
+
+// Payment models
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentMethod {
+    CreditCard,
+    DebitCard,
+    Paypal,
+    BankTransfer,
+    Pix,
+    Boleto,
+    Cash,
+    // Vendas importadas sem meio de pagamento informado
+    Other,
+}
+
+impl PaymentMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentMethod::CreditCard => "credit_card",
+            PaymentMethod::DebitCard => "debit_card",
+            PaymentMethod::Paypal => "paypal",
+            PaymentMethod::BankTransfer => "bank_transfer",
+            PaymentMethod::Pix => "pix",
+            PaymentMethod::Boleto => "boleto",
+            PaymentMethod::Cash => "cash",
+            PaymentMethod::Other => "other",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        serde_json::from_value(serde_json::Value::String(raw.trim().to_lowercase()))
+            .map_err(|_| format!("Unknown payment method '{}'", raw))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentIntentStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+// O que o comprador precisa para concluir o pagamento
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PaymentInstructions {
+    Pix {
+        // Payload "copia e cola" do BR Code, também usado para gerar o QR Code
+        br_code: String,
+        txid: String,
+        expires_at: DateTime<Utc>,
+    },
+    Boleto {
+        barcode: String,
+        digitable_line: String,
+        due_date: NaiveDate,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentIntent {
+    pub id: Uuid,
+    pub sale_id: Uuid,
+    pub gateway: String,
+    pub method: PaymentMethod,
+    pub amount: Money,
+    pub status: PaymentIntentStatus,
+    pub instructions: Option<PaymentInstructions>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PaymentIntentCreate {
+    // Quando omitido, usa o meio de pagamento da venda
+    pub method: Option<PaymentMethod>,
+}
+
+// Evento de mudança de status enviado pelo gateway via webhook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentEvent {
+    pub id: Uuid,
+    pub intent_id: Uuid,
+    pub status: PaymentIntentStatus,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PaymentSimulation {
+    pub status: PaymentIntentStatus,
+    pub failure_reason: Option<String>,
+}
+
+// Refund models
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundReason {
+    CustomerRequest,
+    Duplicate,
+    Defective,
+    Fraudulent,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefundLine {
+    pub product_id: Uuid,
+    pub quantity: i32,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Refund {
+    pub id: Uuid,
+    pub sale_id: Uuid,
+    pub amount: Money,
+    pub reason: RefundReason,
+    pub note: Option<String>,
+    pub lines: Vec<RefundLine>,
+    // Se alguma unidade voltou ao estoque (só volta o que a venda baixou do razão)
+    pub restocked: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefundLineCreate {
+    pub product_id: Uuid,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefundCreate {
+    // Informe `amount` ou `lines`; sem nenhum dos dois, reembolsa o saldo restante
+    pub amount: Option<Decimal>,
+    pub lines: Option<Vec<RefundLineCreate>>,
+    pub reason: RefundReason,
+    pub note: Option<String>,
+    #[serde(default)]
+    pub restock: bool,
+}
+
+// Product models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Product {
+    pub id: Uuid,
+    pub name: String,
+    pub sku: String,
+    pub category: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Nomenclatura Comum do Mercosul, exigida na NF-e
+    pub ncm: String,
+    pub price: Money,
+    // Custo unitário, na mesma moeda do preço
+    pub cost: Option<Money>,
+    // Saldo e reservado derivados do razão de estoque (somente leitura)
+    pub stock_quantity: i32,
+    pub reserved_quantity: i32,
+    // Produtos inativos saem do catálogo de venda, mas continuam nas vendas antigas
+    pub is_active: bool,
+    // Valores dos campos personalizados, validados contra o esquema de produtos
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Product {
+    // Margem sobre o preço de venda, em %; sem custo cadastrado não há margem
+    pub fn margin_percent(&self) -> Option<Decimal> {
+        margin_percent(&self.price, self.cost.as_ref())
+    }
+}
+
+pub fn margin_percent(price: &Money, cost: Option<&Money>) -> Option<Decimal> {
+    let cost = cost?;
+    if price.amount.is_zero() || cost.currency != price.currency {
+        return None;
+    }
+    Some(round_cents((price.amount - cost.amount) / price.amount * Decimal::ONE_HUNDRED))
+}
+
+impl Listable for Product {
+    const FILTERABLE: &'static [&'static str] =
+        &["name", "sku", "category", "is_active", "price", "margin", "stock_quantity", "tags", "created_at"];
+    const SORTABLE: &'static [&'static str] =
+        &["name", "sku", "category", "price", "margin", "stock_quantity", "created_at", "updated_at"];
+    const DEFAULT_SORT: &'static str = "name";
+    const DYNAMIC_PREFIXES: &'static [&'static str] = &["custom."];
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        if let Some(key) = field.strip_prefix("custom.") {
+            return FieldValue::from_json(self.custom_fields.get(key));
+        }
+        match field {
+            "name" => FieldValue::Text(self.name.clone()),
+            "sku" => FieldValue::Text(self.sku.clone()),
+            "category" => FieldValue::Text(self.category.clone()),
+            "is_active" => FieldValue::Bool(self.is_active),
+            "price" => FieldValue::Number(self.price.amount.to_f64().unwrap_or_default()),
+            "margin" => self
+                .margin_percent()
+                .map(|margin| FieldValue::Number(margin.to_f64().unwrap_or_default()))
+                .unwrap_or(FieldValue::Null),
+            "stock_quantity" => FieldValue::Number(self.stock_quantity as f64),
+            "tags" => FieldValue::List(self.tags.iter().cloned().map(FieldValue::Text).collect()),
+            "created_at" => FieldValue::Time(self.created_at),
+            "updated_at" => FieldValue::Time(self.updated_at),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
 }
 
-// Customer models
 #[derive(Debug, Serialize, Deserialize)]
+pub struct ProductCreate {
+    pub name: String,
+    pub sku: String,
+    pub category: String,
+    pub description: Option<String>,
+    pub ncm: Option<String>,
+    pub price: Decimal,
+    pub cost: Option<Decimal>,
+    // Padrão: moeda base
+    pub currency: Option<Currency>,
+    pub stock_quantity: Option<i32>,
+    pub is_active: Option<bool>,
+    #[serde(default)]
+    pub custom_fields: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub changed_by: Option<String>,
+}
+
+// Só os campos informados são alterados; o estoque muda pelas vendas e reembolsos
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductUpdate {
+    pub name: Option<String>,
+    pub sku: Option<String>,
+    pub category: Option<String>,
+    pub description: Option<String>,
+    pub ncm: Option<String>,
+    pub price: Option<Decimal>,
+    pub cost: Option<Decimal>,
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
+    pub tags: Option<Vec<String>>,
+    // Quem alterou preço ou custo, gravado no histórico
+    pub changed_by: Option<String>,
+}
+
+// Cada mudança de preço ou custo, para análises de margem e de preço ao longo do tempo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductPriceChange {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub price: Money,
+    pub cost: Option<Money>,
+    pub margin_percent: Option<Decimal>,
+    // Vazio no registro inicial do produto
+    pub previous_price: Option<Money>,
+    pub previous_cost: Option<Money>,
+    pub changed_by: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProductRevenue {
+    pub product_id: Uuid,
+    pub name: String,
+    pub quantity: i32,
+    pub orders: usize,
+    // Na moeda base, descontados os reembolsos
+    pub revenue: Money,
+    pub tax: Money,
+}
+
+// Customer models
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Customer {
     pub id: Uuid,
     pub name: String,
     pub email: String,
     pub phone: String,
     pub company: String,
-    pub lifetime_value: f64,
+    pub tax_id: Option<TaxId>,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub segment: Option<String>,
+    // Receita líquida de reembolsos na moeda base, recalculada com o CLV
+    pub lifetime_value: Money,
+    #[serde(default)]
+    pub predicted_clv: Option<ClvEstimate>,
     pub status: String,
+    // Valores dos campos personalizados, validados contra o esquema de clientes
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Preenchido quando os dados pessoais são apagados a pedido do titular
+    #[serde(default)]
+    pub anonymized_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Listable for Customer {
+    const FILTERABLE: &'static [&'static str] =
+        &["name", "email", "company", "city", "country", "segment", "status", "tags", "lifetime_value", "predicted_clv", "created_at"];
+    const SORTABLE: &'static [&'static str] =
+        &["name", "company", "lifetime_value", "predicted_clv", "created_at", "updated_at"];
+    const DEFAULT_SORT: &'static str = "name";
+    const DYNAMIC_PREFIXES: &'static [&'static str] = &["custom."];
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        if let Some(key) = field.strip_prefix("custom.") {
+            return FieldValue::from_json(self.custom_fields.get(key));
+        }
+        let optional = |value: &Option<String>| value.clone().map(FieldValue::Text).unwrap_or(FieldValue::Null);
+        match field {
+            "name" => FieldValue::Text(self.name.clone()),
+            "email" => FieldValue::Text(self.email.clone()),
+            "company" => FieldValue::Text(self.company.clone()),
+            "city" => optional(&self.city),
+            "country" => optional(&self.country),
+            "segment" => optional(&self.segment),
+            "status" => FieldValue::Text(self.status.clone()),
+            "tags" => FieldValue::List(self.tags.iter().cloned().map(FieldValue::Text).collect()),
+            "lifetime_value" => FieldValue::Number(self.lifetime_value.amount.to_f64().unwrap_or_default()),
+            "predicted_clv" => self
+                .predicted_clv
+                .as_ref()
+                .map(|clv| FieldValue::Number(clv.value.amount.to_f64().unwrap_or_default()))
+                .unwrap_or(FieldValue::Null),
+            "created_at" => FieldValue::Time(self.created_at),
+            "updated_at" => FieldValue::Time(self.updated_at),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomerCreate {
+    pub name: String,
+    pub email: String,
+    pub phone: Option<String>,
+    pub company: Option<String>,
+    pub tax_id: Option<String>,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub segment: Option<String>,
+    pub status: Option<String>,
+    #[serde(default)]
+    pub custom_fields: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// Só os campos informados são alterados
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomerUpdate {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub company: Option<String>,
+    pub tax_id: Option<String>,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub segment: Option<String>,
+    pub status: Option<String>,
+    // Em `custom_fields`, `null` remove o valor; `tags` substitui a lista
+    pub custom_fields: Option<serde_json::Map<String, serde_json::Value>>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerNote {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomerNoteCreate {
+    pub author: Option<String>,
+    pub body: String,
+}
+
+// Import models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SalesImportMapping {
+    // Nome da coluna na planilha para cada campo da venda
+    pub customer_email: String,
+    pub sku: String,
+    pub quantity: String,
+    pub unit_price: Option<String>,
+    pub discount: Option<String>,
+    pub currency: Option<String>,
+    pub date: Option<String>,
+    pub payment_method: Option<String>,
+    pub status: Option<String>,
+    // Linhas com a mesma referência viram uma única venda com vários itens
+    pub order_ref: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportJob {
+    pub id: Uuid,
+    pub file_name: String,
+    pub dry_run: bool,
+    pub status: ImportStatus,
+    pub total_rows: Option<usize>,
+    pub processed_rows: usize,
+    pub valid_rows: usize,
+    pub error_rows: usize,
+    pub valid_sales: usize,
+    pub sales_created: usize,
+    pub message: Option<String>,
+    #[serde(skip_serializing)]
+    pub errors: Vec<ImportRowError>,
     pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+// Invoice models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceCustomer {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub phone: String,
+    pub company: String,
+}
+
+impl From<&Customer> for InvoiceCustomer {
+    fn from(customer: &Customer) -> Self {
+        InvoiceCustomer {
+            id: customer.id,
+            name: customer.name.clone(),
+            email: customer.email.clone(),
+            phone: customer.phone.clone(),
+            company: customer.company.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductSnapshot {
+    pub product_id: Uuid,
+    pub sku: String,
+    pub name: String,
+}
+
+impl From<&Product> for ProductSnapshot {
+    fn from(product: &Product) -> Self {
+        ProductSnapshot { product_id: product.id, sku: product.sku.clone(), name: product.name.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaleSnapshot {
+    pub customer: InvoiceCustomer,
+    pub products: Vec<ProductSnapshot>,
+}
+
+impl SaleSnapshot {
+    // Um registro por produto das linhas, na ordem em que aparecem
+    pub fn capture(customer: &Customer, products: &[Product], items: &[SaleItem]) -> SaleSnapshot {
+        let mut snapshot = SaleSnapshot { customer: customer.into(), products: Vec::new() };
+        for product in items.iter().filter_map(|item| products.iter().find(|p| p.id == item.product_id)) {
+            snapshot.add_product(product.into());
+        }
+        snapshot
+    }
+
+    pub fn add_product(&mut self, product: ProductSnapshot) {
+        if !self.products.iter().any(|p| p.product_id == product.product_id) {
+            self.products.push(product);
+        }
+    }
+
+    pub fn product(&self, product_id: Uuid) -> Option<&ProductSnapshot> {
+        self.products.iter().find(|p| p.product_id == product_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub product_id: Uuid,
+    pub sku: String,
+    pub description: String,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub discount: Decimal,
+    pub tax_amount: Decimal,
+    pub line_total: Decimal,
+}
+
+// Cópia imutável dos dados da venda, do cliente e dos produtos na emissão
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Invoice {
+    pub id: Uuid,
+    pub number: String,
+    pub tenant_id: String,
+    pub sale_id: Uuid,
+    pub customer: InvoiceCustomer,
+    pub lines: Vec<InvoiceLine>,
+    pub subtotal: Money,
+    pub discount_total: Money,
+    pub tax_total: Money,
+    pub prices_include_tax: bool,
+    pub total: Money,
+    pub payment_method: PaymentMethod,
+    pub sale_date: DateTime<Utc>,
+    pub issued_at: DateTime<Utc>,
 }
 
 // AI models
@@ -0,0 +1,158 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{AddAssign, SubAssign};
+
+// Código ISO 4217 (ex.: "BRL", "USD")
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Currency(String);
+
+impl Currency {
+    pub fn new(code: &str) -> Result<Self, String> {
+        let code = code.trim().to_uppercase();
+        if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!("Invalid currency code: {}", code));
+        }
+        Ok(Currency(code))
+    }
+
+    pub fn code(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = String;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Currency::new(&code)
+    }
+}
+
+impl From<Currency> for String {
+    fn from(currency: Currency) -> Self {
+        currency.0
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// Maior preço unitário aceito: cabe nas colunas DECIMAL(10, 2) de produtos e itens de venda
+pub const MAX_UNIT_PRICE: Decimal = dec!(99_999_999.99);
+
+// Arredondamento comercial para centavos
+pub fn round_cents(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Money { amount, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Money { amount: Decimal::ZERO, currency }
+    }
+
+    // Moedas diferentes não se somam; conversões passam por ExchangeRates
+    pub fn checked_add(&self, other: &Money) -> Result<Money, String> {
+        if self.currency != other.currency {
+            return Err(format!("Cannot add {} to {}", other.currency, self.currency));
+        }
+        let amount = self.amount.checked_add(other.amount).ok_or("Amount is too large")?;
+        Ok(Money { amount, currency: self.currency.clone() })
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, String> {
+        if self.currency != other.currency {
+            return Err(format!("Cannot subtract {} from {}", other.currency, self.currency));
+        }
+        let amount = self.amount.checked_sub(other.amount).ok_or("Amount is too large")?;
+        Ok(Money { amount, currency: self.currency.clone() })
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:.2}", self.currency, self.amount)
+    }
+}
+
+impl AddAssign<Decimal> for Money {
+    fn add_assign(&mut self, amount: Decimal) {
+        self.amount += amount;
+    }
+}
+
+impl SubAssign<Decimal> for Money {
+    fn sub_assign(&mut self, amount: Decimal) {
+        self.amount -= amount;
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ExchangeRateFile {
+    base: Currency,
+    rates: HashMap<Currency, Decimal>,
+}
+
+// Cotações para a moeda base: 1 unidade da moeda = `rate` unidades da base
+#[derive(Debug, Clone)]
+pub struct ExchangeRates {
+    pub base: Currency,
+    rates: HashMap<Currency, Decimal>,
+}
+
+impl ExchangeRates {
+    pub fn new(base: Currency) -> Self {
+        ExchangeRates { base, rates: HashMap::new() }
+    }
+
+    pub fn load(path: &str, base: Currency) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read exchange rates from {}: {}", path, e))?;
+        let file: ExchangeRateFile = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid exchange rate file {}: {}", path, e))?;
+
+        if file.base != base {
+            return Err(format!(
+                "Exchange rate file is based on {} but the base currency is {}",
+                file.base, base
+            ));
+        }
+
+        // Cotação zero dividiria por zero na conversão; negativa inverteria os valores
+        if let Some((currency, rate)) = file.rates.iter().find(|(_, rate)| **rate <= Decimal::ZERO) {
+            return Err(format!("Exchange rate for {} must be positive, got {}", currency, rate));
+        }
+
+        Ok(ExchangeRates { base, rates: file.rates })
+    }
+
+    pub fn rate(&self, currency: &Currency) -> Option<Decimal> {
+        if *currency == self.base {
+            return Some(Decimal::ONE);
+        }
+        self.rates.get(currency).copied()
+    }
+
+    pub fn convert(&self, money: &Money, to: &Currency) -> Option<Money> {
+        let from_rate = self.rate(&money.currency)?;
+        let to_rate = self.rate(to)?;
+        let amount = money.amount.checked_mul(from_rate)?.checked_div(to_rate)?;
+        Some(Money::new(round_cents(amount), to.clone()))
+    }
+}
@@ -0,0 +1,135 @@
+use crate::models::query::{FieldValue, Listable};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSubjectType {
+    Customer,
+    User,
+}
+
+impl DataSubjectType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataSubjectType::Customer => "customer",
+            DataSubjectType::User => "user",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyRequestKind {
+    Export,
+    Erasure,
+}
+
+impl PrivacyRequestKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrivacyRequestKind::Export => "export",
+            PrivacyRequestKind::Erasure => "erasure",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyRequestStatus {
+    Pending,
+    Completed,
+    Rejected,
+}
+
+impl PrivacyRequestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrivacyRequestStatus::Pending => "pending",
+            PrivacyRequestStatus::Completed => "completed",
+            PrivacyRequestStatus::Rejected => "rejected",
+        }
+    }
+}
+
+// Comprovante de atendimento guardado no registro do pedido
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyEvidence {
+    // SHA-256 do e-mail do titular, para provar depois o atendimento sem guardar o dado
+    pub subject_fingerprint: String,
+    // SHA-256 do documento exportado, exatamente como é entregue
+    pub document_sha256: Option<String>,
+    // Quantidade de registros exportados ou anonimizados por categoria
+    pub records: BTreeMap<String, usize>,
+    // Dados mantidos e a base legal (ex.: documentos fiscais)
+    pub retained: Vec<String>,
+}
+
+// Pedido de titular (LGPD art. 18 / GDPR arts. 15 e 17)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyRequest {
+    pub id: Uuid,
+    pub kind: PrivacyRequestKind,
+    pub subject_type: DataSubjectType,
+    // Uuid do cliente ou id numérico do usuário
+    pub subject_id: String,
+    pub requested_by: Option<String>,
+    pub reason: Option<String>,
+    pub status: PrivacyRequestStatus,
+    pub received_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    // Quem atendeu ou recusou o pedido
+    pub handled_by: Option<String>,
+    pub rejection_reason: Option<String>,
+    pub evidence: Option<PrivacyEvidence>,
+}
+
+impl PrivacyRequest {
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.status == PrivacyRequestStatus::Pending && now > self.due_at
+    }
+}
+
+impl Listable for PrivacyRequest {
+    const FILTERABLE: &'static [&'static str] =
+        &["kind", "subject_type", "subject_id", "status", "overdue", "received_at", "due_at"];
+    const SORTABLE: &'static [&'static str] = &["received_at", "due_at", "completed_at"];
+    const DEFAULT_SORT: &'static str = "due_at";
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        match field {
+            "kind" => FieldValue::Text(self.kind.as_str().to_string()),
+            "subject_type" => FieldValue::Text(self.subject_type.as_str().to_string()),
+            "subject_id" => FieldValue::Text(self.subject_id.clone()),
+            "status" => FieldValue::Text(self.status.as_str().to_string()),
+            "overdue" => FieldValue::Bool(self.is_overdue(Utc::now())),
+            "received_at" => FieldValue::Time(self.received_at),
+            "due_at" => FieldValue::Time(self.due_at),
+            "completed_at" => self.completed_at.map(FieldValue::Time).unwrap_or(FieldValue::Null),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrivacyRequestCreate {
+    pub kind: PrivacyRequestKind,
+    pub subject_type: DataSubjectType,
+    pub subject_id: String,
+    pub requested_by: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PrivacyRequestAction {
+    pub performed_by: Option<String>,
+    // Obrigatório ao recusar
+    pub reason: Option<String>,
+}
@@ -0,0 +1,111 @@
+use crate::models::money::Money;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PromotionKind {
+    // Percentual sobre as linhas elegíveis
+    Percentage { percent: Decimal },
+    // Valor fixo rateado entre as linhas elegíveis, convertido para a moeda da venda
+    FixedAmount { amount: Money },
+    // A cada `buy + get` unidades do mesmo produto, `get` saem de graça
+    BuyXGetY { buy: i32, get: i32 },
+    // O cliente leva o produto superior pagando o preço do produto comprado
+    TierUpgrade { from_product_id: Uuid, to_product_id: Uuid },
+}
+
+impl PromotionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromotionKind::Percentage { .. } => "percentage",
+            PromotionKind::FixedAmount { .. } => "fixed_amount",
+            PromotionKind::BuyXGetY { .. } => "buy_x_get_y",
+            PromotionKind::TierUpgrade { .. } => "tier_upgrade",
+        }
+    }
+}
+
+// Sem código, a promoção é aplicada automaticamente às vendas elegíveis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Promotion {
+    pub id: Uuid,
+    pub code: Option<String>,
+    pub name: String,
+    pub kind: PromotionKind,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub max_redemptions: Option<u32>,
+    pub max_per_customer: Option<u32>,
+    // Sem produtos nem categorias, vale para todo o catálogo
+    pub product_ids: Vec<Uuid>,
+    pub categories: Vec<String>,
+    // Promoção não cumulativa não é combinada com nenhuma outra
+    pub stackable: bool,
+    // Maior prioridade é aplicada primeiro
+    pub priority: i32,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromotionCreate {
+    pub code: Option<String>,
+    pub name: String,
+    pub kind: PromotionKind,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub max_redemptions: Option<u32>,
+    pub max_per_customer: Option<u32>,
+    #[serde(default)]
+    pub product_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub stackable: bool,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+// Promoção aplicada a uma venda, com o desconto na moeda da venda
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedPromotion {
+    pub promotion_id: Uuid,
+    pub code: Option<String>,
+    pub name: String,
+    pub discount: Decimal,
+}
+
+// Promoção que era elegível mas ficou de fora da venda
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedPromotion {
+    pub promotion_id: Uuid,
+    pub code: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionRedemption {
+    pub id: Uuid,
+    pub promotion_id: Uuid,
+    pub sale_id: Uuid,
+    pub customer_id: Uuid,
+    pub discount: Money,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromotionReport {
+    pub promotion_id: Uuid,
+    pub code: Option<String>,
+    pub name: String,
+    pub kind: String,
+    pub redemptions: usize,
+    pub customers: usize,
+    // Valores na moeda base, considerando só vendas capturadas
+    pub discount_given: Money,
+    pub revenue: Money,
+    pub average_order_value: Money,
+}
@@ -0,0 +1,406 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 100;
+
+// Parâmetros reservados; todo o resto é tratado como filtro
+const RESERVED_PARAMS: [&str; 4] = ["sort", "limit", "cursor", "include_total"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "t", content = "v")]
+pub enum FieldValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Time(DateTime<Utc>),
+    // Campos com vários valores, como tags; não servem para ordenação
+    List(Vec<FieldValue>),
+}
+
+impl FieldValue {
+    // Valor de campo personalizado; datas guardadas como texto viram `Time` para comparar por período
+    pub fn from_json(value: Option<&serde_json::Value>) -> FieldValue {
+        match value {
+            None | Some(serde_json::Value::Null) => FieldValue::Null,
+            Some(serde_json::Value::Bool(b)) => FieldValue::Bool(*b),
+            Some(serde_json::Value::Number(n)) => n.as_f64().map(FieldValue::Number).unwrap_or(FieldValue::Null),
+            Some(serde_json::Value::String(s)) => parse_time(s).map(FieldValue::Time).unwrap_or_else(|| FieldValue::Text(s.clone())),
+            Some(other) => FieldValue::Text(other.to_string()),
+        }
+    }
+
+    // Converte o valor bruto da query string para o mesmo tipo do campo
+    fn parse_like(&self, raw: &str) -> Option<FieldValue> {
+        match self {
+            FieldValue::Null | FieldValue::Text(_) | FieldValue::List(_) => Some(FieldValue::Text(raw.to_string())),
+            FieldValue::Bool(_) => raw.parse().ok().map(FieldValue::Bool),
+            FieldValue::Number(_) => raw.parse().ok().map(FieldValue::Number),
+            FieldValue::Time(_) => parse_time(raw).map(FieldValue::Time),
+        }
+    }
+
+    // Posição de cada variante quando os tipos diferem (um campo personalizado de texto
+    // pode ter valores que parecem datas); mantém a ordem total exigida por `sort_by` e pelo cursor
+    fn rank(&self) -> u8 {
+        match self {
+            FieldValue::Null => 0,
+            FieldValue::Bool(_) => 1,
+            FieldValue::Number(_) => 2,
+            FieldValue::Text(_) => 3,
+            FieldValue::Time(_) => 4,
+            FieldValue::List(_) => 5,
+        }
+    }
+
+    fn compare(&self, other: &FieldValue) -> Ordering {
+        match (self, other) {
+            (FieldValue::Bool(a), FieldValue::Bool(b)) => a.cmp(b),
+            (FieldValue::Number(a), FieldValue::Number(b)) => a.total_cmp(b),
+            (FieldValue::Text(a), FieldValue::Text(b)) => a.cmp(b),
+            (FieldValue::Time(a), FieldValue::Time(b)) => a.cmp(b),
+            (FieldValue::List(a), FieldValue::List(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| x.compare(y))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+fn parse_time(raw: &str) -> Option<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(raw) {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    In,
+}
+
+impl FilterOp {
+    fn parse(op: &str) -> Option<Self> {
+        match op {
+            "eq" => Some(FilterOp::Eq),
+            "ne" => Some(FilterOp::Ne),
+            "gt" => Some(FilterOp::Gt),
+            "gte" => Some(FilterOp::Gte),
+            "lt" => Some(FilterOp::Lt),
+            "lte" => Some(FilterOp::Lte),
+            "in" => Some(FilterOp::In),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub field: String,
+    pub op: FilterOp,
+    pub value: String,
+}
+
+impl Filter {
+    fn matches(&self, actual: &FieldValue) -> bool {
+        // Em listas, `ne` casa se nenhum valor for igual; os demais operadores, se algum valor casar
+        if let FieldValue::List(values) = actual {
+            if self.op == FilterOp::Ne {
+                let equal = Filter { op: FilterOp::Eq, ..self.clone() };
+                return !values.iter().any(|value| equal.matches(value));
+            }
+            return values.iter().any(|value| self.matches(value));
+        }
+
+        if self.op == FilterOp::In {
+            return self.value.split(',').any(|candidate| {
+                actual.parse_like(candidate.trim())
+                    .map(|expected| actual.compare(&expected) == Ordering::Equal)
+                    .unwrap_or(false)
+            });
+        }
+
+        let Some(expected) = actual.parse_like(&self.value) else {
+            return false;
+        };
+        let ordering = actual.compare(&expected);
+        // Campo vazio não entra em faixas (`gt`, `lt`...), como no SQL
+        if *actual == FieldValue::Null && !matches!(self.op, FilterOp::Eq | FilterOp::Ne) {
+            return false;
+        }
+
+        match self.op {
+            FilterOp::Eq => ordering == Ordering::Equal,
+            FilterOp::Ne => ordering != Ordering::Equal,
+            FilterOp::Gt => ordering == Ordering::Greater,
+            FilterOp::Gte => ordering != Ordering::Less,
+            FilterOp::Lt => ordering == Ordering::Less,
+            FilterOp::Lte => ordering != Ordering::Greater,
+            FilterOp::In => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SortKey {
+    pub field: String,
+    pub descending: bool,
+}
+
+// Recursos que podem ser listados com filtros, ordenação e cursor
+pub trait Listable {
+    const FILTERABLE: &'static [&'static str];
+    const SORTABLE: &'static [&'static str];
+    const DEFAULT_SORT: &'static str;
+    // Prefixos de campos dinâmicos (ex.: `custom.`) aceitos em filtros e ordenação
+    const DYNAMIC_PREFIXES: &'static [&'static str] = &[];
+
+    fn accepts_dynamic(field: &str) -> bool {
+        Self::DYNAMIC_PREFIXES.iter().any(|prefix| field.len() > prefix.len() && field.starts_with(prefix))
+    }
+
+    fn field_value(&self, field: &str) -> FieldValue;
+    fn cursor_id(&self) -> String;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    sort: Vec<SortKey>,
+    values: Vec<FieldValue>,
+    id: String,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(raw: &str) -> Result<Self, String> {
+        URL_SAFE_NO_PAD.decode(raw)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| "Invalid cursor".to_string())
+    }
+}
+
+#[derive(Debug)]
+pub struct ListQuery {
+    pub filters: Vec<Filter>,
+    pub sort: Vec<SortKey>,
+    pub limit: usize,
+    pub include_total: bool,
+    cursor: Option<Cursor>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PageMeta {
+    pub limit: usize,
+    pub count: usize,
+    pub has_more: bool,
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub meta: PageMeta,
+}
+
+impl ListQuery {
+    // Interpreta `?status=completed&created_at[gte]=...&sort=-amount&limit=50&cursor=...`
+    pub fn parse<T: Listable>(params: &[(String, String)]) -> Result<Self, String> {
+        let mut filters = Vec::new();
+        let mut sort_param = T::DEFAULT_SORT.to_string();
+        let mut limit = DEFAULT_LIMIT;
+        let mut cursor_param = None;
+        let mut include_total = false;
+
+        for (key, value) in params {
+            match key.as_str() {
+                "sort" => sort_param = value.clone(),
+                "limit" => {
+                    limit = value.parse::<usize>()
+                        .map_err(|_| format!("Invalid limit: {}", value))?
+                        .clamp(1, MAX_LIMIT);
+                }
+                "cursor" => cursor_param = Some(value.clone()),
+                "include_total" => include_total = value == "true" || value == "1",
+                _ => filters.push(Self::parse_filter::<T>(key, value)?),
+            }
+        }
+
+        let sort = Self::parse_sort::<T>(&sort_param)?;
+
+        let cursor = match cursor_param {
+            Some(raw) => {
+                let cursor = Cursor::decode(&raw)?;
+                if cursor.sort != sort {
+                    return Err("Cursor does not match the requested sort".to_string());
+                }
+                Some(cursor)
+            }
+            None => None,
+        };
+
+        Ok(ListQuery { filters, sort, limit, include_total, cursor })
+    }
+
+    fn parse_filter<T: Listable>(key: &str, value: &str) -> Result<Filter, String> {
+        let (field, op) = match key.split_once('[') {
+            Some((field, rest)) => {
+                let op = rest.strip_suffix(']')
+                    .and_then(FilterOp::parse)
+                    .ok_or_else(|| format!("Invalid filter operator: {}", key))?;
+                (field, op)
+            }
+            None => (key, FilterOp::Eq),
+        };
+
+        if RESERVED_PARAMS.contains(&field) || !(T::FILTERABLE.contains(&field) || T::accepts_dynamic(field)) {
+            return Err(format!("Field '{}' is not filterable", field));
+        }
+
+        Ok(Filter { field: field.to_string(), op, value: value.to_string() })
+    }
+
+    fn parse_sort<T: Listable>(raw: &str) -> Result<Vec<SortKey>, String> {
+        raw.split(',')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let (field, descending) = match part.strip_prefix('-') {
+                    Some(field) => (field, true),
+                    None => (part, false),
+                };
+                if !(T::SORTABLE.contains(&field) || T::accepts_dynamic(field)) {
+                    return Err(format!("Field '{}' is not sortable", field));
+                }
+                Ok(SortKey { field: field.to_string(), descending })
+            })
+            .collect()
+    }
+
+    fn compare<T: Listable>(&self, a: &T, b: &T) -> Ordering {
+        for key in &self.sort {
+            let ordering = a.field_value(&key.field).compare(&b.field_value(&key.field));
+            let ordering = if key.descending { ordering.reverse() } else { ordering };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        // Desempate pelo id garante paginação estável
+        a.cursor_id().cmp(&b.cursor_id())
+    }
+
+    fn is_after_cursor<T: Listable>(&self, item: &T, cursor: &Cursor) -> bool {
+        for (key, cursor_value) in self.sort.iter().zip(cursor.values.iter()) {
+            let ordering = item.field_value(&key.field).compare(cursor_value);
+            let ordering = if key.descending { ordering.reverse() } else { ordering };
+            if ordering != Ordering::Equal {
+                return ordering == Ordering::Greater;
+            }
+        }
+        item.cursor_id() > cursor.id
+    }
+
+    // Todos os itens filtrados e ordenados, sem paginação (exportações)
+    pub fn matching<'a, T: Listable>(&self, items: &'a [T]) -> Vec<&'a T> {
+        let mut matching: Vec<&T> = items
+            .iter()
+            .filter(|item| {
+                self.filters.iter().all(|filter| filter.matches(&item.field_value(&filter.field)))
+            })
+            .collect();
+        matching.sort_by(|a, b| self.compare(*a, *b));
+        matching
+    }
+
+    pub fn apply<T: Listable + Clone>(&self, items: &[T]) -> Page<T> {
+        let matching = self.matching(items);
+        let total = matching.len();
+
+        let mut remaining = matching
+            .into_iter()
+            .filter(|item| match &self.cursor {
+                Some(cursor) => self.is_after_cursor(*item, cursor),
+                None => true,
+            })
+            .peekable();
+
+        let data: Vec<T> = remaining.by_ref().take(self.limit).cloned().collect();
+        let has_more = remaining.peek().is_some();
+
+        let next_cursor = match data.last() {
+            Some(last) if has_more => Some(Cursor {
+                sort: self.sort.clone(),
+                values: self.sort.iter().map(|key| last.field_value(&key.field)).collect(),
+                id: last.cursor_id(),
+            }.encode()),
+            _ => None,
+        };
+
+        Page {
+            meta: PageMeta {
+                limit: self.limit,
+                count: data.len(),
+                has_more,
+                next_cursor,
+                total: if self.include_total { Some(total) } else { None },
+            },
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FieldValue;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn mixed_variants_have_a_total_order() {
+        let time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let values = [
+            FieldValue::Text("b".to_string()),
+            FieldValue::Time(time),
+            FieldValue::Null,
+            FieldValue::Number(2.0),
+            FieldValue::Text("a".to_string()),
+            FieldValue::Bool(true),
+            FieldValue::List(vec![FieldValue::Text("x".to_string())]),
+            FieldValue::Number(f64::NAN),
+        ];
+        for a in &values {
+            for b in &values {
+                assert_eq!(a.compare(b), b.compare(a).reverse());
+                for c in &values {
+                    if a.compare(b).is_le() && b.compare(c).is_le() {
+                        assert!(a.compare(c).is_le());
+                    }
+                }
+            }
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.compare(b));
+        assert_eq!(sorted[0], FieldValue::Null);
+        assert_eq!(sorted[4], FieldValue::Text("a".to_string()));
+        assert_eq!(sorted[6], FieldValue::Time(time));
+    }
+}
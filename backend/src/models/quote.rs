@@ -0,0 +1,190 @@
+use crate::models::money::{Currency, Money};
+use crate::models::query::{FieldValue, Listable};
+use crate::models::PaymentMethod;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    Lead,
+    Qualified,
+    Proposal,
+    Negotiation,
+    Won,
+    Lost,
+}
+
+impl PipelineStage {
+    pub const ALL: [PipelineStage; 6] = [
+        PipelineStage::Lead,
+        PipelineStage::Qualified,
+        PipelineStage::Proposal,
+        PipelineStage::Negotiation,
+        PipelineStage::Won,
+        PipelineStage::Lost,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineStage::Lead => "lead",
+            PipelineStage::Qualified => "qualified",
+            PipelineStage::Proposal => "proposal",
+            PipelineStage::Negotiation => "negotiation",
+            PipelineStage::Won => "won",
+            PipelineStage::Lost => "lost",
+        }
+    }
+
+    // Probabilidade padrão de fechamento, em percentual
+    pub fn default_probability(&self) -> Decimal {
+        match self {
+            PipelineStage::Lead => dec!(10),
+            PipelineStage::Qualified => dec!(25),
+            PipelineStage::Proposal => dec!(50),
+            PipelineStage::Negotiation => dec!(75),
+            PipelineStage::Won => dec!(100),
+            PipelineStage::Lost => Decimal::ZERO,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self, PipelineStage::Won | PipelineStage::Lost)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuoteItem {
+    pub product_id: Uuid,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+    pub discount: Decimal,
+    // Sem impostos; eles são calculados quando a cotação vira venda
+    pub line_total: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageChange {
+    pub stage: PipelineStage,
+    pub probability: Decimal,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quote {
+    pub id: Uuid,
+    pub number: String,
+    pub customer_id: Uuid,
+    // Vendedor responsável (id do usuário)
+    pub rep_id: u64,
+    pub items: Vec<QuoteItem>,
+    pub amount: Money,
+    pub stage: PipelineStage,
+    pub probability: Decimal,
+    pub stage_history: Vec<StageChange>,
+    pub expires_at: DateTime<Utc>,
+    pub notes: Option<String>,
+    pub lost_reason: Option<String>,
+    pub sale_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Quote {
+    pub fn currency(&self) -> &Currency {
+        &self.amount.currency
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        !self.stage.is_closed() && now >= self.expires_at
+    }
+
+    // Estágio e probabilidade vigentes em uma data, pelo histórico
+    pub fn stage_at(&self, at: DateTime<Utc>) -> Option<&StageChange> {
+        self.stage_history.iter().rev().find(|change| change.changed_at <= at)
+    }
+}
+
+impl Listable for Quote {
+    const FILTERABLE: &'static [&'static str] =
+        &["stage", "customer_id", "rep_id", "amount", "currency", "expires_at", "created_at"];
+    const SORTABLE: &'static [&'static str] = &["amount", "probability", "expires_at", "created_at"];
+    const DEFAULT_SORT: &'static str = "-created_at";
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        match field {
+            "stage" => FieldValue::Text(self.stage.as_str().to_string()),
+            "customer_id" => FieldValue::Text(self.customer_id.to_string()),
+            "rep_id" => FieldValue::Number(self.rep_id as f64),
+            "amount" => FieldValue::Number(self.amount.amount.to_f64().unwrap_or_default()),
+            "probability" => FieldValue::Number(self.probability.to_f64().unwrap_or_default()),
+            "currency" => FieldValue::Text(self.currency().to_string()),
+            "expires_at" => FieldValue::Time(self.expires_at),
+            "created_at" => FieldValue::Time(self.created_at),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteCreate {
+    pub customer_id: Uuid,
+    pub rep_id: u64,
+    pub items: Vec<QuoteItemCreate>,
+    pub currency: Option<Currency>,
+    // Validade em dias; padrão 30
+    pub valid_days: Option<i64>,
+    pub stage: Option<PipelineStage>,
+    pub probability: Option<Decimal>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteItemCreate {
+    pub product_id: Uuid,
+    pub quantity: i32,
+    // Preço negociado; quando omitido, usa o preço atual do produto
+    pub unit_price: Option<Decimal>,
+    pub discount: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteStageUpdate {
+    pub stage: PipelineStage,
+    // Quando omitida, usa a probabilidade padrão do estágio
+    pub probability: Option<Decimal>,
+    pub lost_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteConvert {
+    pub payment_method: PaymentMethod,
+    pub tax_region: Option<String>,
+    #[serde(default)]
+    pub coupon_codes: Vec<String>,
+    pub warehouse: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineBucket {
+    pub key: String,
+    pub count: usize,
+    pub value: Decimal,
+    pub weighted_value: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSnapshot {
+    pub date: DateTime<Utc>,
+    pub count: usize,
+    pub value: Decimal,
+    pub weighted_value: Decimal,
+}
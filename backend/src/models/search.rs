@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEntity {
+    Customer,
+    Product,
+    Sale,
+}
+
+impl SearchEntity {
+    pub const ALL: [SearchEntity; 3] = [SearchEntity::Customer, SearchEntity::Product, SearchEntity::Sale];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchEntity::Customer => "customer",
+            SearchEntity::Product => "product",
+            SearchEntity::Sale => "sale",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<SearchEntity> {
+        SearchEntity::ALL.into_iter().find(|entity| entity.as_str() == raw.trim().to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    #[serde(rename = "type")]
+    pub entity: SearchEntity,
+    pub id: Uuid,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub score: f64,
+    // Campos em que os termos foram encontrados
+    pub matched_fields: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub query: String,
+    pub total: usize,
+    pub results: Vec<SearchHit>,
+    // Momento da última atualização do índice
+    pub indexed_at: DateTime<Utc>,
+}
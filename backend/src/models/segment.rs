@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// Faixa de notas RFM (1 a 5), inclusiva
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ScoreRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+impl ScoreRange {
+    pub const fn new(min: u8, max: u8) -> Self {
+        ScoreRange { min, max }
+    }
+
+    pub fn contains(&self, score: u8) -> bool {
+        (self.min..=self.max).contains(&score)
+    }
+}
+
+impl Default for ScoreRange {
+    fn default() -> Self {
+        ScoreRange::new(1, 5)
+    }
+}
+
+// As definições são avaliadas em ordem; vale a primeira que casar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentDefinition {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub recency: ScoreRange,
+    #[serde(default)]
+    pub frequency: ScoreRange,
+    #[serde(default)]
+    pub monetary: ScoreRange,
+}
+
+impl SegmentDefinition {
+    pub fn matches(&self, r: u8, f: u8, m: u8) -> bool {
+        self.recency.contains(r) && self.frequency.contains(f) && self.monetary.contains(m)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerRfm {
+    pub customer_id: Uuid,
+    pub name: String,
+    pub last_purchase_at: DateTime<Utc>,
+    pub recency_days: i64,
+    pub frequency: usize,
+    // Receita líquida de reembolsos, na moeda base
+    pub monetary: Decimal,
+    pub r: u8,
+    pub f: u8,
+    pub m: u8,
+    pub segment: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentSummary {
+    pub segment: String,
+    pub customers: usize,
+    // Percentual dos clientes pontuados
+    pub share: Decimal,
+    pub revenue: Decimal,
+    pub average_revenue: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentTrendPoint {
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub scored_customers: usize,
+    pub segments: Vec<SegmentSummary>,
+}
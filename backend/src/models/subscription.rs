@@ -0,0 +1,157 @@
+use crate::models::money::{Currency, Money};
+use crate::models::PaymentMethod;
+use chrono::{DateTime, Months, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingInterval {
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl BillingInterval {
+    pub fn months(&self) -> u32 {
+        match self {
+            BillingInterval::Monthly => 1,
+            BillingInterval::Quarterly => 3,
+            BillingInterval::Yearly => 12,
+        }
+    }
+
+    pub fn next(&self, from: DateTime<Utc>) -> DateTime<Utc> {
+        from.checked_add_months(Months::new(self.months())).unwrap_or(from)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Trialing,
+    Active,
+    Canceled,
+}
+
+// O preço de catálogo é mensal; cada período cobra `unit_price * quantity * meses do intervalo`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub product_id: Uuid,
+    pub quantity: i32,
+    // Preço mensal travado na contratação ou na última troca de plano
+    pub unit_price: Money,
+    pub interval: BillingInterval,
+    pub payment_method: PaymentMethod,
+    pub status: SubscriptionStatus,
+    pub trial_ends_at: Option<DateTime<Utc>>,
+    pub started_at: DateTime<Utc>,
+    pub current_period_start: DateTime<Utc>,
+    // Data da próxima renovação
+    pub current_period_end: DateTime<Utc>,
+    pub cancel_at_period_end: bool,
+    pub canceled_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    // Crédito de downgrade, abatido na próxima renovação
+    pub credit: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Subscription {
+    pub fn period_amount(&self) -> Decimal {
+        self.unit_price.amount * Decimal::from(self.quantity) * Decimal::from(self.interval.months())
+    }
+
+    // Receita recorrente mensal na moeda da assinatura; trial e canceladas não contam
+    pub fn mrr(&self) -> Decimal {
+        match self.status {
+            SubscriptionStatus::Active => self.unit_price.amount * Decimal::from(self.quantity),
+            _ => Decimal::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriptionCreate {
+    pub customer_id: Uuid,
+    pub product_id: Uuid,
+    pub quantity: Option<i32>,
+    pub interval: BillingInterval,
+    pub payment_method: PaymentMethod,
+    #[serde(default)]
+    pub trial_days: i64,
+}
+
+// Troca de plano ou de quantidade no meio do período
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriptionChangeRequest {
+    pub product_id: Option<Uuid>,
+    pub quantity: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubscriptionCancel {
+    // Padrão: mantém o acesso até o fim do período já pago
+    pub at_period_end: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MrrMovementKind {
+    New,
+    Expansion,
+    Contraction,
+    Churn,
+}
+
+// Variação de MRR na moeda base; a soma até uma data dá o MRR naquela data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MrrMovement {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub customer_id: Uuid,
+    pub kind: MrrMovementKind,
+    pub amount: Decimal,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionChange {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub from_product_id: Uuid,
+    pub to_product_id: Uuid,
+    pub from_quantity: i32,
+    pub to_quantity: i32,
+    // Positivo gera venda de ajuste; negativo vira crédito na assinatura
+    pub proration: Decimal,
+    pub sale_id: Option<Uuid>,
+    pub changed_at: DateTime<Utc>,
+}
+
+// Valores na moeda base; taxas em percentual sobre o MRR do início do período
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionMetrics {
+    pub currency: Currency,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub mrr: Decimal,
+    pub arr: Decimal,
+    pub active_subscriptions: usize,
+    pub trialing_subscriptions: usize,
+    pub paying_customers: usize,
+    // Receita média por cliente pagante
+    pub arpa: Decimal,
+    pub starting_mrr: Decimal,
+    pub new_mrr: Decimal,
+    pub expansion_mrr: Decimal,
+    pub contraction_mrr: Decimal,
+    pub churned_mrr: Decimal,
+    pub net_new_mrr: Decimal,
+    pub mrr_churn_rate: Decimal,
+    pub customer_churn_rate: Decimal,
+    pub net_revenue_retention: Decimal,
+}
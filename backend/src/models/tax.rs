@@ -0,0 +1,131 @@
+use crate::models::money::round_cents;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+// Regra da tabela de impostos; sem categoria ou região vale para todas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxRule {
+    pub code: String,
+    pub name: String,
+    // Alíquota em percentual (ex.: 18.00)
+    pub rate: Decimal,
+    pub category: Option<String>,
+    pub region: Option<String>,
+    // Imposto composto incide sobre a base mais os impostos aplicados antes dele
+    #[serde(default)]
+    pub compound: bool,
+    // Ordem de aplicação; menor primeiro
+    #[serde(default)]
+    pub priority: u32,
+}
+
+impl TaxRule {
+    fn matches(&self, category: &str, region: &str) -> bool {
+        self.category.as_deref().is_none_or(|c| c.eq_ignore_ascii_case(category))
+            && self.region.as_deref().is_none_or(|r| r.eq_ignore_ascii_case(region))
+    }
+
+    // Regra com categoria e região vence a que só tem uma delas, que vence a genérica
+    fn specificity(&self) -> u8 {
+        self.category.is_some() as u8 * 2 + self.region.is_some() as u8
+    }
+}
+
+// Imposto calculado para uma linha de venda
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineTax {
+    pub code: String,
+    pub name: String,
+    pub rate: Decimal,
+    pub compound: bool,
+    pub base: Decimal,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxTable {
+    pub default_region: String,
+    // Quando verdadeiro, os preços de catálogo já incluem os impostos
+    #[serde(default)]
+    pub prices_include_tax: bool,
+    pub rules: Vec<TaxRule>,
+}
+
+impl TaxTable {
+    pub fn empty(default_region: &str) -> Self {
+        TaxTable {
+            default_region: default_region.to_string(),
+            prices_include_tax: false,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read tax rules from {}: {}", path, e))?;
+        let table: TaxTable = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid tax rules file {}: {}", path, e))?;
+
+        if let Some(rule) = table.rules.iter().find(|r| r.rate < Decimal::ZERO || r.rate >= Decimal::ONE_HUNDRED) {
+            return Err(format!("Tax rate for '{}' must be between 0 and 100", rule.code));
+        }
+        Ok(table)
+    }
+
+    // Uma regra por código de imposto, a mais específica, na ordem de aplicação
+    fn rules_for(&self, category: &str, region: &str) -> Vec<&TaxRule> {
+        let mut selected: Vec<&TaxRule> = Vec::new();
+        for rule in self.rules.iter().filter(|r| r.matches(category, region)) {
+            match selected.iter_mut().find(|s| s.code == rule.code) {
+                Some(current) if rule.specificity() > current.specificity() => *current = rule,
+                Some(_) => {}
+                None => selected.push(rule),
+            }
+        }
+        selected.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.code.cmp(&b.code)));
+        selected
+    }
+
+    fn compute(rules: &[&TaxRule], base: Decimal, round: fn(Decimal) -> Decimal) -> Vec<LineTax> {
+        let mut taxes: Vec<LineTax> = Vec::with_capacity(rules.len());
+        for rule in rules {
+            let taxable = if rule.compound {
+                base + taxes.iter().map(|t| t.amount).sum::<Decimal>()
+            } else {
+                base
+            };
+            taxes.push(LineTax {
+                code: rule.code.clone(),
+                name: rule.name.clone(),
+                rate: rule.rate,
+                compound: rule.compound,
+                base: round(taxable),
+                amount: round(taxable * rule.rate / Decimal::ONE_HUNDRED),
+            });
+        }
+        taxes
+    }
+
+    // Calcula os impostos de um valor já com desconto. Preço sem imposto: o valor é a base.
+    // Preço com imposto: a base é extraída do valor e o arredondamento vai para o último imposto.
+    pub fn calculate(&self, amount: Decimal, category: &str, region: &str, inclusive: bool) -> Vec<LineTax> {
+        let rules = self.rules_for(category, region);
+        if rules.is_empty() {
+            return Vec::new();
+        }
+        if !inclusive {
+            return Self::compute(&rules, amount, round_cents);
+        }
+
+        let multiplier: Decimal = Decimal::ONE
+            + Self::compute(&rules, Decimal::ONE, |d| d).iter().map(|t| t.amount).sum::<Decimal>();
+        let base = round_cents(amount / multiplier);
+        let mut taxes = Self::compute(&rules, base, round_cents);
+
+        let difference = amount - base - taxes.iter().map(|t| t.amount).sum::<Decimal>();
+        if let Some(last) = taxes.last_mut() {
+            last.amount += difference;
+        }
+        taxes
+    }
+}
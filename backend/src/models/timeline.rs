@@ -0,0 +1,76 @@
+use crate::models::money::Money;
+use crate::models::query::{FieldValue, Listable};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEventType {
+    Sale,
+    Refund,
+    Payment,
+    StatusChange,
+    Note,
+    Subscription,
+    Quote,
+    Merge,
+    Insight,
+}
+
+impl TimelineEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimelineEventType::Sale => "sale",
+            TimelineEventType::Refund => "refund",
+            TimelineEventType::Payment => "payment",
+            TimelineEventType::StatusChange => "status_change",
+            TimelineEventType::Note => "note",
+            TimelineEventType::Subscription => "subscription",
+            TimelineEventType::Quote => "quote",
+            TimelineEventType::Merge => "merge",
+            TimelineEventType::Insight => "insight",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    // Único no feed: tipo, registro de origem e, quando há vários eventos por registro, a ordem
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: TimelineEventType,
+    pub occurred_at: DateTime<Utc>,
+    pub title: String,
+    pub amount: Option<Money>,
+    // Registro de origem (venda, reembolso, nota...)
+    pub reference_id: Uuid,
+    pub details: serde_json::Value,
+}
+
+impl Listable for TimelineEvent {
+    const FILTERABLE: &'static [&'static str] = &["type", "occurred_at"];
+    const SORTABLE: &'static [&'static str] = &["occurred_at"];
+    const DEFAULT_SORT: &'static str = "-occurred_at";
+
+    fn field_value(&self, field: &str) -> FieldValue {
+        match field {
+            "type" => FieldValue::Text(self.kind.as_str().to_string()),
+            "occurred_at" => FieldValue::Time(self.occurred_at),
+            _ => FieldValue::Null,
+        }
+    }
+
+    fn cursor_id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerStatusChange {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub from: String,
+    pub to: String,
+    pub changed_at: DateTime<Utc>,
+}
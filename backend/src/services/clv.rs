@@ -0,0 +1,336 @@
+use crate::models::clv::{BgNbdParams, GammaGammaParams};
+use chrono::{DateTime, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+use uuid::Uuid;
+
+const SECONDS_PER_WEEK: f64 = 7.0 * 24.0 * 3600.0;
+// Regularização leve (nos log-parâmetros) para bases pequenas não divergirem
+const PENALIZER: f64 = 0.01;
+const MAX_ITERATIONS: usize = 1000;
+// Abaixo disso o Gamma-Gamma não é ajustado
+const MIN_REPEAT_CUSTOMERS: usize = 3;
+// Semente fixa: a mesma base gera sempre os mesmos intervalos
+const BOOTSTRAP_SEED: u64 = 42;
+
+// Histórico de compras resumido, com tempos em semanas
+#[derive(Debug, Clone)]
+pub struct PurchaseHistory {
+    pub customer_id: Uuid,
+    // Compras repetidas (total menos a primeira)
+    pub frequency: f64,
+    // Da primeira à última compra
+    pub recency: f64,
+    // Da primeira compra até hoje
+    pub age: f64,
+    // Valor médio das compras repetidas; sem elas, o da primeira compra
+    pub monetary: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClvFit {
+    pub bg_nbd: BgNbdParams,
+    pub gamma_gamma: Option<GammaGammaParams>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClvPrediction {
+    pub expected_purchases: f64,
+    pub expected_order_value: f64,
+    pub probability_alive: f64,
+    pub value: f64,
+}
+
+// Transações (cliente, data, valor) resumidas por cliente
+pub fn histories(transactions: &[(Uuid, DateTime<Utc>, f64)], now: DateTime<Utc>) -> Vec<PurchaseHistory> {
+    let mut by_customer: BTreeMap<Uuid, Vec<(DateTime<Utc>, f64)>> = BTreeMap::new();
+    for (customer_id, at, value) in transactions {
+        by_customer.entry(*customer_id).or_default().push((*at, *value));
+    }
+
+    let weeks = |from: DateTime<Utc>, to: DateTime<Utc>| ((to - from).num_seconds() as f64 / SECONDS_PER_WEEK).max(0.0);
+    by_customer
+        .into_iter()
+        .map(|(customer_id, mut purchases)| {
+            purchases.sort_by_key(|(at, _)| *at);
+            let (first, first_value) = purchases[0];
+            let last = purchases[purchases.len() - 1].0;
+            let repeats = &purchases[1..];
+            let monetary = if repeats.is_empty() {
+                first_value
+            } else {
+                repeats.iter().map(|(_, value)| value).sum::<f64>() / repeats.len() as f64
+            };
+            PurchaseHistory {
+                customer_id,
+                frequency: repeats.len() as f64,
+                recency: weeks(first, last),
+                age: weeks(first, now),
+                monetary,
+            }
+        })
+        .collect()
+}
+
+// Aproximação de Lanczos para ln Γ(x)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_81,
+        676.520_368_121_885,
+        -1_259.139_216_722_403,
+        771.323_428_777_653,
+        -176.615_029_162_141,
+        12.507_343_278_687,
+        -0.138_571_095_265_72,
+        9.984_369_578_019_57e-6,
+        1.505_632_735_149_31e-7,
+    ];
+    if x < 0.5 {
+        return (PI / (PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let sum = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64));
+    let t = x + 7.5;
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// Função hipergeométrica 2F1 pela série, para |z| < 1
+fn hyp2f1(a: f64, b: f64, c: f64, z: f64) -> f64 {
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for k in 0..10_000 {
+        let k = k as f64;
+        term *= (a + k) * (b + k) / ((c + k) * (k + 1.0)) * z;
+        sum += term;
+        if term.abs() < 1e-12 * sum.abs() {
+            break;
+        }
+    }
+    sum
+}
+
+fn log_sum_exp(x: f64, y: f64) -> f64 {
+    let max = x.max(y);
+    max + ((x - max).exp() + (y - max).exp()).ln()
+}
+
+// Nelder-Mead sem derivadas; devolve o melhor ponto encontrado
+fn minimize(f: impl Fn(&[f64]) -> f64, start: &[f64]) -> Vec<f64> {
+    let eval = |point: &[f64]| {
+        let value = f(point);
+        if value.is_finite() { value } else { f64::INFINITY }
+    };
+    let n = start.len();
+    let mut simplex: Vec<Vec<f64>> = vec![start.to_vec()];
+    for i in 0..n {
+        let mut point = start.to_vec();
+        point[i] += 0.5;
+        simplex.push(point);
+    }
+    let mut values: Vec<f64> = simplex.iter().map(|p| eval(p)).collect();
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut order: Vec<usize> = (0..=n).collect();
+        order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+        simplex = order.iter().map(|i| simplex[*i].clone()).collect();
+        values = order.iter().map(|i| values[*i]).collect();
+        if (values[n] - values[0]).abs() < 1e-10 {
+            break;
+        }
+
+        let centroid: Vec<f64> = (0..n)
+            .map(|j| simplex[..n].iter().map(|p| p[j]).sum::<f64>() / n as f64)
+            .collect();
+        let worst = simplex[n].clone();
+        let toward = |coefficient: f64| -> Vec<f64> {
+            centroid.iter().zip(&worst).map(|(c, w)| c + coefficient * (c - w)).collect()
+        };
+
+        let reflected = toward(1.0);
+        let reflected_value = eval(&reflected);
+        if reflected_value < values[0] {
+            let expanded = toward(2.0);
+            let expanded_value = eval(&expanded);
+            if expanded_value < reflected_value {
+                (simplex[n], values[n]) = (expanded, expanded_value);
+            } else {
+                (simplex[n], values[n]) = (reflected, reflected_value);
+            }
+        } else if reflected_value < values[n - 1] {
+            (simplex[n], values[n]) = (reflected, reflected_value);
+        } else {
+            let contracted = if reflected_value < values[n] { toward(0.5) } else { toward(-0.5) };
+            let contracted_value = eval(&contracted);
+            if contracted_value < values[n].min(reflected_value) {
+                (simplex[n], values[n]) = (contracted, contracted_value);
+            } else {
+                // Encolhe o simplex em direção ao melhor ponto
+                let best = simplex[0].clone();
+                for i in 1..=n {
+                    simplex[i] = best.iter().zip(&simplex[i]).map(|(b, p)| b + 0.5 * (p - b)).collect();
+                    values[i] = eval(&simplex[i]);
+                }
+            }
+        }
+    }
+
+    let best = (0..=n).min_by(|a, b| values[*a].total_cmp(&values[*b])).unwrap_or(0);
+    simplex.swap_remove(best)
+}
+
+fn penalty(log_params: &[f64]) -> f64 {
+    PENALIZER * log_params.iter().map(|p| p * p).sum::<f64>()
+}
+
+fn bg_nbd_log_likelihood(params: &BgNbdParams, history: &PurchaseHistory) -> f64 {
+    let BgNbdParams { r, alpha, a, b } = *params;
+    let x = history.frequency;
+    let a1 = ln_gamma(r + x) - ln_gamma(r) + r * alpha.ln();
+    let a2 = ln_gamma(a + b) + ln_gamma(b + x) - ln_gamma(b) - ln_gamma(a + b + x);
+    let a3 = -(r + x) * (alpha + history.age).ln();
+    if x > 0.0 {
+        let a4 = a.ln() - (b + x - 1.0).ln() - (r + x) * (alpha + history.recency).ln();
+        a1 + a2 + log_sum_exp(a3, a4)
+    } else {
+        a1 + a2 + a3
+    }
+}
+
+fn gamma_gamma_log_likelihood(params: &GammaGammaParams, history: &PurchaseHistory) -> f64 {
+    let GammaGammaParams { p, q, gamma } = *params;
+    let (x, m) = (history.frequency, history.monetary);
+    ln_gamma(p * x + q) - ln_gamma(p * x) - ln_gamma(q) + q * gamma.ln() + (p * x - 1.0) * m.ln() + p * x * x.ln()
+        - (p * x + q) * (gamma + x * m).ln()
+}
+
+fn fit_bg_nbd(histories: &[PurchaseHistory], start: Option<BgNbdParams>) -> Option<BgNbdParams> {
+    if histories.is_empty() {
+        return None;
+    }
+    let start = start.map_or(vec![0.0; 4], |s| vec![s.r.ln(), s.alpha.ln(), s.a.ln(), s.b.ln()]);
+    let objective = |log_params: &[f64]| {
+        let params = BgNbdParams {
+            r: log_params[0].exp(),
+            alpha: log_params[1].exp(),
+            a: log_params[2].exp(),
+            b: log_params[3].exp(),
+        };
+        let log_likelihood: f64 = histories.iter().map(|h| bg_nbd_log_likelihood(&params, h)).sum();
+        -log_likelihood / histories.len() as f64 + penalty(log_params)
+    };
+    let best = minimize(objective, &start);
+    Some(BgNbdParams { r: best[0].exp(), alpha: best[1].exp(), a: best[2].exp(), b: best[3].exp() })
+}
+
+fn fit_gamma_gamma(histories: &[PurchaseHistory], start: Option<GammaGammaParams>) -> Option<GammaGammaParams> {
+    let repeat: Vec<&PurchaseHistory> = histories.iter().filter(|h| h.frequency > 0.0 && h.monetary > 0.0).collect();
+    if repeat.len() < MIN_REPEAT_CUSTOMERS {
+        return None;
+    }
+    // Começa perto do ticket médio para a escala de gamma não partir de 1
+    let mean = repeat.iter().map(|h| h.monetary).sum::<f64>() / repeat.len() as f64;
+    let start = start.map_or(vec![0.0, 1.0, mean.ln()], |s| vec![s.p.ln(), s.q.ln(), s.gamma.ln()]);
+    let objective = |log_params: &[f64]| {
+        let params = GammaGammaParams { p: log_params[0].exp(), q: log_params[1].exp(), gamma: log_params[2].exp() };
+        let log_likelihood: f64 = repeat.iter().map(|h| gamma_gamma_log_likelihood(&params, h)).sum();
+        -log_likelihood / repeat.len() as f64 + PENALIZER * (log_params[0].powi(2) + log_params[1].powi(2))
+    };
+    let best = minimize(objective, &start);
+    Some(GammaGammaParams { p: best[0].exp(), q: best[1].exp(), gamma: best[2].exp() })
+}
+
+pub fn fit(histories: &[PurchaseHistory], start: Option<&ClvFit>) -> Option<ClvFit> {
+    Some(ClvFit {
+        bg_nbd: fit_bg_nbd(histories, start.map(|s| s.bg_nbd))?,
+        gamma_gamma: fit_gamma_gamma(histories, start.and_then(|s| s.gamma_gamma)),
+    })
+}
+
+pub fn predict(fit: &ClvFit, history: &PurchaseHistory, horizon_weeks: f64) -> ClvPrediction {
+    let BgNbdParams { r, alpha, b, .. } = fit.bg_nbd;
+    // A forma fechada exige a > 1
+    let a = fit.bg_nbd.a.max(1.01);
+    let x = history.frequency;
+    let t = horizon_weeks;
+
+    let churn_odds = if x > 0.0 {
+        a / (b + x - 1.0) * ((alpha + history.age) / (alpha + history.recency)).powf(r + x)
+    } else {
+        0.0
+    };
+    let ratio = ((alpha + history.age) / (alpha + history.age + t)).powf(r + x);
+    let z = t / (alpha + history.age + t);
+    let expected_purchases = ((a + b + x - 1.0) / (a - 1.0)
+        * (1.0 - ratio * hyp2f1(r + x, b + x, a + b + x - 1.0, z)))
+        / (1.0 + churn_odds);
+
+    let expected_order_value = match fit.gamma_gamma {
+        Some(GammaGammaParams { p, q, gamma }) => {
+            let q = q.max(1.01);
+            if x > 0.0 {
+                p * (gamma + x * history.monetary) / (p * x + q - 1.0)
+            } else {
+                p * gamma / (q - 1.0)
+            }
+        }
+        None => history.monetary,
+    }
+    .max(0.0);
+    let expected_purchases = expected_purchases.max(0.0);
+
+    ClvPrediction {
+        expected_purchases,
+        expected_order_value,
+        probability_alive: 1.0 / (1.0 + churn_odds),
+        value: expected_purchases * expected_order_value,
+    }
+}
+
+// Intervalo por bootstrap: reamostra clientes, reajusta e recalcula o valor de cada um
+pub fn bootstrap_bounds(
+    histories: &[PurchaseHistory],
+    fit: &ClvFit,
+    horizon_weeks: f64,
+    samples: usize,
+    confidence: u32,
+) -> HashMap<Uuid, (f64, f64)> {
+    let mut rng = StdRng::seed_from_u64(BOOTSTRAP_SEED);
+    let mut values: Vec<Vec<f64>> = vec![Vec::with_capacity(samples); histories.len()];
+    for _ in 0..samples {
+        let sample: Vec<PurchaseHistory> = (0..histories.len())
+            .map(|_| histories[rng.gen_range(0..histories.len())].clone())
+            .collect();
+        let Some(sample_fit) = self::fit(&sample, Some(fit)) else {
+            continue;
+        };
+        for (i, history) in histories.iter().enumerate() {
+            let value = predict(&sample_fit, history, horizon_weeks).value;
+            if value.is_finite() {
+                values[i].push(value);
+            }
+        }
+    }
+
+    let tail = (1.0 - f64::from(confidence) / 100.0) / 2.0;
+    histories
+        .iter()
+        .zip(values.iter_mut())
+        .map(|(history, values)| {
+            let point = predict(fit, history, horizon_weeks).value;
+            if values.is_empty() {
+                return (history.customer_id, (point, point));
+            }
+            values.sort_by(|a, b| a.total_cmp(b));
+            let last = (values.len() - 1) as f64;
+            let lower = values[(tail * last).floor() as usize];
+            let upper = values[((1.0 - tail) * last).ceil() as usize];
+            (history.customer_id, (lower.min(point), upper.max(point)))
+        })
+        .collect()
+}
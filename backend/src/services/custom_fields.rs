@@ -0,0 +1,136 @@
+use crate::models::custom_field::{CustomFieldDefinition, CustomFieldDefinitionCreate, CustomFieldEntity, CustomFieldType};
+use chrono::NaiveDate;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+const MAX_TEXT_LENGTH: usize = 500;
+const MAX_TAG_LENGTH: usize = 50;
+const MAX_TAGS: usize = 30;
+
+// Chaves em snake_case: entram na URL dos filtros e no cabeçalho das exportações
+pub fn validate_definition(
+    req: &CustomFieldDefinitionCreate,
+    existing: &[CustomFieldDefinition],
+) -> Result<(String, Vec<String>), String> {
+    let key = req.key.trim().to_lowercase();
+    let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+        && key.len() <= 40
+        && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid_key {
+        return Err("Key must start with a letter and contain only lowercase letters, digits and underscores (max 40)".to_string());
+    }
+    if existing.iter().any(|d| d.entity == req.entity && d.key == key) {
+        return Err(format!("Custom field {} already exists for {}", key, req.entity.as_str()));
+    }
+    if req.label.trim().is_empty() {
+        return Err("Label is required".to_string());
+    }
+
+    let mut options: Vec<String> = Vec::new();
+    for option in req.options.iter().map(|o| o.trim()).filter(|o| !o.is_empty()) {
+        if !options.iter().any(|o| o == option) {
+            options.push(option.to_string());
+        }
+    }
+    match req.field_type {
+        CustomFieldType::Enum if options.is_empty() => return Err("Enum fields need at least one option".to_string()),
+        CustomFieldType::Enum => {}
+        _ if !options.is_empty() => return Err("Options are only allowed on enum fields".to_string()),
+        _ => {}
+    }
+    Ok((key, options))
+}
+
+fn validate_value(definition: &CustomFieldDefinition, value: &Value) -> Result<Value, String> {
+    let key = &definition.key;
+    match (definition.field_type, value) {
+        (CustomFieldType::Text, Value::String(text)) => {
+            let text = text.trim();
+            if text.chars().count() > MAX_TEXT_LENGTH {
+                return Err(format!("Custom field {} exceeds {} characters", key, MAX_TEXT_LENGTH));
+            }
+            Ok(Value::String(text.to_string()))
+        }
+        (CustomFieldType::Number, Value::Number(_)) => Ok(value.clone()),
+        (CustomFieldType::Date, Value::String(text)) => NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+            .map(|date| Value::String(date.to_string()))
+            .map_err(|_| format!("Custom field {} must be a date (YYYY-MM-DD)", key)),
+        (CustomFieldType::Enum, Value::String(text)) => definition
+            .options
+            .iter()
+            .find(|o| o.eq_ignore_ascii_case(text.trim()))
+            .map(|o| Value::String(o.clone()))
+            .ok_or_else(|| format!("Custom field {} must be one of {:?}", key, definition.options)),
+        (CustomFieldType::Boolean, Value::Bool(_)) => Ok(value.clone()),
+        (field_type, _) => Err(format!("Custom field {} must be a {}", key, type_name(field_type))),
+    }
+}
+
+fn type_name(field_type: CustomFieldType) -> &'static str {
+    match field_type {
+        CustomFieldType::Text => "string",
+        CustomFieldType::Number => "number",
+        CustomFieldType::Date => "date",
+        CustomFieldType::Enum => "string",
+        CustomFieldType::Boolean => "boolean",
+    }
+}
+
+// Aplica `changes` sobre os valores atuais e confere tudo contra o esquema da entidade
+pub fn apply_values(
+    definitions: &[CustomFieldDefinition],
+    entity: CustomFieldEntity,
+    current: &BTreeMap<String, Value>,
+    changes: &Map<String, Value>,
+) -> Result<BTreeMap<String, Value>, String> {
+    let schema: Vec<&CustomFieldDefinition> = definitions.iter().filter(|d| d.entity == entity).collect();
+    let mut values = current.clone();
+
+    for (key, value) in changes {
+        let Some(definition) = schema.iter().find(|d| d.key == *key) else {
+            return Err(format!("Unknown custom field {} for {}", key, entity.as_str()));
+        };
+        // Texto vazio também limpa o campo
+        if value.is_null() || value.as_str().is_some_and(|s| s.trim().is_empty()) {
+            values.remove(key);
+            continue;
+        }
+        values.insert(key.clone(), validate_value(definition, value)?);
+    }
+
+    let missing: Vec<&str> = schema
+        .iter()
+        .filter(|d| d.required && !values.contains_key(&d.key))
+        .map(|d| d.key.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing required custom fields: {}", missing.join(", ")));
+    }
+    Ok(values)
+}
+
+// Minúsculas, sem repetição; vírgula é separador do filtro `tags[in]`
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+        if tag.contains(',') || tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(format!("Invalid tag {}: no commas and at most {} characters", tag, MAX_TAG_LENGTH));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(format!("At most {} tags are allowed", MAX_TAGS));
+    }
+    Ok(normalized)
+}
+
+// Valor para uma célula de CSV
+pub fn export_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(other) => other.to_string(),
+    }
+}
@@ -0,0 +1,149 @@
+use crate::models::Customer;
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+
+// Pontuação mínima para o par entrar na fila de revisão
+pub const MIN_SCORE: u32 = 50;
+const NAME_THRESHOLD: f64 = 0.8;
+// Sufixos societários ignorados na comparação de nomes
+const COMPANY_SUFFIXES: [&str; 14] = [
+    "inc", "ltda", "ltd", "llc", "sa", "s/a", "me", "epp", "eireli", "corp", "corporation", "co", "company", "gmbh",
+];
+
+// Minúsculas, sem "+tag" e, no Gmail, sem pontos no usuário
+pub fn normalize_email(email: &str) -> String {
+    let email = email.trim().to_lowercase();
+    let Some((local, domain)) = email.split_once('@') else {
+        return email;
+    };
+    let local = local.split('+').next().unwrap_or(local);
+    let domain = if domain == "googlemail.com" { "gmail.com" } else { domain };
+    let local = if domain == "gmail.com" { local.replace('.', "") } else { local.to_string() };
+    format!("{}@{}", local, domain)
+}
+
+// Só os dígitos, sem o prefixo internacional "00"
+pub fn canonical_phone(phone: &str) -> String {
+    let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+    digits.strip_prefix("00").map(str::to_string).unwrap_or(digits)
+}
+
+// Mesmo número com ou sem código de país/área: um termina com o outro e há ao menos 8 dígitos em comum
+fn same_phone(a: &str, b: &str) -> bool {
+    let (a, b) = (canonical_phone(a), canonical_phone(b));
+    let (short, long) = if a.len() <= b.len() { (&a, &b) } else { (&b, &a) };
+    short.len() >= 8 && long.ends_with(short.as_str())
+}
+
+pub fn fold_accent(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        other => other,
+    }
+}
+
+pub fn normalize_name(name: &str) -> String {
+    let cleaned: String = name
+        .to_lowercase()
+        .chars()
+        .map(fold_accent)
+        .map(|c| if c.is_alphanumeric() || c == '/' { c } else { ' ' })
+        .collect();
+    cleaned
+        .split_whitespace()
+        .filter(|token| !COMPANY_SUFFIXES.contains(token))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matches = vec![false; a.len()];
+    let mut b_matches = vec![false; b.len()];
+    let mut matches = 0.0;
+    for (i, ca) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        for j in start..end {
+            if !b_matches[j] && b[j] == *ca {
+                a_matches[i] = true;
+                b_matches[j] = true;
+                matches += 1.0;
+                break;
+            }
+        }
+    }
+    if matches == 0.0 {
+        return 0.0;
+    }
+
+    let a_matched = a.iter().zip(&a_matches).filter(|(_, m)| **m).map(|(c, _)| c);
+    let b_matched = b.iter().zip(&b_matches).filter(|(_, m)| **m).map(|(c, _)| c);
+    let transpositions = a_matched.zip(b_matched).filter(|(x, y)| x != y).count() as f64 / 2.0;
+    (matches / a.len() as f64 + matches / b.len() as f64 + (matches - transpositions) / matches) / 3.0
+}
+
+fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let similarity = jaro(&a, &b);
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count() as f64;
+    similarity + prefix * 0.1 * (1.0 - similarity)
+}
+
+// Maior entre Jaro-Winkler do nome inteiro e sobreposição de palavras (ordem diferente)
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_name(a), normalize_name(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let tokens_a: HashSet<&str> = a.split_whitespace().collect();
+    let tokens_b: HashSet<&str> = b.split_whitespace().collect();
+    let overlap = tokens_a.intersection(&tokens_b).count() as f64 / tokens_a.union(&tokens_b).count() as f64;
+    jaro_winkler(&a, &b).max(overlap)
+}
+
+// Pontuação de 0 a 100 e os motivos; None abaixo do mínimo
+pub fn score(a: &Customer, b: &Customer) -> Option<(Decimal, Vec<String>)> {
+    let mut points = 0.0;
+    let mut reasons = Vec::new();
+
+    if let (Some(x), Some(y)) = (&a.tax_id, &b.tax_id) {
+        if x == y {
+            points += 100.0;
+            reasons.push("same tax id".to_string());
+        }
+    }
+    if normalize_email(&a.email) == normalize_email(&b.email) {
+        points += 60.0;
+        reasons.push("same email".to_string());
+    }
+    if same_phone(&a.phone, &b.phone) {
+        points += 30.0;
+        reasons.push("same phone".to_string());
+    }
+    let similarity = name_similarity(&a.name, &b.name);
+    if similarity >= NAME_THRESHOLD {
+        points += 35.0 * similarity;
+        reasons.push(format!("similar name ({:.0}%)", similarity * 100.0));
+    }
+    let company = normalize_name(&a.company);
+    if !company.is_empty() && company == normalize_name(&b.company) {
+        points += 20.0;
+        reasons.push("same company".to_string());
+    }
+
+    let points = points.min(100.0).round() as u32;
+    (points >= MIN_SCORE).then(|| (Decimal::from(points), reasons))
+}
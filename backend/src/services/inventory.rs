@@ -0,0 +1,446 @@
+use crate::models::inventory::{
+    LocationDemand, LocationStock, ReorderRecommendation, ReservationStatus, StockAllocation, StockMovement,
+    StockMovementKind, StockReservation, TransferSuggestion, Warehouse, DEFAULT_LOCATION,
+};
+use crate::models::money::{round_cents, Money};
+use crate::models::Product;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+// Razão de estoque: o saldo é sempre a soma dos lançamentos, e o reservado a soma das reservas ativas.
+// Os depósitos ficam junto, sob a mesma trava, porque a escolha do depósito e a reserva são uma operação só
+#[derive(Debug, Default)]
+pub struct StockLedger {
+    pub warehouses: Vec<Warehouse>,
+    pub movements: Vec<StockMovement>,
+    pub reservations: Vec<StockReservation>,
+}
+
+impl StockLedger {
+    pub fn warehouse(&self, code: &str) -> Option<&Warehouse> {
+        self.warehouses.iter().find(|w| w.code == code)
+    }
+
+    pub fn default_location(&self) -> String {
+        self.warehouses
+            .iter()
+            .find(|w| w.is_default)
+            .map(|w| w.code.clone())
+            .unwrap_or_else(|| DEFAULT_LOCATION.to_string())
+    }
+
+    // Código de um depósito cadastrado; para receber mercadoria ou atender vendas ele precisa estar ativo
+    pub fn check_location(&self, raw: &str, receiving: bool) -> Result<String, String> {
+        let code = validate_location(raw)?;
+        match self.warehouse(&code) {
+            None => Err(format!("Unknown warehouse: {}", code)),
+            Some(warehouse) if receiving && !warehouse.is_active => Err(format!("Warehouse {} is inactive", code)),
+            Some(_) => Ok(code),
+        }
+    }
+
+    pub fn on_hand(&self, product_id: Uuid, location: Option<&str>) -> i32 {
+        self.movements
+            .iter()
+            .filter(|m| m.product_id == product_id && location.is_none_or(|l| m.location == l))
+            .map(|m| m.quantity)
+            .sum()
+    }
+
+    pub fn reserved(&self, product_id: Uuid, location: Option<&str>) -> i32 {
+        self.reservations
+            .iter()
+            .filter(|r| r.product_id == product_id && r.status == ReservationStatus::Active)
+            .filter(|r| location.is_none_or(|l| r.location == l))
+            .map(|r| r.quantity)
+            .sum()
+    }
+
+    pub fn available(&self, product_id: Uuid, location: Option<&str>) -> i32 {
+        self.on_hand(product_id, location) - self.reserved(product_id, location)
+    }
+
+    // Saldos por local, em ordem alfabética; locais zerados e sem reserva ficam de fora
+    pub fn locations(&self, product_id: Uuid) -> Vec<LocationStock> {
+        let mut locations: BTreeMap<&str, (i32, i32)> = BTreeMap::new();
+        for movement in self.movements.iter().filter(|m| m.product_id == product_id) {
+            locations.entry(movement.location.as_str()).or_default().0 += movement.quantity;
+        }
+        for reservation in self.reservations.iter().filter(|r| r.product_id == product_id && r.status == ReservationStatus::Active) {
+            locations.entry(reservation.location.as_str()).or_default().1 += reservation.quantity;
+        }
+        locations
+            .into_iter()
+            .filter(|(_, (on_hand, reserved))| *on_hand != 0 || *reserved != 0)
+            .map(|(location, (on_hand, reserved))| LocationStock {
+                location: location.to_string(),
+                on_hand,
+                reserved,
+                available: on_hand - reserved,
+            })
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn record(
+        &mut self,
+        product_id: Uuid,
+        kind: StockMovementKind,
+        location: &str,
+        quantity: i32,
+        reference_id: Option<Uuid>,
+        reason: Option<String>,
+        created_by: Option<String>,
+        now: DateTime<Utc>,
+    ) -> StockMovement {
+        let movement = StockMovement {
+            id: Uuid::new_v4(),
+            product_id,
+            kind,
+            location: location.to_string(),
+            quantity,
+            reference_id,
+            reason,
+            created_by,
+            created_at: now,
+        };
+        self.movements.push(movement.clone());
+        movement
+    }
+
+    // Escolhe de onde sai cada linha. Com depósito informado, tudo sai dele; sem, vale o primeiro depósito
+    // ativo que atende a venda inteira (o padrão antes dos demais, depois o de maior saldo) e, se nenhum
+    // atende sozinho, cada produto é dividido entre os depósitos nessa mesma ordem. Em falta, devolve o produto sem saldo
+    pub fn allocate(&self, requested: &HashMap<Uuid, i32>, preferred: Option<&str>) -> Result<Vec<StockAllocation>, Uuid> {
+        let mut product_ids: Vec<Uuid> = requested.keys().copied().collect();
+        product_ids.sort();
+        let allocation = |product_id: Uuid, location: &str, quantity: i32| StockAllocation {
+            product_id,
+            location: location.to_string(),
+            quantity,
+        };
+
+        if let Some(location) = preferred {
+            if let Some(product_id) = product_ids.iter().find(|id| self.available(**id, Some(location)) < requested[*id]) {
+                return Err(*product_id);
+            }
+            return Ok(product_ids.iter().map(|id| allocation(*id, location, requested[id])).collect());
+        }
+
+        let mut candidates: Vec<(&Warehouse, i32)> = self
+            .warehouses
+            .iter()
+            .filter(|w| w.is_active)
+            .map(|w| (w, product_ids.iter().map(|id| self.available(*id, Some(&w.code)).max(0)).sum()))
+            .collect();
+        candidates.sort_by(|(a, a_available), (b, b_available)| {
+            b.is_default.cmp(&a.is_default).then(b_available.cmp(a_available)).then(a.code.cmp(&b.code))
+        });
+
+        let whole = candidates
+            .iter()
+            .find(|(w, _)| product_ids.iter().all(|id| self.available(*id, Some(&w.code)) >= requested[id]));
+        if let Some((warehouse, _)) = whole {
+            return Ok(product_ids.iter().map(|id| allocation(*id, &warehouse.code, requested[id])).collect());
+        }
+
+        let mut allocations = Vec::new();
+        for product_id in product_ids {
+            let mut remaining = requested[&product_id];
+            for (warehouse, _) in &candidates {
+                let quantity = remaining.min(self.available(product_id, Some(&warehouse.code)));
+                if quantity > 0 {
+                    allocations.push(allocation(product_id, &warehouse.code, quantity));
+                    remaining -= quantity;
+                }
+                if remaining == 0 {
+                    break;
+                }
+            }
+            if remaining > 0 {
+                return Err(product_id);
+            }
+        }
+        Ok(allocations)
+    }
+
+    pub fn reserve(&mut self, sale_id: Uuid, allocations: &[StockAllocation], now: DateTime<Utc>) {
+        for allocation in allocations {
+            self.reservations.push(StockReservation {
+                id: Uuid::new_v4(),
+                sale_id,
+                product_id: allocation.product_id,
+                location: allocation.location.clone(),
+                quantity: allocation.quantity,
+                status: ReservationStatus::Active,
+                created_at: now,
+                resolved_at: None,
+            });
+        }
+    }
+
+    // Reservas ativas da venda viram baixa no razão; chamadas repetidas não baixam de novo
+    pub fn commit(&mut self, sale_id: Uuid, now: DateTime<Utc>) -> Vec<Uuid> {
+        let mut committed = Vec::new();
+        for reservation in self.reservations.iter_mut().filter(|r| r.sale_id == sale_id && r.status == ReservationStatus::Active) {
+            reservation.status = ReservationStatus::Committed;
+            reservation.resolved_at = Some(now);
+            committed.push((reservation.product_id, reservation.location.clone(), reservation.quantity));
+        }
+        for (product_id, location, quantity) in &committed {
+            self.record(*product_id, StockMovementKind::Sale, location, -quantity, Some(sale_id), None, None, now);
+        }
+        committed.into_iter().map(|(product_id, _, _)| product_id).collect()
+    }
+
+    pub fn release(&mut self, sale_id: Uuid, now: DateTime<Utc>) -> Vec<Uuid> {
+        let mut released = Vec::new();
+        for reservation in self.reservations.iter_mut().filter(|r| r.sale_id == sale_id && r.status == ReservationStatus::Active) {
+            reservation.status = ReservationStatus::Released;
+            reservation.resolved_at = Some(now);
+            released.push(reservation.product_id);
+        }
+        released
+    }
+
+    // Quanto de um produto ainda pode voltar de uma venda, por local: o que saiu do razão pela baixa da venda
+    // menos as devoluções dos reembolsos anteriores. Venda sem baixa (importada ou não paga) não devolve nada
+    pub fn returnable(&self, sale_id: Uuid, refund_ids: &[Uuid], product_id: Uuid) -> Vec<(String, i32)> {
+        let mut locations: Vec<(String, i32)> = Vec::new();
+        for movement in self.movements.iter().filter(|m| m.product_id == product_id) {
+            let quantity = match (movement.kind, movement.reference_id) {
+                (StockMovementKind::Sale, Some(id)) if id == sale_id => -movement.quantity,
+                (StockMovementKind::RefundReturn, Some(id)) if refund_ids.contains(&id) => -movement.quantity,
+                _ => continue,
+            };
+            match locations.iter_mut().find(|(location, _)| *location == movement.location) {
+                Some((_, total)) => *total += quantity,
+                None => locations.push((movement.location.clone(), quantity)),
+            }
+        }
+        locations.retain(|(_, quantity)| *quantity > 0);
+        locations
+    }
+}
+
+// Códigos de local em minúsculas: letras, dígitos, '-' e '_'
+pub fn validate_location(raw: &str) -> Result<String, String> {
+    let location = raw.trim().to_lowercase();
+    if location.is_empty() || location.len() > 40 || !location.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')) {
+        return Err(format!("Invalid location code: {}", raw.trim()));
+    }
+    Ok(location)
+}
+
+// Transferências sugeridas: cobre a falta de um depósito com a sobra de outro. Devolve também
+// o que nenhuma transferência cobre, por depósito, para orientar onde entregar as compras
+pub fn rebalance(
+    ledger: &StockLedger,
+    products: &[Product],
+    demand: &HashMap<(Uuid, String), i32>,
+) -> (Vec<TransferSuggestion>, Vec<LocationDemand>) {
+    let mut transfers = Vec::new();
+    let mut uncovered = Vec::new();
+    let locations: Vec<&str> = ledger.warehouses.iter().filter(|w| w.is_active).map(|w| w.code.as_str()).collect();
+
+    for product in products {
+        // (depósito, disponível, demanda)
+        let positions: Vec<(&str, i32, i32)> = locations
+            .iter()
+            .map(|location| {
+                let forecast = demand.get(&(product.id, location.to_string())).copied().unwrap_or(0);
+                (*location, ledger.available(product.id, Some(location)), forecast)
+            })
+            .collect();
+        let mut shortfalls: Vec<(&str, i32, i32, i32)> = positions
+            .iter()
+            .filter(|(_, available, forecast)| forecast > available)
+            .map(|(location, available, forecast)| (*location, forecast - available, *available, *forecast))
+            .collect();
+        let mut surpluses: Vec<(&str, i32)> = positions
+            .iter()
+            .filter(|(_, available, forecast)| available > forecast)
+            .map(|(location, available, forecast)| (*location, available - forecast))
+            .collect();
+        shortfalls.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        surpluses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        for (location, missing, available, forecast) in shortfalls.iter_mut() {
+            for (source, spare) in surpluses.iter_mut().filter(|(_, spare)| *spare > 0) {
+                if *missing == 0 {
+                    break;
+                }
+                let quantity = (*missing).min(*spare);
+                transfers.push(TransferSuggestion {
+                    product_id: product.id,
+                    sku: product.sku.clone(),
+                    from_location: source.to_string(),
+                    to_location: location.to_string(),
+                    quantity,
+                    reason: format!(
+                        "{} has {} available for a demand of {}; {} has {} beyond its own demand",
+                        location, available, forecast, source, *spare
+                    ),
+                });
+                *missing -= quantity;
+                *spare -= quantity;
+            }
+            if *missing > 0 {
+                uncovered.push(LocationDemand {
+                    product_id: product.id,
+                    location: location.to_string(),
+                    quantity: *missing,
+                });
+            }
+        }
+    }
+    (transfers, uncovered)
+}
+
+// Padrões da política de reposição; o prazo e o nível de serviço podem mudar por produto
+#[derive(Debug, Clone, Copy)]
+pub struct ReorderParams {
+    pub lead_time_days: f64,
+    pub service_level: f64,
+    // Custo fixo de emitir um pedido de compra, na moeda base
+    pub ordering_cost: f64,
+    // Custo anual de manter uma unidade em estoque, como fração do custo unitário
+    pub holding_cost_rate: f64,
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).map(round_cents).unwrap_or_default()
+}
+
+// Quantil da normal padrão para o nível de serviço (Abramowitz-Stegun 26.2.23, erro < 4.5e-4)
+pub fn service_level_z(level: f64) -> f64 {
+    let t = (-2.0 * (1.0 - level).ln()).sqrt();
+    t - (2.515517 + 0.802853 * t + 0.010328 * t * t) / (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t)
+}
+
+// Vendas diárias do período (dias sem venda contam como zero): média e desvio padrão amostral
+pub fn demand_stats(daily: &[f64]) -> (f64, f64) {
+    if daily.len() < 2 {
+        return (daily.first().copied().unwrap_or(0.0), 0.0);
+    }
+    let n = daily.len() as f64;
+    let mean = daily.iter().sum::<f64>() / n;
+    let variance = daily.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance.sqrt())
+}
+
+// Estoque de segurança = z × desvio diário × √prazo; ponto de pedido = demanda no prazo + segurança;
+// lote econômico (EOQ) = √(2 × demanda anual × custo do pedido ÷ custo anual de manter uma unidade).
+// Pede quando o disponível chega ao ponto de pedido, o maior entre o EOQ e o que falta até o ponto.
+// `unit_cost` já vem na moeda base
+pub fn reorder_recommendation(
+    product: &Product,
+    daily: &[f64],
+    position: i32,
+    unit_cost: Option<Money>,
+    params: &ReorderParams,
+    location: &str,
+) -> ReorderRecommendation {
+    let (mean, std_dev) = demand_stats(daily);
+    let units_sold = daily.iter().sum::<f64>().round() as i32;
+    let lead_time = params.lead_time_days;
+    let z = service_level_z(params.service_level);
+    let mut reasons = Vec::new();
+
+    let mut recommendation = ReorderRecommendation {
+        product_id: product.id,
+        sku: product.sku.clone(),
+        name: product.name.clone(),
+        units_sold,
+        average_daily_demand: decimal(mean),
+        demand_std_dev: decimal(std_dev),
+        lead_time_days: decimal(lead_time),
+        service_level: decimal(params.service_level),
+        safety_stock: 0,
+        reorder_point: 0,
+        economic_order_quantity: None,
+        inventory_position: position,
+        order_quantity: 0,
+        location: location.to_string(),
+        order_cost: None,
+        unit_cost: unit_cost.clone(),
+        reasons: Vec::new(),
+    };
+
+    if units_sold <= 0 {
+        recommendation.reasons = vec![format!(
+            "No sales in the last {} days: no reorder point and no order until demand appears",
+            daily.len()
+        )];
+        return recommendation;
+    }
+
+    reasons.push(format!(
+        "Sold {} units in the last {} days: {:.2} per day on average, standard deviation {:.2}",
+        units_sold,
+        daily.len(),
+        mean,
+        std_dev
+    ));
+    let safety = z * std_dev * lead_time.sqrt();
+    let safety_stock = safety.ceil() as i32;
+    reasons.push(format!(
+        "Safety stock {} = z {:.3} ({}% service level) × {:.2} × √{} days of lead time",
+        safety_stock,
+        z,
+        decimal(params.service_level * 100.0).normalize(),
+        std_dev,
+        decimal(lead_time).normalize()
+    ));
+    let lead_time_demand = mean * lead_time;
+    let reorder_point = (lead_time_demand + safety).ceil() as i32;
+    reasons.push(format!(
+        "Reorder point {} = {:.2} units of demand during the lead time + {:.2} of safety stock",
+        reorder_point, lead_time_demand, safety
+    ));
+
+    let annual_demand = mean * 365.0;
+    let holding = unit_cost.as_ref().and_then(|cost| cost.amount.to_f64()).map(|cost| cost * params.holding_cost_rate);
+    let economic = match (&unit_cost, holding) {
+        (Some(cost), Some(holding)) if holding > 0.0 => {
+            let eoq = ((2.0 * annual_demand * params.ordering_cost / holding).sqrt().ceil() as i32).max(1);
+            reasons.push(format!(
+                "EOQ {} = √(2 × {:.0} units/year × {:.2} {} per order ÷ {:.2} {} to hold one unit for a year)",
+                eoq, annual_demand, params.ordering_cost, cost.currency, holding, cost.currency
+            ));
+            Some(eoq)
+        }
+        _ => None,
+    };
+    recommendation.safety_stock = safety_stock;
+    recommendation.reorder_point = reorder_point;
+    recommendation.economic_order_quantity = economic;
+
+    if position > reorder_point {
+        reasons.push(format!("{} available is above the reorder point of {}: no order needed", position, reorder_point));
+    } else {
+        let lot = match economic {
+            Some(eoq) => eoq,
+            None => {
+                let lot = (lead_time_demand.ceil() as i32).max(1);
+                reasons.push(format!(
+                    "No cost registered, so there is no EOQ: ordering {} units, the demand during the lead time",
+                    lot
+                ));
+                lot
+            }
+        };
+        let quantity = lot.max(reorder_point - position);
+        reasons.push(format!(
+            "{} available is at or below the reorder point of {}: order {} units for {}",
+            position, reorder_point, quantity, location
+        ));
+        recommendation.order_quantity = quantity;
+        recommendation.order_cost = unit_cost.map(|cost| Money::new(round_cents(cost.amount * Decimal::from(quantity)), cost.currency));
+    }
+    recommendation.reasons = reasons;
+    recommendation
+}
@@ -0,0 +1,13 @@
+pub mod clv;
+pub mod custom_fields;
+pub mod dedup;
+pub mod inventory;
+pub mod nfe;
+pub mod payments;
+pub mod promotions;
+pub mod pdf;
+pub mod privacy;
+pub mod rfm;
+pub mod search;
+pub mod template;
+pub mod xml;
@@ -0,0 +1,585 @@
+use crate::models::fiscal::{FiscalIssuer, NfeDocument, TaxId};
+use crate::models::money::round_cents;
+use crate::models::{Customer, PaymentMethod, Product, Sale, SaleItem};
+use crate::services::xml::{self, Content, Element, Particle, SimpleType};
+use chrono::{DateTime, FixedOffset, Utc};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+const NFE_NAMESPACE: &str = "http://www.portalfiscal.inf.br/nfe";
+const LAYOUT_VERSION: &str = "4.00";
+const HOMOLOGATION_NAME: &str = "NF-E EMITIDA EM AMBIENTE DE HOMOLOGACAO - SEM VALOR FISCAL";
+
+const STATES: [&str; 27] = [
+    "RO", "AC", "AM", "RR", "PA", "AP", "TO", "MA", "PI", "CE", "RN", "PB", "PE", "AL", "SE", "BA",
+    "MG", "ES", "RJ", "SP", "PR", "SC", "RS", "MS", "MT", "GO", "DF",
+];
+const STATE_CODES: [&str; 27] = [
+    "11", "12", "13", "14", "15", "16", "17", "21", "22", "23", "24", "25", "26", "27", "28", "29",
+    "31", "32", "33", "35", "41", "42", "43", "50", "51", "52", "53",
+];
+
+fn state_code(state: &str) -> Option<&'static str> {
+    STATES.iter().position(|s| *s == state).map(|i| STATE_CODES[i])
+}
+
+// Meios de pagamento do leiaute (tPag); 99 exige descrição em xPag
+fn payment_code(method: PaymentMethod) -> (&'static str, Option<&'static str>) {
+    match method {
+        PaymentMethod::Cash => ("01", None),
+        PaymentMethod::CreditCard => ("03", None),
+        PaymentMethod::DebitCard => ("04", None),
+        PaymentMethod::Boleto => ("15", None),
+        PaymentMethod::Pix => ("17", None),
+        PaymentMethod::BankTransfer => ("18", None),
+        PaymentMethod::Paypal => ("99", Some("PayPal")),
+        PaymentMethod::Other => ("99", Some("Outros")),
+    }
+}
+
+// Dígito da chave de acesso: módulo 11 com pesos 2..9 da direita para a esquerda
+fn access_key_digit(key: &str) -> u32 {
+    let sum: u32 = key.chars().rev()
+        .enumerate()
+        .map(|(i, c)| (c as u32 - '0' as u32) * (2 + (i as u32 % 8)))
+        .sum();
+    let remainder = sum % 11;
+    if remainder < 2 { 0 } else { 11 - remainder }
+}
+
+fn money(value: Decimal) -> String {
+    format!("{:.2}", round_cents(value))
+}
+
+fn is_cnpj(value: &str) -> bool {
+    TaxId::parse(value).is_ok_and(|id| matches!(id, TaxId::Cnpj(_)))
+}
+
+fn is_cpf(value: &str) -> bool {
+    TaxId::parse(value).is_ok_and(|id| matches!(id, TaxId::Cpf(_)))
+}
+
+fn is_number(value: &str) -> bool {
+    (1..=9).contains(&value.len()) && !value.starts_with('0') && value.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_series(value: &str) -> bool {
+    value == "0" || ((1..=3).contains(&value.len()) && !value.starts_with('0') && value.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_gtin(value: &str) -> bool {
+    value == "SEM GTIN" || (matches!(value.len(), 8 | 12 | 13 | 14) && value.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_outgoing_cfop(value: &str) -> bool {
+    value.len() == 4 && value.starts_with(['5', '6', '7']) && value.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_state_registration(value: &str) -> bool {
+    value == "ISENTO" || ((2..=14).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit()))
+}
+
+const fn el(name: &'static str, min: usize, max: usize, content: &'static Content) -> Particle {
+    Particle { name, min, max, attrs: &[], content }
+}
+
+const fn req(name: &'static str, content: &'static Content) -> Particle {
+    el(name, 1, 1, content)
+}
+
+const fn opt(name: &'static str, content: &'static Content) -> Particle {
+    el(name, 0, 1, content)
+}
+
+// Subconjunto do leiaute 4.00 (nfe_v4.00.xsd) usado pelas vendas da plataforma,
+// mantendo a ordem dos elementos e os padrões dos tipos simples do esquema oficial
+static DEC_1302: Content = Content::Simple(SimpleType::Decimal { integer: 13, min_fraction: 2, max_fraction: 2 });
+static DEC_1104: Content = Content::Simple(SimpleType::Decimal { integer: 11, min_fraction: 0, max_fraction: 4 });
+static DEC_1110: Content = Content::Simple(SimpleType::Decimal { integer: 11, min_fraction: 0, max_fraction: 10 });
+static DEC_0302: Content = Content::Simple(SimpleType::Decimal { integer: 3, min_fraction: 2, max_fraction: 4 });
+static TEXT_60: Content = Content::Simple(SimpleType::Text(2, 60));
+static TEXT_1_60: Content = Content::Simple(SimpleType::Text(1, 60));
+static TEXT_120: Content = Content::Simple(SimpleType::Text(1, 120));
+static TEXT_5000: Content = Content::Simple(SimpleType::Text(1, 5000));
+static UNIT: Content = Content::Simple(SimpleType::Text(1, 6));
+static DIGIT: Content = Content::Simple(SimpleType::Digits(1, 1));
+static STATE_CODE: Content = Content::Simple(SimpleType::Enum(&STATE_CODES));
+static STATE: Content = Content::Simple(SimpleType::Enum(&STATES));
+static CITY_CODE: Content = Content::Simple(SimpleType::Digits(7, 7));
+static POSTAL_CODE: Content = Content::Simple(SimpleType::Digits(8, 8));
+static PHONE: Content = Content::Simple(SimpleType::Digits(6, 14));
+static RANDOM_CODE: Content = Content::Simple(SimpleType::Digits(8, 8));
+static NCM: Content = Content::Simple(SimpleType::Digits(8, 8));
+static DATE_TIME: Content = Content::Simple(SimpleType::DateTimeOffset);
+static CNPJ: Content = Content::Simple(SimpleType::Custom(is_cnpj));
+static CPF: Content = Content::Simple(SimpleType::Custom(is_cpf));
+static NUMBER: Content = Content::Simple(SimpleType::Custom(is_number));
+static SERIES: Content = Content::Simple(SimpleType::Custom(is_series));
+static GTIN: Content = Content::Simple(SimpleType::Custom(is_gtin));
+static CFOP: Content = Content::Simple(SimpleType::Custom(is_outgoing_cfop));
+static STATE_REGISTRATION: Content = Content::Simple(SimpleType::Custom(is_state_registration));
+static MODEL: Content = Content::Simple(SimpleType::Enum(&["55"]));
+static COUNTRY: Content = Content::Simple(SimpleType::Enum(&["1058"]));
+static BOOL_FLAG: Content = Content::Simple(SimpleType::Enum(&["0", "1"]));
+static OPERATION_TYPE: Content = Content::Simple(SimpleType::Enum(&["0", "1"]));
+static DESTINATION: Content = Content::Simple(SimpleType::Enum(&["1", "2", "3"]));
+static PRINT_FORMAT: Content = Content::Simple(SimpleType::Enum(&["0", "1", "2", "3", "4", "5"]));
+static EMISSION_TYPE: Content = Content::Simple(SimpleType::Enum(&["1", "2", "3", "4", "5", "6", "7", "9"]));
+static ENVIRONMENT: Content = Content::Simple(SimpleType::Enum(&["1", "2"]));
+static PURPOSE: Content = Content::Simple(SimpleType::Enum(&["1", "2", "3", "4"]));
+static PRESENCE: Content = Content::Simple(SimpleType::Enum(&["0", "1", "2", "3", "4", "5", "9"]));
+static PROCESS: Content = Content::Simple(SimpleType::Enum(&["0", "1", "2", "3"]));
+static TAX_REGIME: Content = Content::Simple(SimpleType::Enum(&["1", "2", "3", "4"]));
+static IE_INDICATOR: Content = Content::Simple(SimpleType::Enum(&["1", "2", "9"]));
+static ORIGIN: Content = Content::Simple(SimpleType::Enum(&["0", "1", "2", "3", "4", "5", "6", "7", "8"]));
+static ICMS_CST: Content = Content::Simple(SimpleType::Enum(&["00"]));
+static ICMS_BASE_MODE: Content = Content::Simple(SimpleType::Enum(&["0", "1", "2", "3"]));
+static CSOSN: Content = Content::Simple(SimpleType::Enum(&["102", "103", "300", "400"]));
+static PIS_CST_RATE: Content = Content::Simple(SimpleType::Enum(&["01", "02"]));
+static PIS_CST_OTHER: Content = Content::Simple(SimpleType::Enum(&["49", "99"]));
+static FREIGHT: Content = Content::Simple(SimpleType::Enum(&["0", "1", "2", "3", "4", "9"]));
+static PAYMENT_TYPE: Content = Content::Simple(SimpleType::Enum(&[
+    "01", "02", "03", "04", "05", "10", "11", "12", "13", "15", "16", "17", "18", "19", "90", "99",
+]));
+
+static IDE: Content = Content::Sequence(&[
+    req("cUF", &STATE_CODE),
+    req("cNF", &RANDOM_CODE),
+    req("natOp", &TEXT_1_60),
+    req("mod", &MODEL),
+    req("serie", &SERIES),
+    req("nNF", &NUMBER),
+    req("dhEmi", &DATE_TIME),
+    req("tpNF", &OPERATION_TYPE),
+    req("idDest", &DESTINATION),
+    req("cMunFG", &CITY_CODE),
+    req("tpImp", &PRINT_FORMAT),
+    req("tpEmis", &EMISSION_TYPE),
+    req("cDV", &DIGIT),
+    req("tpAmb", &ENVIRONMENT),
+    req("finNFe", &PURPOSE),
+    req("indFinal", &BOOL_FLAG),
+    req("indPres", &PRESENCE),
+    req("procEmi", &PROCESS),
+    req("verProc", &TEXT_1_60),
+]);
+
+static ADDRESS: Content = Content::Sequence(&[
+    req("xLgr", &TEXT_60),
+    req("nro", &TEXT_1_60),
+    req("xBairro", &TEXT_60),
+    req("cMun", &CITY_CODE),
+    req("xMun", &TEXT_60),
+    req("UF", &STATE),
+    req("CEP", &POSTAL_CODE),
+    opt("cPais", &COUNTRY),
+    opt("xPais", &TEXT_1_60),
+    opt("fone", &PHONE),
+]);
+
+static EMIT: Content = Content::Sequence(&[
+    req("CNPJ", &CNPJ),
+    req("xNome", &TEXT_60),
+    opt("xFant", &TEXT_1_60),
+    req("enderEmit", &ADDRESS),
+    req("IE", &STATE_REGISTRATION),
+    req("CRT", &TAX_REGIME),
+]);
+
+// No esquema CNPJ e CPF são uma escolha; o gerador garante que só um é emitido
+static DEST: Content = Content::Sequence(&[
+    opt("CNPJ", &CNPJ),
+    opt("CPF", &CPF),
+    req("xNome", &TEXT_60),
+    req("indIEDest", &IE_INDICATOR),
+    opt("email", &TEXT_1_60),
+]);
+
+static PROD: Content = Content::Sequence(&[
+    req("cProd", &TEXT_1_60),
+    req("cEAN", &GTIN),
+    req("xProd", &TEXT_120),
+    req("NCM", &NCM),
+    req("CFOP", &CFOP),
+    req("uCom", &UNIT),
+    req("qCom", &DEC_1104),
+    req("vUnCom", &DEC_1110),
+    req("vProd", &DEC_1302),
+    req("cEANTrib", &GTIN),
+    req("uTrib", &UNIT),
+    req("qTrib", &DEC_1104),
+    req("vUnTrib", &DEC_1110),
+    opt("vDesc", &DEC_1302),
+    opt("vOutro", &DEC_1302),
+    req("indTot", &BOOL_FLAG),
+]);
+
+static ICMS00: Content = Content::Sequence(&[
+    req("orig", &ORIGIN),
+    req("CST", &ICMS_CST),
+    req("modBC", &ICMS_BASE_MODE),
+    req("vBC", &DEC_1302),
+    req("pICMS", &DEC_0302),
+    req("vICMS", &DEC_1302),
+]);
+
+static ICMSSN102: Content = Content::Sequence(&[
+    req("orig", &ORIGIN),
+    req("CSOSN", &CSOSN),
+]);
+
+static ICMS: Content = Content::Choice(&[req("ICMS00", &ICMS00), req("ICMSSN102", &ICMSSN102)]);
+
+static PIS_RATE: Content = Content::Sequence(&[
+    req("CST", &PIS_CST_RATE),
+    req("vBC", &DEC_1302),
+    req("pPIS", &DEC_0302),
+    req("vPIS", &DEC_1302),
+]);
+
+static PIS_OTHER: Content = Content::Sequence(&[
+    req("CST", &PIS_CST_OTHER),
+    req("vBC", &DEC_1302),
+    req("pPIS", &DEC_0302),
+    req("vPIS", &DEC_1302),
+]);
+
+static PIS: Content = Content::Choice(&[req("PISAliq", &PIS_RATE), req("PISOutr", &PIS_OTHER)]);
+
+static COFINS_RATE: Content = Content::Sequence(&[
+    req("CST", &PIS_CST_RATE),
+    req("vBC", &DEC_1302),
+    req("pCOFINS", &DEC_0302),
+    req("vCOFINS", &DEC_1302),
+]);
+
+static COFINS_OTHER: Content = Content::Sequence(&[
+    req("CST", &PIS_CST_OTHER),
+    req("vBC", &DEC_1302),
+    req("pCOFINS", &DEC_0302),
+    req("vCOFINS", &DEC_1302),
+]);
+
+static COFINS: Content = Content::Choice(&[req("COFINSAliq", &COFINS_RATE), req("COFINSOutr", &COFINS_OTHER)]);
+
+static IMPOSTO: Content = Content::Sequence(&[req("ICMS", &ICMS), req("PIS", &PIS), req("COFINS", &COFINS)]);
+
+static DET: Content = Content::Sequence(&[req("prod", &PROD), req("imposto", &IMPOSTO)]);
+
+static ICMS_TOT: Content = Content::Sequence(&[
+    req("vBC", &DEC_1302),
+    req("vICMS", &DEC_1302),
+    req("vICMSDeson", &DEC_1302),
+    req("vFCP", &DEC_1302),
+    req("vBCST", &DEC_1302),
+    req("vST", &DEC_1302),
+    req("vFCPST", &DEC_1302),
+    req("vFCPSTRet", &DEC_1302),
+    req("vProd", &DEC_1302),
+    req("vFrete", &DEC_1302),
+    req("vSeg", &DEC_1302),
+    req("vDesc", &DEC_1302),
+    req("vII", &DEC_1302),
+    req("vIPI", &DEC_1302),
+    req("vIPIDevol", &DEC_1302),
+    req("vPIS", &DEC_1302),
+    req("vCOFINS", &DEC_1302),
+    req("vOutro", &DEC_1302),
+    req("vNF", &DEC_1302),
+]);
+
+static TOTAL: Content = Content::Sequence(&[req("ICMSTot", &ICMS_TOT)]);
+
+static TRANSP: Content = Content::Sequence(&[req("modFrete", &FREIGHT)]);
+
+static DET_PAG: Content = Content::Sequence(&[
+    req("tPag", &PAYMENT_TYPE),
+    opt("xPag", &TEXT_60),
+    req("vPag", &DEC_1302),
+]);
+
+static PAG: Content = Content::Sequence(&[el("detPag", 1, 100, &DET_PAG)]);
+
+static INF_ADIC: Content = Content::Sequence(&[opt("infCpl", &TEXT_5000)]);
+
+static INF_NFE: Content = Content::Sequence(&[
+    req("ide", &IDE),
+    req("emit", &EMIT),
+    req("dest", &DEST),
+    Particle { name: "det", min: 1, max: 990, attrs: &["nItem"], content: &DET },
+    req("total", &TOTAL),
+    req("transp", &TRANSP),
+    req("pag", &PAG),
+    opt("infAdic", &INF_ADIC),
+]);
+
+static NFE_CONTENT: Content = Content::Sequence(&[Particle {
+    name: "infNFe",
+    min: 1,
+    max: 1,
+    attrs: &["versao", "Id"],
+    content: &INF_NFE,
+}]);
+
+// Subconjunto do leiaute 4.00 cobrindo só os grupos que geramos; não substitui o XSD oficial
+pub static NFE_SCHEMA: Particle = Particle { name: "NFe", min: 1, max: 1, attrs: &["xmlns"], content: &NFE_CONTENT };
+
+#[derive(Default)]
+struct Totals {
+    base: Decimal,
+    icms: Decimal,
+    products: Decimal,
+    discount: Decimal,
+    other: Decimal,
+    pis: Decimal,
+    cofins: Decimal,
+}
+
+fn tax_group(name: &'static str, cst: &str, base: Decimal, rate_tag: &'static str, rate: Decimal, value_tag: &'static str, value: Decimal) -> Element {
+    Element::new(name)
+        .child(Element::leaf("CST", cst))
+        .child(Element::leaf("vBC", money(base)))
+        .child(Element::leaf(rate_tag, format!("{:.2}", rate)))
+        .child(Element::leaf(value_tag, money(value)))
+}
+
+fn build_item(
+    index: usize,
+    issuer: &FiscalIssuer,
+    sale: &Sale,
+    item: &SaleItem,
+    product: &Product,
+    totals: &mut Totals,
+) -> Element {
+    let regime_normal = issuer.tax_regime == 3;
+    let quantity = Decimal::from(item.quantity);
+    let unit_price = sale.to_base(item.unit_price);
+    let gross = round_cents(unit_price * quantity);
+    let discount = sale.to_base(item.discount);
+    let taxable = gross - discount;
+    // Impostos da venda cobrados por fora do preço entram como outras despesas
+    let other = if sale.prices_include_tax { Decimal::ZERO } else { sale.to_base(item.tax_amount) };
+
+    let prod = Element::new("prod")
+        .child(Element::leaf("cProd", &product.sku))
+        .child(Element::leaf("cEAN", "SEM GTIN"))
+        .child(Element::leaf("xProd", &product.name))
+        .child(Element::leaf("NCM", &product.ncm))
+        .child(Element::leaf("CFOP", &issuer.cfop))
+        .child(Element::leaf("uCom", "UN"))
+        .child(Element::leaf("qCom", format!("{:.4}", quantity)))
+        .child(Element::leaf("vUnCom", money(unit_price)))
+        .child(Element::leaf("vProd", money(gross)))
+        .child(Element::leaf("cEANTrib", "SEM GTIN"))
+        .child(Element::leaf("uTrib", "UN"))
+        .child(Element::leaf("qTrib", format!("{:.4}", quantity)))
+        .child(Element::leaf("vUnTrib", money(unit_price)))
+        .optional("vDesc", (discount > Decimal::ZERO).then(|| money(discount)))
+        .optional("vOutro", (other > Decimal::ZERO).then(|| money(other)))
+        .child(Element::leaf("indTot", "1"));
+
+    // Regime Normal destaca ICMS/PIS/COFINS; no Simples Nacional o recolhimento é pelo DAS
+    let (icms, pis, cofins) = if regime_normal {
+        let icms_value = round_cents(taxable * issuer.icms_rate / Decimal::ONE_HUNDRED);
+        let pis_value = round_cents(taxable * issuer.pis_rate / Decimal::ONE_HUNDRED);
+        let cofins_value = round_cents(taxable * issuer.cofins_rate / Decimal::ONE_HUNDRED);
+        totals.base += taxable;
+        totals.icms += icms_value;
+        totals.pis += pis_value;
+        totals.cofins += cofins_value;
+        (
+            Element::new("ICMS00")
+                .child(Element::leaf("orig", "0"))
+                .child(Element::leaf("CST", "00"))
+                .child(Element::leaf("modBC", "3"))
+                .child(Element::leaf("vBC", money(taxable)))
+                .child(Element::leaf("pICMS", format!("{:.2}", issuer.icms_rate)))
+                .child(Element::leaf("vICMS", money(icms_value))),
+            tax_group("PISAliq", "01", taxable, "pPIS", issuer.pis_rate, "vPIS", pis_value),
+            tax_group("COFINSAliq", "01", taxable, "pCOFINS", issuer.cofins_rate, "vCOFINS", cofins_value),
+        )
+    } else {
+        (
+            Element::new("ICMSSN102")
+                .child(Element::leaf("orig", "0"))
+                .child(Element::leaf("CSOSN", "102")),
+            tax_group("PISOutr", "49", Decimal::ZERO, "pPIS", Decimal::ZERO, "vPIS", Decimal::ZERO),
+            tax_group("COFINSOutr", "49", Decimal::ZERO, "pCOFINS", Decimal::ZERO, "vCOFINS", Decimal::ZERO),
+        )
+    };
+
+    totals.products += gross;
+    totals.discount += discount;
+    totals.other += other;
+
+    Element::new("det")
+        .attr("nItem", index + 1)
+        .child(prod)
+        .child(
+            Element::new("imposto")
+                .child(Element::new("ICMS").child(icms))
+                .child(Element::new("PIS").child(pis))
+                .child(Element::new("COFINS").child(cofins)),
+        )
+}
+
+// Monta a NF-e da venda e valida contra o esquema antes de devolver o XML
+pub fn generate(
+    issuer: &FiscalIssuer,
+    sale: &Sale,
+    customer: &Customer,
+    products: &[Product],
+    number: u64,
+    issued_at: DateTime<Utc>,
+) -> Result<NfeDocument, Vec<String>> {
+    let tax_id = customer.tax_id.as_ref()
+        .ok_or_else(|| vec![format!("Customer {} has no CPF/CNPJ", customer.id)])?;
+    let state = state_code(&issuer.address.state)
+        .ok_or_else(|| vec![format!("Invalid issuer state: {}", issuer.address.state)])?;
+
+    let brasilia = FixedOffset::west_opt(3 * 3600).unwrap();
+    let local_time = issued_at.with_timezone(&brasilia);
+    let random_code = format!("{:08}", sale.id.as_u128() % 100_000_000);
+
+    let key_without_digit = format!(
+        "{}{}{}55{:03}{:09}1{}",
+        state,
+        local_time.format("%y%m"),
+        issuer.cnpj.digits(),
+        issuer.series,
+        number,
+        random_code
+    );
+    let check_digit = access_key_digit(&key_without_digit);
+    let access_key = format!("{}{}", key_without_digit, check_digit);
+
+    let mut errors = Vec::new();
+    let mut totals = Totals::default();
+    let mut items = Vec::new();
+    for (index, item) in sale.items.iter().enumerate() {
+        match products.iter().find(|p| p.id == item.product_id) {
+            Some(product) => items.push(build_item(index, issuer, sale, item, product, &mut totals)),
+            None => errors.push(format!("Product {} not found", item.product_id)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let invoice_total = totals.products - totals.discount + totals.other;
+
+    let ide = Element::new("ide")
+        .child(Element::leaf("cUF", state))
+        .child(Element::leaf("cNF", &random_code))
+        .child(Element::leaf("natOp", "Venda de mercadoria"))
+        .child(Element::leaf("mod", "55"))
+        .child(Element::leaf("serie", issuer.series))
+        .child(Element::leaf("nNF", number))
+        .child(Element::leaf("dhEmi", local_time.format("%Y-%m-%dT%H:%M:%S%:z")))
+        .child(Element::leaf("tpNF", "1"))
+        .child(Element::leaf("idDest", "1"))
+        .child(Element::leaf("cMunFG", &issuer.address.city_code))
+        .child(Element::leaf("tpImp", "1"))
+        .child(Element::leaf("tpEmis", "1"))
+        .child(Element::leaf("cDV", check_digit))
+        .child(Element::leaf("tpAmb", issuer.environment))
+        .child(Element::leaf("finNFe", "1"))
+        .child(Element::leaf("indFinal", if matches!(tax_id, TaxId::Cpf(_)) { "1" } else { "0" }))
+        .child(Element::leaf("indPres", "2"))
+        .child(Element::leaf("procEmi", "0"))
+        .child(Element::leaf("verProc", concat!("ai-business-", env!("CARGO_PKG_VERSION"))));
+
+    let address = &issuer.address;
+    let emit = Element::new("emit")
+        .child(Element::leaf("CNPJ", issuer.cnpj.digits()))
+        .child(Element::leaf("xNome", &issuer.legal_name))
+        .optional("xFant", issuer.trade_name.as_ref())
+        .child(
+            Element::new("enderEmit")
+                .child(Element::leaf("xLgr", &address.street))
+                .child(Element::leaf("nro", &address.number))
+                .child(Element::leaf("xBairro", &address.district))
+                .child(Element::leaf("cMun", &address.city_code))
+                .child(Element::leaf("xMun", &address.city))
+                .child(Element::leaf("UF", &address.state))
+                .child(Element::leaf("CEP", &address.postal_code))
+                .child(Element::leaf("cPais", "1058"))
+                .child(Element::leaf("xPais", "Brasil"))
+                .optional("fone", address.phone.as_ref()),
+        )
+        .child(Element::leaf("IE", &issuer.state_registration))
+        .child(Element::leaf("CRT", issuer.tax_regime));
+
+    // Em homologação a SEFAZ exige este nome no destinatário
+    let recipient_name = if issuer.environment == 2 { HOMOLOGATION_NAME } else { customer.name.as_str() };
+    let dest = match tax_id {
+        TaxId::Cnpj(digits) => Element::new("dest").child(Element::leaf("CNPJ", digits)),
+        TaxId::Cpf(digits) => Element::new("dest").child(Element::leaf("CPF", digits)),
+    }
+    .child(Element::leaf("xNome", recipient_name))
+    .child(Element::leaf("indIEDest", "9"))
+    .optional("email", (!customer.email.is_empty()).then_some(&customer.email));
+
+    let icms_total = Element::new("ICMSTot").children(
+        [
+            ("vBC", totals.base),
+            ("vICMS", totals.icms),
+            ("vICMSDeson", Decimal::ZERO),
+            ("vFCP", Decimal::ZERO),
+            ("vBCST", Decimal::ZERO),
+            ("vST", Decimal::ZERO),
+            ("vFCPST", Decimal::ZERO),
+            ("vFCPSTRet", Decimal::ZERO),
+            ("vProd", totals.products),
+            ("vFrete", Decimal::ZERO),
+            ("vSeg", Decimal::ZERO),
+            ("vDesc", totals.discount),
+            ("vII", Decimal::ZERO),
+            ("vIPI", Decimal::ZERO),
+            ("vIPIDevol", Decimal::ZERO),
+            ("vPIS", totals.pis),
+            ("vCOFINS", totals.cofins),
+            ("vOutro", totals.other),
+            ("vNF", invoice_total),
+        ]
+        .into_iter()
+        .map(|(name, value)| Element::leaf(name, money(value))),
+    );
+
+    let (payment_type, payment_description) = payment_code(sale.payment_method);
+    let pag = Element::new("pag").child(
+        Element::new("detPag")
+            .child(Element::leaf("tPag", payment_type))
+            .optional("xPag", payment_description)
+            .child(Element::leaf("vPag", money(invoice_total))),
+    );
+
+    let root = Element::new("NFe").attr("xmlns", NFE_NAMESPACE).child(
+        Element::new("infNFe")
+            .attr("versao", LAYOUT_VERSION)
+            .attr("Id", format!("NFe{}", access_key))
+            .child(ide)
+            .child(emit)
+            .child(dest)
+            .children(items)
+            .child(Element::new("total").child(icms_total))
+            .child(Element::new("transp").child(Element::leaf("modFrete", "9")))
+            .child(pag)
+            .child(Element::new("infAdic").child(Element::leaf("infCpl", format!("Venda {}", sale.id)))),
+    );
+
+    let errors = xml::validate(&root, &NFE_SCHEMA);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(NfeDocument {
+        id: Uuid::new_v4(),
+        sale_id: sale.id,
+        series: issuer.series,
+        number,
+        access_key,
+        total: invoice_total,
+        xml: root.to_document(),
+        issued_at,
+    })
+}
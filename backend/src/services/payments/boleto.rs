@@ -0,0 +1,123 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+pub struct Boleto {
+    pub barcode: String,
+    pub digitable_line: String,
+}
+
+fn digits(value: &str) -> impl Iterator<Item = u32> + '_ {
+    value.chars().filter_map(|c| c.to_digit(10))
+}
+
+// DV dos campos da linha digitável: pesos 2,1 da direita, somando os algarismos do produto
+fn mod10(value: &str) -> u32 {
+    let sum: u32 = digits(value)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            let product = d * if i % 2 == 0 { 2 } else { 1 };
+            product / 10 + product % 10
+        })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+// DV geral do código de barras: pesos 2..9 da direita; 0, 1, 10 e 11 viram 1
+fn mod11(value: &str) -> u32 {
+    let sum: u32 = digits(value)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| d * (2 + (i as u32 % 8)))
+        .sum();
+    match 11 - sum % 11 {
+        0 | 1 | 10 | 11 => 1,
+        digit => digit,
+    }
+}
+
+// Fator de vencimento: dias desde 07/10/1997; ao passar de 9999 (22/02/2025) recomeça em 1000
+fn due_factor(due_date: NaiveDate) -> u32 {
+    let base = NaiveDate::from_ymd_opt(1997, 10, 7).unwrap();
+    let days = (due_date - base).num_days() as u32;
+    if days > 9999 { (days - 10000) % 9000 + 1000 } else { days }
+}
+
+// Código de barras (44) e linha digitável (47) no padrão FEBRABAN para cobrança em reais
+pub fn generate(bank_code: &str, due_date: NaiveDate, amount: Decimal, free_field: &str) -> Result<Boleto, String> {
+    if bank_code.len() != 3 || !bank_code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid bank code: {}", bank_code));
+    }
+    if free_field.len() != 25 || !free_field.chars().all(|c| c.is_ascii_digit()) {
+        return Err("Boleto free field must have 25 digits".to_string());
+    }
+    let cents = (amount * Decimal::ONE_HUNDRED).round();
+    if cents <= Decimal::ZERO || cents > Decimal::from(9_999_999_999u64) {
+        return Err(format!("Amount {:.2} cannot be charged by boleto", amount));
+    }
+
+    let due_and_amount = format!("{:04}{:0>10}", due_factor(due_date), cents.to_string());
+    let without_digit = format!("{}9{}{}", bank_code, due_and_amount, free_field);
+    let check_digit = mod11(&without_digit);
+    let barcode = format!("{}9{}{}{}", bank_code, check_digit, due_and_amount, free_field);
+
+    let field1 = format!("{}9{}", bank_code, &free_field[..5]);
+    let field2 = &free_field[5..15];
+    let field3 = &free_field[15..];
+    let digitable_line = format!(
+        "{}.{}{} {}.{}{} {}.{}{} {} {}",
+        &field1[..5], &field1[5..], mod10(&field1),
+        &field2[..5], &field2[5..], mod10(field2),
+        &field3[..5], &field3[5..], mod10(field3),
+        check_digit,
+        due_and_amount
+    );
+
+    Ok(Boleto { barcode, digitable_line })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{due_factor, generate, mod10, mod11};
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    // Boleto de referência do Banco do Brasil: R$ 1,00 com vencimento em 31/12/2007
+    const BARCODE: &str = "00193373700000001000500940144816060680935031";
+    const DIGITABLE_LINE: &str = "00190.50095 40144.816069 06809.350314 3 37370000000100";
+
+    #[test]
+    fn check_digits_match_the_reference_boleto() {
+        assert_eq!(mod10("001905009"), 5);
+        assert_eq!(mod10("4014481606"), 9);
+        assert_eq!(mod10("0680935031"), 4);
+        assert_eq!(mod11(&format!("{}{}", &BARCODE[..4], &BARCODE[5..])), 3);
+    }
+
+    #[test]
+    fn generates_the_reference_barcode_and_digitable_line() {
+        let due_date = NaiveDate::from_ymd_opt(2007, 12, 31).unwrap();
+        let boleto = generate("001", due_date, dec!(1.00), "0500940144816060680935031").unwrap();
+        assert_eq!(boleto.barcode, BARCODE);
+        assert_eq!(boleto.digitable_line, DIGITABLE_LINE);
+    }
+
+    #[test]
+    fn due_factor_restarts_at_1000_after_9999() {
+        assert_eq!(due_factor(NaiveDate::from_ymd_opt(2007, 12, 31).unwrap()), 3737);
+        assert_eq!(due_factor(NaiveDate::from_ymd_opt(2025, 2, 21).unwrap()), 9999);
+        assert_eq!(due_factor(NaiveDate::from_ymd_opt(2025, 2, 22).unwrap()), 1000);
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        let due_date = NaiveDate::from_ymd_opt(2026, 1, 10).unwrap();
+        assert!(generate("01", due_date, dec!(1), &"0".repeat(25)).is_err());
+        assert!(generate("001", due_date, dec!(1), &"0".repeat(24)).is_err());
+        assert!(generate("001", due_date, dec!(0), &"0".repeat(25)).is_err());
+    }
+}
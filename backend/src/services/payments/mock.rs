@@ -0,0 +1,136 @@
+use super::{boleto, pix, sign_webhook, verify_webhook, PaymentGateway, SIGNATURE_HEADER};
+use crate::models::{
+    PaymentEvent, PaymentInstructions, PaymentIntent, PaymentIntentStatus, PaymentMethod, PaymentSimulation, Sale,
+};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+// Código fora da faixa FEBRABAN para que boletos de teste nunca sejam pagáveis
+const MOCK_BANK_CODE: &str = "999";
+const PIX_EXPIRATION_MINUTES: i64 = 60;
+
+// Gateway local para desenvolvimento: gera instruções reais de PIX/boleto e envia
+// webhooks assinados para a própria API quando um pagamento é simulado
+pub struct MockGateway {
+    pub secret: String,
+    pub webhook_url: String,
+    pub pix_key: String,
+    pub merchant_name: String,
+    pub merchant_city: String,
+    pub boleto_days: i64,
+    client: reqwest::Client,
+}
+
+impl MockGateway {
+    pub fn new(
+        secret: &str,
+        webhook_url: &str,
+        pix_key: &str,
+        merchant_name: &str,
+        merchant_city: &str,
+        boleto_days: i64,
+    ) -> Self {
+        MockGateway {
+            secret: secret.to_string(),
+            webhook_url: webhook_url.to_string(),
+            pix_key: pix_key.to_string(),
+            merchant_name: merchant_name.to_string(),
+            merchant_city: merchant_city.to_string(),
+            boleto_days,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn instructions(&self, sale: &Sale, intent_id: Uuid, method: PaymentMethod) -> Result<Option<PaymentInstructions>, String> {
+        let amount = sale.net_amount()?.amount;
+        if matches!(method, PaymentMethod::Pix | PaymentMethod::Boleto) && sale.currency().code() != "BRL" {
+            return Err(format!("{} payments must be in BRL", method.as_str()));
+        }
+
+        match method {
+            PaymentMethod::Pix => {
+                let txid = intent_id.simple().to_string()[..25].to_string();
+                Ok(Some(PaymentInstructions::Pix {
+                    br_code: pix::br_code(&self.pix_key, &self.merchant_name, &self.merchant_city, amount, &txid)?,
+                    txid,
+                    expires_at: Utc::now() + Duration::minutes(PIX_EXPIRATION_MINUTES),
+                }))
+            }
+            PaymentMethod::Boleto => {
+                let due_date = (Utc::now() + Duration::days(self.boleto_days)).date_naive();
+                // "Nosso número" derivado do id do pagamento
+                let free_field = format!("{:025}", intent_id.as_u128() % 10u128.pow(25));
+                let boleto = boleto::generate(MOCK_BANK_CODE, due_date, amount, &free_field)?;
+                Ok(Some(PaymentInstructions::Boleto {
+                    barcode: boleto.barcode,
+                    digitable_line: boleto.digitable_line,
+                    due_date,
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl PaymentGateway for MockGateway {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn create_intent(&self, sale: &Sale, method: PaymentMethod) -> Result<PaymentIntent, String> {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        Ok(PaymentIntent {
+            id,
+            sale_id: sale.id,
+            gateway: self.name().to_string(),
+            method,
+            amount: sale.net_amount()?,
+            status: PaymentIntentStatus::Pending,
+            instructions: self.instructions(sale, id, method)?,
+            failure_reason: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    fn parse_webhook(&self, signature: Option<&str>, body: &[u8]) -> Result<PaymentEvent, String> {
+        verify_webhook(&self.secret, signature, body)?;
+        serde_json::from_slice(body).map_err(|e| format!("Invalid webhook payload: {}", e))
+    }
+
+    fn simulate(&self, intent: &PaymentIntent, simulation: &PaymentSimulation) -> Result<PaymentEvent, String> {
+        if intent.status != PaymentIntentStatus::Pending {
+            return Err("Only pending payments can be simulated".to_string());
+        }
+        if simulation.status == PaymentIntentStatus::Pending {
+            return Err("Simulated status must be 'succeeded' or 'failed'".to_string());
+        }
+
+        let event = PaymentEvent {
+            id: Uuid::new_v4(),
+            intent_id: intent.id,
+            status: simulation.status,
+            failure_reason: simulation.failure_reason.clone(),
+            created_at: Utc::now(),
+        };
+        let body = serde_json::to_vec(&event).map_err(|e| e.to_string())?;
+        let request = self.client
+            .post(&self.webhook_url)
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, sign_webhook(&self.secret, &body))
+            .body(body);
+
+        // Entrega assíncrona, como faria um gateway de verdade
+        actix_web::rt::spawn(async move {
+            match request.send().await {
+                Ok(response) if response.status().is_success() => {}
+                Ok(response) => println!("⚠️  Mock webhook rejected with status {}", response.status()),
+                Err(err) => println!("⚠️  Mock webhook delivery failed: {}", err),
+            }
+        });
+
+        Ok(event)
+    }
+}
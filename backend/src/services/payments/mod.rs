@@ -0,0 +1,67 @@
+pub mod boleto;
+pub mod mock;
+pub mod pix;
+
+use crate::models::{PaymentEvent, PaymentIntent, PaymentMethod, PaymentSimulation, Sale};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+// Janela aceita entre a assinatura do webhook e o recebimento, em segundos
+const SIGNATURE_TOLERANCE: i64 = 300;
+
+pub trait PaymentGateway: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn create_intent(&self, sale: &Sale, method: PaymentMethod) -> Result<PaymentIntent, String>;
+
+    // Confere a assinatura do webhook e devolve o evento recebido
+    fn parse_webhook(&self, signature: Option<&str>, body: &[u8]) -> Result<PaymentEvent, String>;
+
+    // Só gateways de teste conseguem forçar a mudança de status de um pagamento
+    fn simulate(&self, _intent: &PaymentIntent, _simulation: &PaymentSimulation) -> Result<PaymentEvent, String> {
+        Err(format!("Gateway '{}' does not support simulated payments", self.name()))
+    }
+}
+
+fn signature_mac(secret: &str, timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+// Cabeçalho no formato `t=<unix>,v1=<hmac-sha256 hex de "t.body">`
+pub fn sign_webhook(secret: &str, body: &[u8]) -> String {
+    let timestamp = Utc::now().timestamp();
+    let signature = signature_mac(secret, timestamp, body).finalize().into_bytes();
+    format!("t={},v1={}", timestamp, hex::encode(signature))
+}
+
+pub fn verify_webhook(secret: &str, header: Option<&str>, body: &[u8]) -> Result<(), String> {
+    let header = header.ok_or_else(|| "Missing webhook signature".to_string())?;
+
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return Err("Malformed webhook signature".to_string());
+    };
+
+    if (Utc::now().timestamp() - timestamp).abs() > SIGNATURE_TOLERANCE {
+        return Err("Webhook signature has expired".to_string());
+    }
+
+    signature_mac(secret, timestamp, body)
+        .verify_slice(&signature)
+        .map_err(|_| "Invalid webhook signature".to_string())
+}
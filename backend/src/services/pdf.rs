@@ -0,0 +1,96 @@
+// Gerador mínimo de PDF de texto (Courier, A4), sem dependências externas
+
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 40;
+const FONT_SIZE: u32 = 9;
+const LEADING: u32 = 12;
+const LINES_PER_PAGE: usize = ((PAGE_HEIGHT - 2 * MARGIN) / LEADING) as usize;
+
+// Converte para WinAnsi (Latin-1 cobre os acentos do português) escapando o que o PDF exige
+fn encode_text(text: &str, out: &mut Vec<u8>) {
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                out.push(c as u8);
+            }
+            c if (c as u32) < 256 => out.push(c as u32 as u8),
+            _ => out.push(b'?'),
+        }
+    }
+}
+
+fn page_content(lines: &[String]) -> Vec<u8> {
+    let mut content = Vec::new();
+    content.extend_from_slice(
+        format!("BT\n/F1 {} Tf\n{} TL\n{} {} Td\n", FONT_SIZE, LEADING, MARGIN, PAGE_HEIGHT - MARGIN).as_bytes(),
+    );
+    for line in lines {
+        content.push(b'(');
+        encode_text(line, &mut content);
+        content.extend_from_slice(b") Tj T*\n");
+    }
+    content.extend_from_slice(b"ET\n");
+    content
+}
+
+pub fn render_text_pdf(lines: &[String]) -> Vec<u8> {
+    let pages: Vec<&[String]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(LINES_PER_PAGE).collect()
+    };
+
+    // 1: catálogo, 2: árvore de páginas, 3: fonte, depois pares (página, conteúdo)
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + i * 2).collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+
+    for (page, page_id) in pages.iter().zip(page_ids.iter()) {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH, PAGE_HEIGHT, page_id + 1
+            )
+            .into_bytes(),
+        );
+        let content = page_content(page);
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(&content);
+        stream.extend_from_slice(b"endstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = pdf.len();
+    pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+    pdf
+}
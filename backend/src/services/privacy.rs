@@ -0,0 +1,36 @@
+use crate::models::Customer;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+
+pub const ANONYMIZED_CUSTOMER: &str = "Anonymized customer";
+pub const ANONYMIZED_USER: &str = "Anonymized user";
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+// Identifica o titular no comprovante sem guardar o e-mail
+pub fn subject_fingerprint(email: &str) -> String {
+    sha256_hex(email.trim().to_lowercase().as_bytes())
+}
+
+// Único por titular, para não colidir com a regra de e-mail único; o domínio .invalid nunca recebe mensagens
+pub fn erased_email(subject_id: &str) -> String {
+    format!("erased+{}@anonymized.invalid", subject_id.replace('-', ""))
+}
+
+// Apaga os dados pessoais e mantém o que alimenta os relatórios (país, segmento, LTV, CLV)
+pub fn anonymize_customer(customer: &mut Customer, now: DateTime<Utc>) {
+    customer.name = ANONYMIZED_CUSTOMER.to_string();
+    customer.email = erased_email(&customer.id.to_string());
+    customer.phone = String::new();
+    customer.company = ANONYMIZED_CUSTOMER.to_string();
+    customer.tax_id = None;
+    customer.address = None;
+    customer.city = None;
+    // Campos personalizados e tags são texto livre e podem conter dados pessoais
+    customer.custom_fields.clear();
+    customer.tags.clear();
+    customer.anonymized_at = Some(now);
+    customer.updated_at = now;
+}
@@ -0,0 +1,229 @@
+use crate::models::money::{round_cents, Currency, ExchangeRates, Money};
+use crate::models::promotion::{AppliedPromotion, Promotion, PromotionKind, PromotionRedemption, SkippedPromotion};
+use crate::models::{Product, SaleItem};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::cmp::Reverse;
+use uuid::Uuid;
+
+pub struct PromotionContext<'a> {
+    pub customer_id: Uuid,
+    pub currency: &'a Currency,
+    pub rates: &'a ExchangeRates,
+    pub products: &'a [Product],
+    // Resgates que contam para os limites de uso
+    pub redemptions: &'a [PromotionRedemption],
+    pub now: DateTime<Utc>,
+}
+
+pub struct PromotionOutcome {
+    pub items: Vec<SaleItem>,
+    pub applied: Vec<AppliedPromotion>,
+    pub skipped: Vec<SkippedPromotion>,
+}
+
+impl PromotionOutcome {
+    fn discount(&self) -> Decimal {
+        self.applied.iter().map(|p| p.discount).sum()
+    }
+}
+
+fn targets(promotion: &Promotion, product_id: Uuid, products: &[Product]) -> bool {
+    if let PromotionKind::TierUpgrade { from_product_id, .. } = promotion.kind {
+        return product_id == from_product_id;
+    }
+    if promotion.product_ids.is_empty() && promotion.categories.is_empty() {
+        return true;
+    }
+    promotion.product_ids.contains(&product_id)
+        || products.iter().find(|p| p.id == product_id).is_some_and(|product| {
+            promotion.categories.iter().any(|c| c.eq_ignore_ascii_case(&product.category))
+        })
+}
+
+pub fn check_eligibility(promotion: &Promotion, items: &[SaleItem], ctx: &PromotionContext) -> Result<(), String> {
+    if !promotion.active {
+        return Err("Promotion is no longer active".to_string());
+    }
+    if promotion.starts_at.is_some_and(|starts_at| ctx.now < starts_at) {
+        return Err("Promotion has not started yet".to_string());
+    }
+    if promotion.ends_at.is_some_and(|ends_at| ctx.now >= ends_at) {
+        return Err("Promotion has expired".to_string());
+    }
+
+    let used: Vec<&PromotionRedemption> = ctx.redemptions.iter().filter(|r| r.promotion_id == promotion.id).collect();
+    if promotion.max_redemptions.is_some_and(|max| used.len() >= max as usize) {
+        return Err("Promotion usage limit reached".to_string());
+    }
+    let used_by_customer = used.iter().filter(|r| r.customer_id == ctx.customer_id).count();
+    if promotion.max_per_customer.is_some_and(|max| used_by_customer >= max as usize) {
+        return Err("Customer reached the usage limit for this promotion".to_string());
+    }
+
+    if !items.iter().any(|item| targets(promotion, item.product_id, ctx.products)) {
+        return Err("No item in the sale is eligible for this promotion".to_string());
+    }
+    Ok(())
+}
+
+fn convert(money: &Money, ctx: &PromotionContext) -> Result<Decimal, String> {
+    ctx.rates
+        .convert(money, ctx.currency)
+        .map(|m| m.amount)
+        .ok_or_else(|| format!("Cannot convert {} to {}", money.currency, ctx.currency))
+}
+
+fn add_discount(item: &mut SaleItem, discount: Decimal) {
+    item.discount += discount;
+    item.line_total -= discount;
+}
+
+// Aplica a promoção sobre o que ainda resta de cada linha e devolve o desconto concedido
+fn apply(promotion: &Promotion, items: &mut [SaleItem], ctx: &PromotionContext) -> Result<Decimal, String> {
+    let eligible: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| targets(promotion, item.product_id, ctx.products))
+        .map(|(index, _)| index)
+        .collect();
+    let mut total = Decimal::ZERO;
+
+    match &promotion.kind {
+        PromotionKind::Percentage { percent } => {
+            for &index in &eligible {
+                let discount = round_cents(items[index].line_total * percent / Decimal::ONE_HUNDRED);
+                add_discount(&mut items[index], discount);
+                total += discount;
+            }
+        }
+        PromotionKind::FixedAmount { amount } => {
+            let base: Decimal = eligible.iter().map(|&index| items[index].line_total).sum();
+            let amount = convert(amount, ctx)?.min(base);
+            // Rateio proporcional; o arredondamento fica com a última linha
+            let mut remaining = amount;
+            for (position, &index) in eligible.iter().enumerate() {
+                let share = if position + 1 == eligible.len() {
+                    remaining
+                } else {
+                    round_cents(amount * items[index].line_total / base)
+                };
+                let discount = share.min(items[index].line_total);
+                add_discount(&mut items[index], discount);
+                remaining -= discount;
+                total += discount;
+            }
+        }
+        PromotionKind::BuyXGetY { buy, get } => {
+            for &index in &eligible {
+                let item = &mut items[index];
+                let free = item.quantity / (buy + get) * get;
+                let discount = (item.unit_price * Decimal::from(free)).min(item.line_total);
+                add_discount(item, discount);
+                total += discount;
+            }
+        }
+        PromotionKind::TierUpgrade { to_product_id, .. } => {
+            let target = ctx.products
+                .iter()
+                .find(|p| p.id == *to_product_id)
+                .ok_or_else(|| format!("Upgrade product {} not found", to_product_id))?;
+            let price = convert(&target.price, ctx)?;
+            for &index in &eligible {
+                let item = &mut items[index];
+                if price <= item.unit_price {
+                    continue;
+                }
+                // O total da linha não muda: a diferença de preço entra como desconto
+                let discount = (price - item.unit_price) * Decimal::from(item.quantity);
+                item.product_id = target.id;
+                item.unit_price = price;
+                item.discount += discount;
+                total += discount;
+            }
+        }
+    }
+
+    if total.is_zero() {
+        return Err("Promotion gives no discount for this sale".to_string());
+    }
+    Ok(total)
+}
+
+// Aplica as promoções em sequência; as que não rendem desconto ficam de fora
+fn apply_all(selection: &[&Promotion], items: &[SaleItem], ctx: &PromotionContext) -> PromotionOutcome {
+    let mut outcome = PromotionOutcome { items: items.to_vec(), applied: Vec::new(), skipped: Vec::new() };
+    for promotion in selection {
+        let mut attempt = outcome.items.clone();
+        match apply(promotion, &mut attempt, ctx) {
+            Ok(discount) => {
+                outcome.items = attempt;
+                outcome.applied.push(AppliedPromotion {
+                    promotion_id: promotion.id,
+                    code: promotion.code.clone(),
+                    name: promotion.name.clone(),
+                    discount,
+                });
+            }
+            Err(reason) => outcome.skipped.push(SkippedPromotion {
+                promotion_id: promotion.id,
+                code: promotion.code.clone(),
+                reason,
+            }),
+        }
+    }
+    outcome
+}
+
+// Cupons informados precisam ser válidos; promoções automáticas entram só quando elegíveis.
+// Entre todas as cumulativas juntas e cada não cumulativa sozinha, vale a de maior desconto.
+pub fn apply_promotions(
+    promotions: &[Promotion],
+    coupon_codes: &[String],
+    items: &[SaleItem],
+    ctx: &PromotionContext,
+) -> Result<PromotionOutcome, String> {
+    let mut candidates: Vec<&Promotion> = Vec::new();
+    for code in coupon_codes {
+        let code = code.trim().to_uppercase();
+        let Some(promotion) = promotions.iter().find(|p| p.code.as_deref() == Some(code.as_str())) else {
+            return Err(format!("Unknown coupon code '{}'", code));
+        };
+        check_eligibility(promotion, items, ctx).map_err(|err| format!("Coupon '{}': {}", code, err))?;
+        if !candidates.iter().any(|c| c.id == promotion.id) {
+            candidates.push(promotion);
+        }
+    }
+    candidates.extend(
+        promotions
+            .iter()
+            .filter(|p| p.code.is_none() && check_eligibility(p, items, ctx).is_ok()),
+    );
+    // Upgrades primeiro, para que os descontos incidam sobre o produto final
+    candidates.sort_by_key(|p| (!matches!(p.kind, PromotionKind::TierUpgrade { .. }), Reverse(p.priority)));
+
+    let stackable: Vec<&Promotion> = candidates.iter().copied().filter(|p| p.stackable).collect();
+    let mut best = apply_all(&stackable, items, ctx);
+    let mut failed: Vec<SkippedPromotion> = Vec::new();
+    for promotion in candidates.iter().filter(|p| !p.stackable) {
+        let mut alone = apply_all(&[promotion], items, ctx);
+        if alone.discount() > best.discount() {
+            std::mem::swap(&mut best, &mut alone);
+        }
+        failed.append(&mut alone.skipped);
+    }
+    best.skipped.append(&mut failed);
+
+    for promotion in &candidates {
+        let considered = best.applied.iter().any(|a| a.promotion_id == promotion.id)
+            || best.skipped.iter().any(|s| s.promotion_id == promotion.id);
+        if !considered {
+            best.skipped.push(SkippedPromotion {
+                promotion_id: promotion.id,
+                code: promotion.code.clone(),
+                reason: "Cannot be combined with the promotions applied to this sale".to_string(),
+            });
+        }
+    }
+    Ok(best)
+}
@@ -0,0 +1,166 @@
+use crate::models::money::round_cents;
+use crate::models::segment::{CustomerRfm, ScoreRange, SegmentDefinition, SegmentSummary};
+use crate::models::{Customer, Sale};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+// Segmento de quem não casa com nenhuma definição
+pub const FALLBACK_SEGMENT: &str = "Others";
+
+fn definition(name: &str, description: &str, recency: ScoreRange, frequency: ScoreRange, monetary: ScoreRange) -> SegmentDefinition {
+    SegmentDefinition {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        recency,
+        frequency,
+        monetary,
+    }
+}
+
+pub fn default_definitions() -> Vec<SegmentDefinition> {
+    let any = ScoreRange::default();
+    vec![
+        definition("Champions", "Compraram há pouco, com frequência e alto valor", ScoreRange::new(4, 5), ScoreRange::new(4, 5), ScoreRange::new(4, 5)),
+        definition("Loyal Customers", "Compram com regularidade", ScoreRange::new(3, 5), ScoreRange::new(3, 5), any),
+        definition("New Customers", "Primeira compra recente", ScoreRange::new(4, 5), ScoreRange::new(1, 1), any),
+        definition("Potential Loyalists", "Recentes, ainda com poucas compras", ScoreRange::new(4, 5), ScoreRange::new(1, 3), any),
+        definition("Cannot Lose Them", "Grandes clientes que pararam de comprar", ScoreRange::new(1, 1), ScoreRange::new(4, 5), ScoreRange::new(4, 5)),
+        definition("At Risk", "Compravam com frequência, mas não voltam há tempo", ScoreRange::new(1, 2), ScoreRange::new(3, 5), any),
+        definition("Need Attention", "Recência média e poucas compras", ScoreRange::new(3, 3), ScoreRange::new(1, 2), any),
+        definition("Hibernating", "Poucas compras e sem comprar há muito tempo", ScoreRange::new(1, 2), ScoreRange::new(1, 2), any),
+    ]
+}
+
+pub fn validate_definitions(definitions: &[SegmentDefinition]) -> Result<(), String> {
+    if definitions.is_empty() {
+        return Err("At least one segment definition is required".to_string());
+    }
+    let mut names = HashSet::new();
+    for definition in definitions {
+        let name = definition.name.trim();
+        if name.is_empty() {
+            return Err("Segment name is required".to_string());
+        }
+        if name.eq_ignore_ascii_case(FALLBACK_SEGMENT) {
+            return Err(format!("'{}' is reserved for customers that match no segment", FALLBACK_SEGMENT));
+        }
+        if !names.insert(name.to_lowercase()) {
+            return Err(format!("Duplicate segment '{}'", name));
+        }
+        for (label, range) in [("recency", definition.recency), ("frequency", definition.frequency), ("monetary", definition.monetary)] {
+            if range.min < 1 || range.max > 5 || range.min > range.max {
+                return Err(format!("Segment '{}': {} range must be within 1..5 with min <= max", name, label));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Nota de 1 a 5 pela posição entre os clientes; empates recebem a mesma nota
+fn quintile(values: &[Decimal], value: Decimal, higher_is_better: bool) -> u8 {
+    let at_or_below = values
+        .iter()
+        .filter(|v| if higher_is_better { **v <= value } else { **v >= value })
+        .count();
+    let total = values.len().max(1);
+    let score = (5 * at_or_below).div_ceil(total);
+    u8::try_from(score).unwrap_or(5).clamp(1, 5)
+}
+
+// Pontua os clientes com ao menos uma venda capturada até `as_of`
+pub fn score_customers(
+    customers: &[Customer],
+    sales: &[Sale],
+    definitions: &[SegmentDefinition],
+    as_of: DateTime<Utc>,
+) -> Vec<CustomerRfm> {
+    let mut stats: HashMap<Uuid, (DateTime<Utc>, usize, Decimal)> = HashMap::new();
+    for sale in sales.iter().filter(|s| s.status.is_captured() && s.created_at <= as_of) {
+        let Ok(net) = sale.net_amount() else { continue };
+        let entry = stats.entry(sale.customer_id).or_insert((sale.created_at, 0, Decimal::ZERO));
+        entry.0 = entry.0.max(sale.created_at);
+        entry.1 += 1;
+        entry.2 += sale.to_base(net.amount);
+    }
+
+    let scored: Vec<_> = customers
+        .iter()
+        .filter_map(|c| stats.get(&c.id).map(|s| (c, *s)))
+        .collect();
+    let recencies: Vec<Decimal> = scored.iter().map(|(_, s)| Decimal::from((as_of - s.0).num_days())).collect();
+    let frequencies: Vec<Decimal> = scored.iter().map(|(_, s)| Decimal::from(s.1)).collect();
+    let monetaries: Vec<Decimal> = scored.iter().map(|(_, s)| s.2).collect();
+
+    scored
+        .iter()
+        .map(|(customer, (last_purchase_at, frequency, monetary))| {
+            let recency_days = (as_of - *last_purchase_at).num_days();
+            let r = quintile(&recencies, Decimal::from(recency_days), false);
+            let f = quintile(&frequencies, Decimal::from(*frequency), true);
+            let m = quintile(&monetaries, *monetary, true);
+            let segment = definitions
+                .iter()
+                .find(|d| d.matches(r, f, m))
+                .map(|d| d.name.clone())
+                .unwrap_or_else(|| FALLBACK_SEGMENT.to_string());
+
+            CustomerRfm {
+                customer_id: customer.id,
+                name: customer.name.clone(),
+                last_purchase_at: *last_purchase_at,
+                recency_days,
+                frequency: *frequency,
+                monetary: round_cents(*monetary),
+                r,
+                f,
+                m,
+                segment,
+            }
+        })
+        .collect()
+}
+
+// Tamanho e receita por segmento; sem janela, a receita é o monetário acumulado
+pub fn summarize(
+    definitions: &[SegmentDefinition],
+    scores: &[CustomerRfm],
+    sales: &[Sale],
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Vec<SegmentSummary> {
+    let revenue_of = |customer: &CustomerRfm| match window {
+        Some((from, to)) => sales
+            .iter()
+            .filter(|s| s.customer_id == customer.customer_id && s.status.is_captured())
+            .filter(|s| s.created_at >= from && s.created_at < to)
+            .filter_map(|s| s.net_amount().ok().map(|net| s.to_base(net.amount)))
+            .sum(),
+        None => customer.monetary,
+    };
+
+    let mut names: Vec<&str> = definitions.iter().map(|d| d.name.as_str()).collect();
+    if scores.iter().any(|s| s.segment == FALLBACK_SEGMENT) {
+        names.push(FALLBACK_SEGMENT);
+    }
+
+    names
+        .into_iter()
+        .map(|name| {
+            let members: Vec<_> = scores.iter().filter(|s| s.segment == name).collect();
+            let revenue: Decimal = members.iter().map(|c| revenue_of(c)).sum();
+            let count = Decimal::from(members.len());
+            SegmentSummary {
+                segment: name.to_string(),
+                customers: members.len(),
+                share: if scores.is_empty() {
+                    Decimal::ZERO
+                } else {
+                    round_cents(count * Decimal::ONE_HUNDRED / Decimal::from(scores.len()))
+                },
+                revenue: round_cents(revenue),
+                average_revenue: if members.is_empty() { Decimal::ZERO } else { round_cents(revenue / count) },
+            }
+        })
+        .collect()
+}
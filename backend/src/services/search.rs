@@ -0,0 +1,155 @@
+use crate::models::search::{SearchEntity, SearchHit};
+use crate::services::dedup::fold_accent;
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+// Bônus para quando o título começa com a busca inteira
+const TITLE_PREFIX_BONUS: f64 = 2.0;
+
+pub struct IndexedField {
+    pub name: &'static str,
+    // Peso do campo na relevância (nome e SKU valem mais que descrição)
+    pub weight: f64,
+    pub text: String,
+}
+
+pub struct SearchDocument {
+    pub entity: SearchEntity,
+    pub id: Uuid,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub fields: Vec<IndexedField>,
+}
+
+// Minúsculas e sem acentos; pontuação vira separador ("João" -> "joao", "ENT-001" -> "ent 001")
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(fold_accent)
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect()
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    normalize(text).split_whitespace().map(str::to_string).collect()
+}
+
+// Termo exato vale 1; prefixo vale mais quanto mais do termo indexado ele cobre
+fn term_match(query_term: &str, indexed: &str) -> f64 {
+    if query_term == indexed {
+        return 1.0;
+    }
+    let covered = query_term.chars().count() as f64 / indexed.chars().count() as f64;
+    0.5 + 0.5 * covered
+}
+
+// Índice invertido em memória; a ordem do BTreeMap permite buscar por prefixo
+pub struct SearchIndex {
+    documents: Vec<SearchDocument>,
+    // Termo -> (documento, campo)
+    terms: BTreeMap<String, Vec<(usize, usize)>>,
+    pub built_at: DateTime<Utc>,
+}
+
+impl SearchIndex {
+    pub fn build(documents: Vec<SearchDocument>, now: DateTime<Utc>) -> Self {
+        let mut terms: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+        for (doc, document) in documents.iter().enumerate() {
+            for (field, indexed) in document.fields.iter().enumerate() {
+                for term in tokenize(&indexed.text) {
+                    let postings = terms.entry(term).or_default();
+                    if !postings.contains(&(doc, field)) {
+                        postings.push((doc, field));
+                    }
+                }
+            }
+        }
+        SearchIndex { documents, terms, built_at: now }
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    // Todos os termos precisam casar (exato ou prefixo); a nota soma o melhor campo de cada termo
+    pub fn search(&self, query: &str, entities: &[SearchEntity], limit: usize) -> (Vec<SearchHit>, usize) {
+        let mut query_terms: Vec<String> = Vec::new();
+        for term in tokenize(query) {
+            if !query_terms.contains(&term) {
+                query_terms.push(term);
+            }
+        }
+        if query_terms.is_empty() {
+            return (Vec::new(), 0);
+        }
+
+        // Documento -> (nota, campos casados, termos casados)
+        let mut scores: HashMap<usize, (f64, Vec<&'static str>, usize)> = HashMap::new();
+        for query_term in &query_terms {
+            // Melhor nota do termo no documento e todos os campos em que ele aparece
+            let mut best: HashMap<usize, (f64, Vec<&'static str>)> = HashMap::new();
+            for (indexed, postings) in self.terms.range(query_term.clone()..).take_while(|(t, _)| t.starts_with(query_term.as_str())) {
+                let matched = term_match(query_term, indexed);
+                for (doc, field) in postings {
+                    let field = &self.documents[*doc].fields[*field];
+                    let entry = best.entry(*doc).or_insert((0.0, Vec::new()));
+                    entry.0 = entry.0.max(field.weight * matched);
+                    if !entry.1.contains(&field.name) {
+                        entry.1.push(field.name);
+                    }
+                }
+            }
+            for (doc, (score, fields)) in best {
+                let entry = scores.entry(doc).or_insert((0.0, Vec::new(), 0));
+                entry.0 += score;
+                for field in fields {
+                    if !entry.1.contains(&field) {
+                        entry.1.push(field);
+                    }
+                }
+                entry.2 += 1;
+            }
+        }
+
+        let phrase = query_terms.join(" ");
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .filter(|(_, (_, _, matched))| *matched == query_terms.len())
+            .map(|(doc, (score, fields, _))| (&self.documents[doc], score, fields))
+            .filter(|(document, _, _)| entities.contains(&document.entity))
+            .map(|(document, score, fields)| {
+                let bonus = if normalize(&document.title).split_whitespace().collect::<Vec<_>>().join(" ").starts_with(&phrase) {
+                    TITLE_PREFIX_BONUS
+                } else {
+                    0.0
+                };
+                SearchHit {
+                    entity: document.entity,
+                    id: document.id,
+                    title: document.title.clone(),
+                    subtitle: document.subtitle.clone(),
+                    score: ((score + bonus) * 1000.0).round() / 1000.0,
+                    matched_fields: fields.into_iter().map(str::to_string).collect(),
+                }
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal).then_with(|| a.title.cmp(&b.title))
+        });
+        let total = hits.len();
+        hits.truncate(limit);
+        (hits, total)
+    }
+}
+
+// Entidades que cada perfil pode pesquisar; vendas (dados financeiros) só para administradores
+pub fn allowed_entities(role: &str) -> &'static [SearchEntity] {
+    match role {
+        "admin" => &SearchEntity::ALL,
+        "user" => &[SearchEntity::Customer, SearchEntity::Product],
+        _ => &[],
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+pub type Values<'a> = HashMap<&'a str, String>;
+
+// Substitui `{{campo}}`, `{{campo:20}}` (alinhado à esquerda) e `{{campo:>12}}` (à direita)
+fn render_line(line: &str, values: &Values) -> String {
+    let mut out = String::new();
+    let mut rest = line;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let tag = &rest[start + 2..start + end];
+        let (name, width) = match tag.split_once(':') {
+            Some((name, width)) => (name.trim(), Some(width.trim())),
+            None => (tag.trim(), None),
+        };
+        let value = values.get(name).map(String::as_str).unwrap_or("");
+
+        match width {
+            Some(width) if width.starts_with('>') => {
+                let width = width[1..].parse().unwrap_or(0);
+                out.push_str(&format!("{:>width$}", value, width = width));
+            }
+            Some(width) => {
+                let width = width.parse().unwrap_or(0);
+                let value: String = value.chars().take(width).collect();
+                out.push_str(&format!("{:<width$}", value, width = width));
+            }
+            None => out.push_str(value),
+        }
+        rest = &rest[start + end + 2..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+// Blocos `{{#nome}}` ... `{{/nome}}` se repetem para cada linha em `sections[nome]`
+pub fn render(template: &str, values: &Values, sections: &HashMap<&str, Vec<Values>>) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut lines = template.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed.strip_prefix("{{#").and_then(|t| t.strip_suffix("}}")) {
+            let closing = format!("{{{{/{}}}}}", name);
+            let block: Vec<&str> = lines.by_ref().take_while(|l| l.trim() != closing).collect();
+
+            for row in sections.get(name).map(Vec::as_slice).unwrap_or_default() {
+                let mut merged = values.clone();
+                merged.extend(row.iter().map(|(k, v)| (*k, v.clone())));
+                output.extend(block.iter().map(|l| render_line(l, &merged)));
+            }
+        } else {
+            output.push(render_line(line, values));
+        }
+    }
+
+    output
+}
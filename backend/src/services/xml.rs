@@ -0,0 +1,191 @@
+use chrono::DateTime;
+
+// Árvore XML simples, suficiente para gerar e validar documentos fiscais
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub name: &'static str,
+    pub attrs: Vec<(&'static str, String)>,
+    pub text: Option<String>,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn new(name: &'static str) -> Self {
+        Element { name, attrs: Vec::new(), text: None, children: Vec::new() }
+    }
+
+    pub fn leaf(name: &'static str, text: impl ToString) -> Self {
+        Element { text: Some(text.to_string()), ..Element::new(name) }
+    }
+
+    pub fn attr(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.attrs.push((name, value.to_string()));
+        self
+    }
+
+    pub fn child(mut self, child: Element) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn children(mut self, children: impl IntoIterator<Item = Element>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    // Filho opcional: só entra no documento quando há valor
+    pub fn optional(self, name: &'static str, value: Option<impl ToString>) -> Self {
+        match value {
+            Some(value) => self.child(Element::leaf(name, value)),
+            None => self,
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(self.name);
+        for (name, value) in &self.attrs {
+            out.push_str(&format!(" {}=\"{}\"", name, escape(value)));
+        }
+        out.push('>');
+        if let Some(text) = &self.text {
+            out.push_str(&escape(text));
+        }
+        for child in &self.children {
+            child.write(out);
+        }
+        out.push_str(&format!("</{}>", self.name));
+    }
+
+    pub fn to_document(&self) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+        self.write(&mut out);
+        out
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Subconjunto de XSD: tipos simples e sequências/escolhas de elementos
+#[derive(Debug)]
+pub enum SimpleType {
+    Digits(usize, usize),
+    Text(usize, usize),
+    Enum(&'static [&'static str]),
+    // Inteiros sem zero à esquerda e casas decimais opcionais (ex.: TDec_1302)
+    Decimal { integer: usize, min_fraction: usize, max_fraction: usize },
+    // AAAA-MM-DDThh:mm:ss±hh:mm
+    DateTimeOffset,
+    Custom(fn(&str) -> bool),
+}
+
+impl SimpleType {
+    fn accepts(&self, value: &str) -> bool {
+        match self {
+            SimpleType::Digits(min, max) => {
+                (*min..=*max).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit())
+            }
+            SimpleType::Text(min, max) => {
+                (*min..=*max).contains(&value.chars().count()) && value.trim() == value
+            }
+            SimpleType::Enum(options) => options.contains(&value),
+            SimpleType::Decimal { integer, min_fraction, max_fraction } => {
+                let (int_part, fraction) = match value.split_once('.') {
+                    Some((int_part, fraction)) => (int_part, Some(fraction)),
+                    None => (value, None),
+                };
+                let int_ok = !int_part.is_empty()
+                    && int_part.len() <= *integer
+                    && int_part.chars().all(|c| c.is_ascii_digit())
+                    && (int_part == "0" || !int_part.starts_with('0'));
+                let fraction_ok = fraction.is_none_or(|f| {
+                    (*min_fraction..=*max_fraction).contains(&f.len()) && f.chars().all(|c| c.is_ascii_digit())
+                });
+                int_ok && fraction_ok
+            }
+            SimpleType::DateTimeOffset => DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%:z").is_ok(),
+            SimpleType::Custom(check) => check(value),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Content {
+    Simple(SimpleType),
+    Sequence(&'static [Particle]),
+    Choice(&'static [Particle]),
+}
+
+#[derive(Debug)]
+pub struct Particle {
+    pub name: &'static str,
+    pub min: usize,
+    pub max: usize,
+    pub attrs: &'static [&'static str],
+    pub content: &'static Content,
+}
+
+fn validate_element(element: &Element, particle: &Particle, path: &str, errors: &mut Vec<String>) {
+    let path = format!("{}/{}", path, element.name);
+
+    for attr in particle.attrs {
+        if !element.attrs.iter().any(|(name, _)| name == attr) {
+            errors.push(format!("{}: missing attribute '{}'", path, attr));
+        }
+    }
+
+    match particle.content {
+        Content::Simple(simple) => {
+            if !element.children.is_empty() {
+                errors.push(format!("{}: element must not have children", path));
+            }
+            match &element.text {
+                Some(text) if simple.accepts(text) => {}
+                Some(text) => errors.push(format!("{}: invalid value '{}'", path, text)),
+                None => errors.push(format!("{}: value is required", path)),
+            }
+        }
+        Content::Sequence(particles) => {
+            let mut children = element.children.iter().peekable();
+            for expected in particles.iter() {
+                let mut count = 0;
+                while count < expected.max && children.peek().is_some_and(|c| c.name == expected.name) {
+                    validate_element(children.next().unwrap(), expected, &path, errors);
+                    count += 1;
+                }
+                if count < expected.min {
+                    errors.push(format!("{}: missing element '{}'", path, expected.name));
+                }
+            }
+            for unexpected in children {
+                errors.push(format!("{}: unexpected element '{}'", path, unexpected.name));
+            }
+        }
+        Content::Choice(particles) => match element.children.as_slice() {
+            [child] => match particles.iter().find(|p| p.name == child.name) {
+                Some(expected) => validate_element(child, expected, &path, errors),
+                None => errors.push(format!("{}: unexpected element '{}'", path, child.name)),
+            },
+            _ => errors.push(format!("{}: expected exactly one of {:?}", path,
+                particles.iter().map(|p| p.name).collect::<Vec<_>>())),
+        },
+    }
+}
+
+// Valida a árvore contra o esquema e devolve todas as violações encontradas
+pub fn validate(root: &Element, schema: &Particle) -> Vec<String> {
+    let mut errors = Vec::new();
+    if root.name != schema.name {
+        errors.push(format!("expected root element '{}', found '{}'", schema.name, root.name));
+    } else {
+        validate_element(root, schema, "", &mut errors);
+    }
+    errors
+}